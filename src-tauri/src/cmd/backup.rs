@@ -1,6 +1,6 @@
 use super::CmdResult;
 use crate::{cmd::StringifyErr as _, feat};
-use feat::LocalBackupFile;
+use feat::{BackupPreview, BackupPruneReport, BackupSource, LocalBackupFile, RestoreComponentKind, RestorePlan};
 use smartstring::alias::String;
 
 /// Create a local backup
@@ -23,8 +23,8 @@ pub async fn delete_local_backup(filename: String) -> CmdResult<()> {
 
 /// Restore local backup
 #[tauri::command]
-pub async fn restore_local_backup(filename: String) -> CmdResult<()> {
-    feat::restore_local_backup(filename).await.stringify_err()
+pub async fn restore_local_backup(filename: String, passphrase: Option<String>) -> CmdResult<()> {
+    feat::restore_local_backup(filename, passphrase).await.stringify_err()
 }
 
 /// Import local backup into the app's backup directory
@@ -38,3 +38,72 @@ pub async fn import_local_backup(source: String) -> CmdResult<String> {
 pub async fn export_local_backup(filename: String, destination: String) -> CmdResult<()> {
     feat::export_local_backup(filename, destination).await.stringify_err()
 }
+
+/// List incremental backups
+#[tauri::command]
+pub async fn list_incremental_backup() -> CmdResult<Vec<LocalBackupFile>> {
+    feat::list_incremental_backup().await.stringify_err()
+}
+
+/// Delete incremental backup
+#[tauri::command]
+pub async fn delete_incremental_backup(filename: String) -> CmdResult<()> {
+    feat::delete_incremental_backup(filename).await.stringify_err()
+}
+
+/// Restore incremental backup
+#[tauri::command]
+pub async fn restore_incremental_backup(filename: String, passphrase: Option<String>) -> CmdResult<()> {
+    feat::restore_incremental_backup(filename, passphrase)
+        .await
+        .stringify_err()
+}
+
+/// Export incremental backup as a standalone backup file
+#[tauri::command]
+pub async fn export_incremental_backup(filename: String, destination: String) -> CmdResult<()> {
+    feat::export_incremental_backup(filename, destination)
+        .await
+        .stringify_err()
+}
+
+/// List the parts of a local or WebDAV backup that can be restored
+#[tauri::command]
+pub async fn get_backup_restore_plan(
+    source: BackupSource,
+    filename: String,
+    passphrase: Option<String>,
+) -> CmdResult<RestorePlan> {
+    feat::get_backup_restore_plan(source, filename, passphrase)
+        .await
+        .stringify_err()
+}
+
+/// Show what a backup contains and how it differs from the current configuration
+#[tauri::command]
+pub async fn preview_backup(
+    source: BackupSource,
+    filename: String,
+    passphrase: Option<String>,
+) -> CmdResult<BackupPreview> {
+    feat::preview_backup(source, filename, passphrase).await.stringify_err()
+}
+
+/// Restore only the selected parts of a backup
+#[tauri::command]
+pub async fn restore_backup_components(
+    source: BackupSource,
+    filename: String,
+    components: Vec<RestoreComponentKind>,
+    passphrase: Option<String>,
+) -> CmdResult<()> {
+    feat::restore_backup_components(source, filename, passphrase, components)
+        .await
+        .stringify_err()
+}
+
+/// Apply the backup retention policy, `dry_run` only reports what would be pruned
+#[tauri::command]
+pub async fn prune_backups(dry_run: bool) -> CmdResult<BackupPruneReport> {
+    feat::prune_backups(dry_run).await.stringify_err()
+}
@@ -0,0 +1,57 @@
+use super::CmdResult;
+use crate::{
+    cmd::StringifyErr as _,
+    config::{Config, IVerge},
+    core::backup_storage::RemoteBackupFile,
+    feat,
+};
+use smartstring::alias::String;
+use std::path::Path;
+
+/// 保存备份目录
+#[tauri::command]
+pub async fn save_backup_directory(path: String) -> CmdResult<()> {
+    if !path.is_empty() {
+        let dir = Path::new(path.as_str());
+        if !dir.is_absolute() {
+            return Err("Backup directory must be an absolute path".into());
+        }
+        tokio::fs::create_dir_all(dir).await.stringify_err()?;
+    }
+
+    let patch = IVerge {
+        backup_directory: Some(path),
+        ..IVerge::default()
+    };
+    Config::verge().await.edit_draft(|e| e.patch_config(&patch));
+    Config::verge().await.apply();
+
+    let verge_data = Config::verge().await.data_arc();
+    verge_data.save_file().await.stringify_err()
+}
+
+/// 创建备份并写入备份目录
+#[tauri::command]
+pub async fn create_directory_backup() -> CmdResult<()> {
+    feat::create_backup_and_upload_directory().await.stringify_err()
+}
+
+/// 列出备份目录中的备份文件
+#[tauri::command]
+pub async fn list_directory_backup() -> CmdResult<Vec<RemoteBackupFile>> {
+    feat::list_directory_backup().await.stringify_err()
+}
+
+/// 删除备份目录中的备份文件
+#[tauri::command]
+pub async fn delete_directory_backup(filename: String) -> CmdResult<()> {
+    feat::delete_directory_backup(filename).await.stringify_err()
+}
+
+/// 从备份目录恢复备份文件
+#[tauri::command]
+pub async fn restore_directory_backup(filename: String, passphrase: Option<String>) -> CmdResult<()> {
+    feat::restore_directory_backup(filename, passphrase)
+        .await
+        .stringify_err()
+}
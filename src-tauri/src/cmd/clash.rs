@@ -1,6 +1,9 @@
 use super::CmdResult;
 use crate::feat;
-use crate::utils::dirs;
+use crate::utils::{
+    dirs,
+    hosts::{HostsImportMode, HostsImportSummary},
+};
 use crate::{
     cmd::StringifyErr as _,
     config::{ClashInfo, Config},
@@ -9,6 +12,7 @@ use crate::{
         CoreManager, handle,
         validate::{CoreConfigValidator, ValidationOutcome},
     },
+    module::auto_backup::{AutoBackupManager, AutoBackupTrigger},
 };
 use clash_verge_logging::{Type, logging, logging_error};
 use compact_str::CompactString;
@@ -47,6 +51,11 @@ pub async fn patch_clash_mode(payload: String) -> CmdResult {
 pub async fn change_clash_core(clash_core: String) -> CmdResult<Option<String>> {
     logging!(info, Type::Config, "changing core to {clash_core}");
 
+    let current_core = Config::verge().await.latest_arc().clash_core.clone();
+    if current_core.as_ref() != Some(&clash_core) {
+        AutoBackupManager::run_backup(AutoBackupTrigger::CoreChange).await;
+    }
+
     match CoreManager::global().change_core(&clash_core).await {
         Ok(_) => {
             logging_error!(Type::Core, Config::profiles().await.data_arc().save_file().await);
@@ -242,6 +251,36 @@ pub async fn validate_dns_config() -> CmdResult<ValidationOutcome> {
         .stringify_err()
 }
 
+/// 导入 hosts 格式文件到 DNS 配置的 hosts 段
+#[tauri::command]
+pub async fn import_hosts_file(path: String, mode: Option<HostsImportMode>) -> CmdResult<HostsImportSummary> {
+    feat::import_hosts_file(path, mode.unwrap_or_default())
+        .await
+        .stringify_err_log(|err| {
+            logging!(error, Type::Config, "Failed to import hosts file: {err}");
+        })
+}
+
+/// 从 URL 导入 hosts 列表，设置更新间隔时由定时器自动刷新
+#[tauri::command]
+pub async fn import_hosts_url(
+    url: String,
+    mode: Option<HostsImportMode>,
+    update_interval: Option<u64>,
+) -> CmdResult<HostsImportSummary> {
+    feat::import_hosts_url(url, mode.unwrap_or_default(), update_interval)
+        .await
+        .stringify_err_log(|err| {
+            logging!(error, Type::Config, "Failed to import hosts list: {err}");
+        })
+}
+
+/// 移除自动刷新的 hosts 列表
+#[tauri::command]
+pub async fn remove_hosts_source(uid: String) -> CmdResult {
+    feat::remove_hosts_source(uid).await.stringify_err()
+}
+
 #[tauri::command]
 pub async fn get_clash_logs() -> CmdResult<Vec<CompactString>> {
     let logs = CoreManager::global().get_clash_logs().await.unwrap_or_default();
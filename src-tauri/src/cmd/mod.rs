@@ -6,6 +6,7 @@ pub type CmdResult<T = ()> = Result<T, String>;
 // Command modules
 pub mod app;
 pub mod backup;
+pub mod backup_directory;
 pub mod clash;
 pub mod lightweight;
 pub mod media_unlock_checker;
@@ -13,6 +14,7 @@ pub mod network;
 pub mod profile;
 pub mod proxy;
 pub mod runtime;
+pub mod s3;
 pub mod save_profile;
 pub mod service;
 pub mod system;
@@ -24,6 +26,7 @@ pub mod webdav;
 // Re-export all command functions for backwards compatibility
 pub use app::*;
 pub use backup::*;
+pub use backup_directory::*;
 pub use clash::*;
 pub use lightweight::*;
 pub use media_unlock_checker::*;
@@ -31,6 +34,7 @@ pub use network::*;
 pub use profile::*;
 pub use proxy::*;
 pub use runtime::*;
+pub use s3::*;
 pub use save_profile::*;
 pub use service::*;
 pub use system::*;
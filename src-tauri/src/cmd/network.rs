@@ -1,6 +1,7 @@
 use super::CmdResult;
 use crate::cmd::StringifyErr as _;
 use crate::core::sysopt::Sysopt;
+use crate::utils::network;
 use clash_verge_logging::{Type, logging};
 use gethostname::gethostname;
 use network_interface::NetworkInterface;
@@ -84,20 +85,7 @@ pub fn get_network_interfaces() -> Vec<String> {
 /// 获取网络接口详细信息
 #[tauri::command]
 pub fn get_network_interfaces_info() -> CmdResult<Vec<NetworkInterface>> {
-    use network_interface::{NetworkInterface, NetworkInterfaceConfig as _};
-
-    let names = get_network_interfaces();
-    let interfaces = NetworkInterface::show().stringify_err()?;
-
-    let mut result = Vec::new();
-
-    for interface in interfaces {
-        if names.contains(&interface.name) {
-            result.push(interface);
-        }
-    }
-
-    Ok(result)
+    network::network_interfaces().stringify_err()
 }
 
 #[tauri::command]
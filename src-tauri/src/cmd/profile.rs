@@ -4,16 +4,23 @@ use crate::cmd::validate::{ValidationNoticeTarget, handle_validation_notice};
 use crate::utils::window_manager::WindowManager;
 use crate::{
     config::{
-        Config, IProfiles, PrfItem, PrfOption,
+        Config, IProfiles, PrfItem, PrfOption, ProfileUpdateReport,
+        profile_history::{self, ProfileVersion},
         profiles::{
-            profiles_append_item_with_filedata_safe, profiles_delete_item_safe, profiles_patch_item_safe,
-            profiles_reorder_safe, profiles_save_file_safe,
+            profiles_append_item_with_filedata_safe, profiles_delete_items_safe, profiles_patch_item_safe,
+            profiles_reorder_safe, profiles_save_file_safe, profiles_tag_items_safe,
         },
         profiles_append_item_safe,
     },
-    core::{CoreManager, handle, timer::Timer, tray::Tray, validate::ValidationOutcome},
+    core::{
+        CoreManager, handle,
+        timer::{ProfileUpdateFailure, Timer},
+        tray::Tray,
+        validate::ValidationOutcome,
+    },
     feat,
-    utils::{dirs, help},
+    module::auto_backup::{AutoBackupManager, AutoBackupTrigger},
+    utils::{dirs, help, quota::ProfileQuotaStatus, schedule},
 };
 use clash_verge_draft::SharedDraft;
 use clash_verge_logging::{Type, logging};
@@ -154,11 +161,71 @@ pub async fn update_profile(index: String, option: Option<PrfOption>) -> CmdResu
     }
 }
 
+/// 批量更新远程订阅，`uids` 为空时更新全部远程订阅
+#[tauri::command]
+pub async fn update_profiles(
+    uids: Option<Vec<String>>,
+    concurrency: Option<usize>,
+) -> CmdResult<Vec<ProfileUpdateReport>> {
+    let concurrency = concurrency.unwrap_or(feat::DEFAULT_BATCH_CONCURRENCY);
+    Ok(feat::update_profiles_batch(uids.as_deref(), concurrency).await)
+}
+
+/// 批量更新带有指定标签的远程订阅
+#[tauri::command]
+pub async fn update_profiles_by_tag(tag: String, concurrency: Option<usize>) -> CmdResult<Vec<ProfileUpdateReport>> {
+    let uids = filter_profiles(Some(vec![tag]), None).await?;
+    // 没有订阅带有该标签时不更新，空列表会被当作全部订阅
+    if uids.is_empty() {
+        return Ok(Vec::new());
+    }
+    update_profiles(Some(uids), concurrency).await
+}
+
+/// 筛选包含全部标签且位于指定文件夹（含子文件夹）中的订阅，返回 uid 列表
+#[tauri::command]
+pub async fn filter_profiles(tags: Option<Vec<String>>, folder: Option<String>) -> CmdResult<Vec<String>> {
+    Ok(Config::profiles()
+        .await
+        .latest_arc()
+        .filter_items(tags.as_deref().unwrap_or_default(), folder.as_deref())
+        .into_iter()
+        .filter_map(|item| item.uid.clone())
+        .collect())
+}
+
+/// 为多个订阅批量添加或移除标签
+#[tauri::command]
+pub async fn tag_profiles(uids: Vec<String>, add: Option<Vec<String>>, remove: Option<Vec<String>>) -> CmdResult {
+    profiles_tag_items_safe(
+        &uids,
+        add.as_deref().unwrap_or_default(),
+        remove.as_deref().unwrap_or_default(),
+    )
+    .await
+    .stringify_err()
+}
+
 /// 删除配置文件
 #[tauri::command]
 pub async fn delete_profile(index: String) -> CmdResult {
-    // 使用Send-safe helper函数
-    let should_update = profiles_delete_item_safe(&index).await.stringify_err()?;
+    delete_profile_items(&[index]).await
+}
+
+/// 批量删除配置文件
+#[tauri::command]
+pub async fn delete_profiles(uids: Vec<String>) -> CmdResult {
+    delete_profile_items(&uids).await
+}
+
+async fn delete_profile_items(uids: &[String]) -> CmdResult {
+    AutoBackupManager::run_backup(AutoBackupTrigger::ProfileDelete).await;
+
+    // 使用Send-safe helper函数，单个订阅删除失败时继续删除其余订阅，最后统一保存及刷新
+    let (deleted_current, errors) = profiles_delete_items_safe(uids).await.stringify_err()?;
+    for error in &errors {
+        logging!(warn, Type::Cmd, "Warning: 删除订阅失败: {}", error);
+    }
     profiles_save_file_safe().await.stringify_err()?;
     if let Err(e) = Tray::global().update_tooltip().await {
         logging!(warn, Type::Cmd, "Warning: 异步更新托盘提示失败: {e}");
@@ -167,7 +234,7 @@ pub async fn delete_profile(index: String) -> CmdResult {
     if let Err(e) = Tray::global().update_menu().await {
         logging!(warn, Type::Cmd, "Warning: 异步更新托盘菜单失败: {e}");
     }
-    if should_update {
+    if let Some(index) = deleted_current {
         match CoreManager::global().update_config_forced().await {
             Ok(outcome) if outcome.is_valid() => {
                 handle::Handle::refresh_clash();
@@ -187,6 +254,9 @@ pub async fn delete_profile(index: String) -> CmdResult {
         }
     }
     Timer::global().refresh().await.stringify_err()?;
+    if !errors.is_empty() {
+        return Err(errors.join("; ").into());
+    }
     Ok(())
 }
 
@@ -330,22 +400,50 @@ pub async fn patch_profiles_config_by_profile_index(profile_index: String) -> Cm
 /// 修改某个profile item的
 #[tauri::command]
 pub async fn patch_profile(index: String, profile: PrfItem) -> CmdResult {
+    if let Some(expr) = profile.option.as_ref().and_then(|o| o.update_cron.as_deref())
+        && !expr.trim().is_empty()
+    {
+        schedule::normalize_cron(expr).stringify_err()?;
+    }
+
     // 保存修改前检查是否有更新 update_interval
     let profiles = Config::profiles().await;
     let should_refresh_timer = if let Ok(old_profile) = profiles.latest_arc().get_item(&index)
         && let Some(new_option) = profile.option.as_ref()
     {
-        let old_interval = old_profile.option.as_ref().and_then(|o| o.update_interval);
+        let old_option = old_profile.option.as_ref();
+        let old_interval = old_option.and_then(|o| o.update_interval);
         let new_interval = new_option.update_interval;
-        let old_allow_auto_update = old_profile.option.as_ref().and_then(|o| o.allow_auto_update);
+        let old_allow_auto_update = old_option.and_then(|o| o.allow_auto_update);
         let new_allow_auto_update = new_option.allow_auto_update;
-        (old_interval != new_interval) || (old_allow_auto_update != new_allow_auto_update)
+        let old_cron = old_option.and_then(|o| o.update_cron.as_ref());
+        let old_jitter = old_option.and_then(|o| o.update_jitter);
+        (old_interval != new_interval)
+            || (old_allow_auto_update != new_allow_auto_update)
+            || (old_cron != new_option.update_cron.as_ref())
+            || (old_jitter != new_option.update_jitter)
+    } else {
+        false
+    };
+    // 拉取订阅使用的节点变更后需要重新生成监听器
+    let should_reload_listeners = if let Ok(old_profile) = profiles.latest_arc().get_item(&index)
+        && let Some(new_option) = profile.option.as_ref()
+    {
+        old_profile.option.as_ref().and_then(|o| o.fetch_node.as_ref()) != new_option.fetch_node.as_ref()
     } else {
         false
     };
 
     profiles_patch_item_safe(&index, &profile).await.stringify_err()?;
 
+    if should_reload_listeners {
+        crate::process::AsyncHandler::spawn(|| async {
+            if let Err(e) = CoreManager::global().update_config_checked().await {
+                logging!(error, Type::Config, "重新生成订阅拉取节点监听器失败: {}", e);
+            }
+        });
+    }
+
     // 如果更新间隔或允许自动更新变更，异步刷新定时器
     if should_refresh_timer {
         crate::process::AsyncHandler::spawn(move || async move {
@@ -397,6 +495,48 @@ pub async fn read_profile_file(index: String) -> CmdResult<String> {
     Ok(data)
 }
 
+/// 立即从关联的外部文件同步本地订阅，返回内容是否发生变化
+#[tauri::command]
+pub async fn sync_linked_profile(index: String) -> CmdResult<bool> {
+    crate::module::linked_profile::sync_linked_profile(&index)
+        .await
+        .stringify_err_log(|err| logging!(error, Type::Cmd, "{}", err))
+}
+
+/// 获取订阅的历史版本
+#[tauri::command]
+pub async fn get_profile_versions(index: String) -> CmdResult<Vec<ProfileVersion>> {
+    profile_history::list_versions(&index).await.stringify_err()
+}
+
+/// 比较订阅的两个历史版本，`to` 为空时与当前文件比较
+#[tauri::command]
+pub async fn diff_profile_versions(index: String, from: String, to: Option<String>) -> CmdResult<String> {
+    feat::diff_profile_versions(&index, &from, to.as_deref())
+        .await
+        .stringify_err()
+}
+
+/// 回滚订阅到指定的历史版本
+#[tauri::command]
+pub async fn rollback_profile(index: String, version: String) -> CmdResult {
+    feat::rollback_profile(&index, &version)
+        .await
+        .stringify_err_log(|err| logging!(error, Type::Cmd, "{}", err))
+}
+
+/// 获取所有订阅的流量及到期状态，按紧急程度排序
+#[tauri::command]
+pub async fn get_profiles_quota_status() -> CmdResult<Vec<ProfileQuotaStatus>> {
+    Ok(feat::profile_quota_status().await)
+}
+
+/// 获取定时更新失败的订阅，包括连续失败次数、最近一次错误及下次重试时间
+#[tauri::command]
+pub async fn get_profile_update_failures() -> CmdResult<Vec<ProfileUpdateFailure>> {
+    Ok(Timer::global().update_failures().await)
+}
+
 /// 获取下一次更新时间
 #[tauri::command]
 pub async fn get_next_update_time(uid: String) -> CmdResult<Option<i64>> {
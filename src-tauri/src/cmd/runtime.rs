@@ -1,5 +1,9 @@
 use super::CmdResult;
-use crate::{cmd::StringifyErr as _, config::Config, core::CoreManager};
+use crate::{
+    cmd::StringifyErr as _,
+    config::{Config, FETCH_NODE_PREFIX},
+    core::CoreManager,
+};
 use anyhow::{Context as _, anyhow};
 use clash_verge_logging::{Type, logging};
 use serde_yaml_ng::Mapping;
@@ -12,6 +16,12 @@ pub async fn get_runtime_config() -> CmdResult<Option<Mapping>> {
     Ok(Config::runtime().await.latest_arc().config.clone())
 }
 
+/// 通过指定节点拉取订阅时注入的代理名前缀，前端据此隐藏这些代理
+#[tauri::command]
+pub fn get_fetch_node_prefix() -> &'static str {
+    FETCH_NODE_PREFIX
+}
+
 /// 获取运行时YAML配置
 #[tauri::command]
 pub async fn get_runtime_yaml() -> CmdResult<String> {
@@ -0,0 +1,63 @@
+use super::CmdResult;
+use crate::{
+    cmd::StringifyErr as _,
+    config::{Config, IVerge},
+    core::{
+        backup_s3::S3Client,
+        backup_storage::{BackupStorage as _, RemoteBackupFile},
+    },
+    feat,
+};
+use smartstring::alias::String;
+
+/// 保存 S3 配置
+#[tauri::command]
+pub async fn save_s3_config(
+    endpoint: String,
+    bucket: String,
+    prefix: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+) -> CmdResult<()> {
+    let patch = IVerge {
+        s3_endpoint: Some(endpoint),
+        s3_bucket: Some(bucket),
+        s3_prefix: Some(prefix),
+        s3_region: Some(region),
+        s3_access_key_id: Some(access_key_id),
+        s3_secret_access_key: Some(secret_access_key),
+        ..IVerge::default()
+    };
+    Config::verge().await.edit_draft(|e| e.patch_config(&patch));
+    Config::verge().await.apply();
+
+    let verge_data = Config::verge().await.data_arc();
+    verge_data.save_file().await.stringify_err()?;
+    S3Client::global().reset();
+    Ok(())
+}
+
+/// 创建备份并上传到 S3
+#[tauri::command]
+pub async fn create_s3_backup() -> CmdResult<()> {
+    feat::create_backup_and_upload_s3().await.stringify_err()
+}
+
+/// 列出 S3 上的备份文件
+#[tauri::command]
+pub async fn list_s3_backup() -> CmdResult<Vec<RemoteBackupFile>> {
+    feat::list_s3_backup().await.stringify_err()
+}
+
+/// 删除 S3 上的备份文件
+#[tauri::command]
+pub async fn delete_s3_backup(filename: String) -> CmdResult<()> {
+    feat::delete_s3_backup(filename).await.stringify_err()
+}
+
+/// 从 S3 恢复备份文件
+#[tauri::command]
+pub async fn restore_s3_backup(filename: String, passphrase: Option<String>) -> CmdResult<()> {
+    feat::restore_s3_backup(filename, passphrase).await.stringify_err()
+}
@@ -29,17 +29,27 @@ pub async fn save_profile_file(index: String, file_data: Option<String>) -> CmdR
     };
 
     // 在异步操作前获取必要元数据并释放锁
-    let (rel_path, is_merge_file, is_script_file, affects_runtime) = {
+    let (rel_path, is_merge_file, is_script_file, is_enhancement, affects_runtime) = {
         let profiles = Config::profiles().await;
         let profiles_guard = profiles.latest_arc();
         let item = profiles_guard.get_item(&index).stringify_err()?;
         let is_merge = item.itype.as_ref().is_some_and(|t| t == "merge");
         let path = item.file.clone().ok_or("file field is null")?;
         let is_script = item.itype.as_ref().is_some_and(|t| t == "script") || path.ends_with(".js");
+        let is_enhancement = backup_trigger.is_none()
+            && matches!(
+                item.itype.as_deref(),
+                Some("merge" | "script" | "rules" | "proxies" | "groups")
+            );
         let affects_runtime = profile_affects_runtime(&profiles_guard, &index);
-        (path, is_merge, is_script, affects_runtime)
+        (path, is_merge, is_script, is_enhancement, affects_runtime)
     };
 
+    // 订阅自己的扩展项在覆盖前备份，保留修改前的内容
+    if is_enhancement {
+        AutoBackupManager::run_backup(AutoBackupTrigger::ProfileEnhancement).await;
+    }
+
     // 读取原始内容（在释放profiles_guard后进行）
     let original_content = PrfItem {
         file: Some(rel_path.clone()),
@@ -2,9 +2,12 @@ use super::CmdResult;
 use crate::{
     cmd::StringifyErr as _,
     config::{Config, IVerge},
-    core, feat,
+    core::{
+        self,
+        backup_storage::{BackupStorage as _, RemoteBackupFile},
+    },
+    feat,
 };
-use reqwest_dav::list_cmd::ListFile;
 use smartstring::alias::String;
 
 /// 保存 WebDAV 配置
@@ -33,7 +36,7 @@ pub async fn create_webdav_backup() -> CmdResult<()> {
 
 /// 列出 WebDAV 上的备份文件
 #[tauri::command]
-pub async fn list_webdav_backup() -> CmdResult<Vec<ListFile>> {
+pub async fn list_webdav_backup() -> CmdResult<Vec<RemoteBackupFile>> {
     feat::list_wevdav_backup().await.stringify_err()
 }
 
@@ -45,6 +48,6 @@ pub async fn delete_webdav_backup(filename: String) -> CmdResult<()> {
 
 /// 从 WebDAV 恢复备份文件
 #[tauri::command]
-pub async fn restore_webdav_backup(filename: String) -> CmdResult<()> {
-    feat::restore_webdav_backup(filename).await.stringify_err()
+pub async fn restore_webdav_backup(filename: String, passphrase: Option<String>) -> CmdResult<()> {
+    feat::restore_webdav_backup(filename, passphrase).await.stringify_err()
 }
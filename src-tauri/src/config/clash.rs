@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use serde_yaml_ng::{Mapping, Value};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
     str::FromStr as _,
 };
 
@@ -16,32 +17,13 @@ pub struct IClashTemp(pub Mapping);
 
 impl IClashTemp {
     pub async fn new() -> Self {
-        let clash_path_result = dirs::clash_path();
-        let map_result = if let Ok(path) = clash_path_result {
-            help::read_mapping(&path).await
-        } else {
-            Err(anyhow::anyhow!("Failed to get clash path"))
+        let map_result = match dirs::clash_path() {
+            Ok(path) => Self::from_file(&path).await,
+            Err(_) => Err(anyhow::anyhow!("Failed to get clash path")),
         };
 
         match map_result {
-            Ok(mut map) => {
-                let template_map = Self::template().0;
-                for (key, value) in template_map.into_iter() {
-                    if !map.contains_key(&key) {
-                        map.insert(key, value);
-                    }
-                }
-
-                // 确保 secret 字段存在且不为空
-                if let Some(val) = map.get_mut("secret")
-                    && let Value::String(s) = val
-                    && s.is_empty()
-                {
-                    *s = "set-your-secret".into();
-                }
-
-                Self(Self::guard(map))
-            }
+            Ok(config) => config,
             Err(err) => {
                 logging!(error, Type::Config, "{err}");
                 Self::template()
@@ -49,6 +31,27 @@ impl IClashTemp {
         }
     }
 
+    /// 从指定文件读取 clash 配置，缺失的字段使用模板补全
+    pub async fn from_file(path: &PathBuf) -> Result<Self> {
+        let mut map = help::read_mapping(path).await?;
+        let template_map = Self::template().0;
+        for (key, value) in template_map.into_iter() {
+            if !map.contains_key(&key) {
+                map.insert(key, value);
+            }
+        }
+
+        // 确保 secret 字段存在且不为空
+        if let Some(val) = map.get_mut("secret")
+            && let Value::String(s) = val
+            && s.is_empty()
+        {
+            *s = "set-your-secret".into();
+        }
+
+        Ok(Self(Self::guard(map)))
+    }
+
     pub fn template() -> Self {
         let mut map = Mapping::new();
         let mut tun_config = Mapping::new();
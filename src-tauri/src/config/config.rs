@@ -18,7 +18,10 @@ use clash_verge_draft::Draft;
 use clash_verge_logging::{Type, logging, logging_error};
 use serde_yaml_ng::{Mapping, Value};
 use smartstring::alias::String;
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 use tauri_plugin_clash_verge_sysinfo::is_current_app_handle_admin;
 use tokio::sync::OnceCell;
 use tokio::time::sleep;
@@ -207,6 +210,18 @@ impl Config {
         Ok(())
     }
 
+    /// 由给定的配置生成运行配置，不写入任何草稿，供恢复备份前预检使用
+    pub async fn generate_standalone(
+        clash: &IClashTemp,
+        verge: &IVerge,
+        profiles: &IProfiles,
+        dns_path: Option<&Path>,
+    ) -> Result<Mapping> {
+        let (mut config, _, _) = enhance::enhance_with(clash.0.clone(), verge, profiles, dns_path).await?;
+        sanitize_tunnels_proxy(&mut config);
+        Ok(config)
+    }
+
     pub async fn verify_config_initialization() {
         let backoff = ExponentialBuilder::default()
             .with_min_delay(std::time::Duration::from_millis(100))
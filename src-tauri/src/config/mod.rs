@@ -2,12 +2,16 @@ mod clash;
 #[allow(clippy::module_inception)]
 mod config;
 mod encrypt;
+mod prfchange;
 mod prfitem;
+mod prfupdate;
+pub mod profile_history;
 pub mod profiles;
 pub mod runtime;
+mod selected;
 mod verge;
 
-pub use self::{clash::*, config::*, encrypt::*, prfitem::*, profiles::*, verge::*};
+pub use self::{clash::*, config::*, encrypt::*, prfitem::*, prfupdate::*, profiles::*, verge::*};
 
 pub const DEFAULT_PAC: &str = r#"function FindProxyForURL(url, host) {
   return "PROXY 127.0.0.1:%mixed-port%; SOCKS5 127.0.0.1:%mixed-port%; DIRECT;";
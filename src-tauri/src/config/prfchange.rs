@@ -0,0 +1,213 @@
+use super::{PrfChangeReport, PrfNodeChange};
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use std::collections::{HashMap, HashSet};
+
+/// A node of the `proxies` section, reduced to the fields used for identity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyNode {
+    pub name: String,
+    pub kind: String,
+    pub server: String,
+    pub port: String,
+}
+
+impl ProxyNode {
+    /// `type|server|port`, stable across renames
+    pub fn identity(&self) -> String {
+        format!("{}|{}|{}", self.kind, self.server, self.port).into()
+    }
+}
+
+pub fn proxy_nodes(config: &Mapping) -> Vec<ProxyNode> {
+    let Some(seq) = config.get("proxies").and_then(Value::as_sequence) else {
+        return Vec::new();
+    };
+
+    seq.iter()
+        .filter_map(Value::as_mapping)
+        .filter_map(|proxy| {
+            let name = proxy.get("name").and_then(Value::as_str)?;
+            let server = proxy.get("server").and_then(Value::as_str)?;
+            let port = match proxy.get("port")? {
+                Value::Number(port) => port.to_string(),
+                Value::String(port) => port.clone(),
+                _ => return None,
+            };
+            let kind = proxy.get("type").and_then(Value::as_str).unwrap_or_default();
+            Some(ProxyNode {
+                name: name.into(),
+                kind: kind.into(),
+                server: server.to_ascii_lowercase().into(),
+                port: port.into(),
+            })
+        })
+        .collect()
+}
+
+/// 代理组名称 -> 成员列表
+fn proxy_groups(config: &Mapping) -> Vec<(String, Vec<String>)> {
+    let Some(seq) = config.get("proxy-groups").and_then(Value::as_sequence) else {
+        return Vec::new();
+    };
+
+    seq.iter()
+        .filter_map(Value::as_mapping)
+        .filter_map(|group| {
+            let name = group.get("name").and_then(Value::as_str)?;
+            let members = group
+                .get("proxies")
+                .and_then(Value::as_sequence)
+                .map(|seq| seq.iter().filter_map(Value::as_str).map(Into::into).collect())
+                .unwrap_or_default();
+            Some((name.into(), members))
+        })
+        .collect()
+}
+
+/// 对比更新前后的订阅内容
+/// - 名称不变但 type/server/port 变化的节点记为修改
+/// - 名称变化但 type/server/port 不变的节点记为重命名
+/// - 成员列表变化的代理组记为修改
+pub fn compute_change_report(old: &Mapping, new: &Mapping) -> PrfChangeReport {
+    let old_nodes = proxy_nodes(old);
+    let new_nodes = proxy_nodes(new);
+    let old_by_name: HashMap<&str, &ProxyNode> = old_nodes.iter().map(|node| (node.name.as_str(), node)).collect();
+    let new_by_name: HashMap<&str, &ProxyNode> = new_nodes.iter().map(|node| (node.name.as_str(), node)).collect();
+
+    let mut report = PrfChangeReport {
+        time: chrono::Local::now().timestamp(),
+        ..PrfChangeReport::default()
+    };
+
+    let mut removed: Vec<&ProxyNode> = Vec::new();
+    for node in &old_nodes {
+        match new_by_name.get(node.name.as_str()) {
+            Some(current) => {
+                let mut fields = Vec::new();
+                if current.kind != node.kind {
+                    fields.push("type".into());
+                }
+                if current.server != node.server {
+                    fields.push("server".into());
+                }
+                if current.port != node.port {
+                    fields.push("port".into());
+                }
+                if !fields.is_empty() {
+                    report.modified.push(PrfNodeChange {
+                        name: node.name.clone(),
+                        fields,
+                    });
+                }
+            }
+            None => removed.push(node),
+        }
+    }
+
+    // 新增的节点中与被删除节点 identity 相同的视为重命名
+    let mut added: Vec<&ProxyNode> = new_nodes
+        .iter()
+        .filter(|node| !old_by_name.contains_key(node.name.as_str()))
+        .collect();
+    for node in removed {
+        let identity = node.identity();
+        match added.iter().position(|candidate| candidate.identity() == identity) {
+            Some(pos) => {
+                let renamed = added.remove(pos);
+                report.renamed.push((node.name.clone(), renamed.name.clone()));
+            }
+            None => report.removed.push(node.name.clone()),
+        }
+    }
+    report.added = added.into_iter().map(|node| node.name.clone()).collect();
+
+    let old_groups = proxy_groups(old);
+    let new_groups = proxy_groups(new);
+    let old_group_names: HashSet<&str> = old_groups.iter().map(|(name, _)| name.as_str()).collect();
+    let new_group_map: HashMap<&str, &Vec<String>> = new_groups
+        .iter()
+        .map(|(name, members)| (name.as_str(), members))
+        .collect();
+
+    for (name, members) in &old_groups {
+        match new_group_map.get(name.as_str()) {
+            Some(current) if *current != members => report.groups_modified.push(name.clone()),
+            Some(_) => {}
+            None => report.groups_removed.push(name.clone()),
+        }
+    }
+    report.groups_added = new_groups
+        .iter()
+        .filter(|(name, _)| !old_group_names.contains(name.as_str()))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(content: &str) -> Mapping {
+        serde_yaml_ng::from_str(content).unwrap_or_default()
+    }
+
+    #[test]
+    fn report_node_and_group_changes() {
+        let old = yaml(
+            "
+proxies:
+  - { name: HK 01, type: ss, server: hk.example.com, port: 443 }
+  - { name: JP 01, type: ss, server: jp.example.com, port: 443 }
+  - { name: US 01, type: vmess, server: us.example.com, port: 443 }
+  - { name: SG 01, type: ss, server: sg.example.com, port: 443 }
+proxy-groups:
+  - { name: Proxy, type: select, proxies: [HK 01, JP 01, US 01, SG 01] }
+  - { name: Old, type: select, proxies: [HK 01] }
+",
+        );
+        let new = yaml(
+            "
+proxies:
+  - { name: 🇭🇰 Hong Kong 01, type: ss, server: HK.example.com, port: 443 }
+  - { name: JP 01, type: ss, server: jp.example.com, port: 8443 }
+  - { name: US 01, type: vmess, server: us.example.com, port: 443 }
+  - { name: TW 01, type: trojan, server: tw.example.com, port: 443 }
+proxy-groups:
+  - { name: Proxy, type: select, proxies: [🇭🇰 Hong Kong 01, JP 01, US 01, TW 01] }
+  - { name: New, type: select, proxies: [TW 01] }
+",
+        );
+
+        let report = compute_change_report(&old, &new);
+        assert_eq!(report.added, vec!["TW 01"]);
+        assert_eq!(report.removed, vec!["SG 01"]);
+        assert_eq!(report.renamed, vec![("HK 01".into(), "🇭🇰 Hong Kong 01".into())]);
+        assert_eq!(
+            report.modified,
+            vec![PrfNodeChange {
+                name: "JP 01".into(),
+                fields: vec!["port".into()],
+            }]
+        );
+        assert_eq!(report.groups_added, vec!["New"]);
+        assert_eq!(report.groups_removed, vec!["Old"]);
+        assert_eq!(report.groups_modified, vec!["Proxy"]);
+        assert!(!report.is_empty());
+    }
+
+    #[test]
+    fn unchanged_profile_has_empty_report() {
+        let config = yaml(
+            "
+proxies:
+  - { name: HK 01, type: ss, server: hk.example.com, port: 443 }
+proxy-groups:
+  - { name: Proxy, type: select, proxies: [HK 01] }
+",
+        );
+        assert!(compute_change_report(&config, &config).is_empty());
+    }
+}
@@ -1,21 +1,32 @@
 use crate::{
-    config::profiles,
+    config::{deserialize_encrypted, profiles, serialize_encrypted},
+    enhance::fetch_node,
     utils::{
         dirs, help,
-        network::{NetworkManager, ProxyType},
+        network::{NetworkManager, ProxyType, conditional_headers},
         tmpl,
     },
 };
 use anyhow::{Context as _, Result, bail};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use reqwest::{
+    StatusCode,
+    header::{AUTHORIZATION, ETAG, HeaderMap, HeaderName, HeaderValue, LAST_MODIFIED},
+};
 use serde::{Deserialize, Serialize};
 use serde_yaml_ng::Mapping;
 use smartstring::alias::String;
-use std::time::Duration;
+use std::{collections::BTreeMap, time::Duration};
 use tokio::fs;
 // TODO, use other re-export
 use reqwest_dav::re_exports::url::form_urlencoded;
 use tauri::Url;
 
+/// 主地址连续失败达到该次数后，优先使用上次成功的镜像
+const MIRROR_PROMOTE_AFTER: u32 = 3;
+/// 镜像优先期间每隔该次数仍先尝试主地址，主地址恢复后取消镜像优先
+const PRIMARY_PROBE_EVERY: u32 = 5;
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct PrfItem {
     pub uid: Option<String>,
@@ -39,6 +50,20 @@ pub struct PrfItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
 
+    /// user defined tags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+
+    /// folder path, nested folders are separated by `/`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+
+    /// for `local` profile
+    /// external file that is watched and synced into the profile file,
+    /// which keeps the last valid content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub linked_path: Option<String>,
+
     /// selected information
     #[serde(skip_serializing_if = "Option::is_none")]
     pub selected: Option<Vec<PrfSelected>>,
@@ -50,6 +75,43 @@ pub struct PrfItem {
     /// updated time
     pub updated: Option<usize>,
 
+    /// changes made by the last update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_report: Option<PrfChangeReport>,
+
+    /// `ETag` of the last fetched remote profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+
+    /// `Last-Modified` of the last fetched remote profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+
+    /// fallback urls of a remote profile, tried in order when the primary url fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirrors: Option<Vec<String>>,
+
+    /// the mirror that served the last successful update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_mirror: Option<String>,
+
+    /// consecutive updates that did not succeed through the primary url,
+    /// including those where it was skipped in favour of the active mirror
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_failures: Option<u32>,
+
+    /// consecutive failed scheduled updates, reset after a successful update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_failures: Option<u32>,
+
+    /// error of the last failed scheduled update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_error: Option<String>,
+
+    /// quota and expiry alerts already sent, cleared once the subscription is back to normal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota_alert: Option<PrfQuotaAlert>,
+
     /// some options of the item
     #[serde(skip_serializing_if = "Option::is_none")]
     pub option: Option<PrfOption>,
@@ -63,7 +125,7 @@ pub struct PrfItem {
     pub file_data: Option<String>,
 }
 
-#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct PrfSelected {
     pub name: Option<String>,
     pub now: Option<String>,
@@ -77,6 +139,88 @@ pub struct PrfExtra {
     pub expire: u64,
 }
 
+#[derive(Default, Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PrfQuotaAlert {
+    /// the highest usage threshold notified
+    pub threshold: u8,
+    pub exhausted: bool,
+    pub expiring: bool,
+    pub expired: bool,
+}
+
+/// Nodes and groups changed by a subscription update
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PrfChangeReport {
+    /// update time in seconds
+    pub time: i64,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+
+    /// (previous name, new name) of nodes with the same type/server/port
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub renamed: Vec<(String, String)>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modified: Vec<PrfNodeChange>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups_added: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups_removed: Vec<String>,
+
+    /// groups whose member list changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups_modified: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PrfNodeChange {
+    pub name: String,
+    /// changed fields among `type`, `server` and `port`
+    pub fields: Vec<String>,
+}
+
+impl PrfChangeReport {
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.modified.is_empty()
+            && self.groups_added.is_empty()
+            && self.groups_removed.is_empty()
+            && self.groups_modified.is_empty()
+    }
+
+    /// 简要描述，用于日志和通知
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        for (label, count) in [
+            ("added", self.added.len()),
+            ("removed", self.removed.len()),
+            ("renamed", self.renamed.len()),
+            ("modified", self.modified.len()),
+        ] {
+            if count > 0 {
+                parts.push(format!("{count} {label}"));
+            }
+        }
+        let groups = self.groups_added.len() + self.groups_removed.len() + self.groups_modified.len();
+        if groups > 0 {
+            parts.push(format!("{groups} groups changed"));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("removed: {}", self.removed.join(", ")));
+        }
+        parts.join(", ").into()
+    }
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct PrfOption {
     /// for `remote` profile's http request
@@ -97,6 +241,16 @@ pub struct PrfOption {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub update_interval: Option<u64>,
 
+    /// update at fixed local times with a crontab expression, e.g. `30 8 * * 1-5`
+    /// takes precedence over `update_interval`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_cron: Option<String>,
+
+    /// random delay in seconds before a cron update starts
+    /// default is 300 seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_jitter: Option<u64>,
+
     /// for `remote` profile
     /// HTTP request timeout in seconds
     /// default is 60 seconds
@@ -109,6 +263,41 @@ pub struct PrfOption {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub danger_accept_invalid_certs: Option<bool>,
 
+    /// for `remote` profile
+    /// extra http request headers, e.g. `X-Device-Id` (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub headers: Option<BTreeMap<String, String>>,
+
+    /// for `remote` profile
+    /// bearer token or basic auth (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub auth: Option<PrfAuth>,
+
+    /// for `remote` profile
+    /// fetch through this proxy url, supports `http`, `https`, `socks5` and `socks5h` (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub fetch_proxy: Option<String>,
+
+    /// for `remote` profile
+    /// fetch through a node of a profile, takes precedence over `fetch_proxy`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetch_node: Option<PrfFetchNode>,
+
     #[serde(default = "default_allow_auto_update")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_auto_update: Option<bool>,
@@ -124,6 +313,38 @@ pub struct PrfOption {
     pub groups: Option<String>,
 }
 
+/// Name prefix of the proxies and listeners injected for [`PrfFetchNode`], never shown to the user
+pub const FETCH_NODE_PREFIX: &str = "verge-fetch-";
+
+/// A node of a profile used to fetch another remote profile
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub struct PrfFetchNode {
+    /// uid of the profile that contains the node
+    pub profile: String,
+
+    /// node name in the `proxies` section
+    pub name: String,
+}
+
+/// Authorization of the remote profile request
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrfAuth {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+}
+
+impl PrfAuth {
+    fn header_value(&self) -> std::string::String {
+        match self {
+            Self::Bearer { token } => format!("Bearer {}", token.trim()),
+            Self::Basic { username, password } => {
+                format!("Basic {}", STANDARD.encode(format!("{username}:{password}")))
+            }
+        }
+    }
+}
+
 impl PrfOption {
     pub fn merge(one: Option<&Self>, other: Option<&Self>) -> Option<Self> {
         match (one, other) {
@@ -136,12 +357,18 @@ impl PrfOption {
                     b_ref.danger_accept_invalid_certs.or(result.danger_accept_invalid_certs);
                 result.allow_auto_update = b_ref.allow_auto_update.or(result.allow_auto_update);
                 result.update_interval = b_ref.update_interval.or(result.update_interval);
+                result.update_cron = b_ref.update_cron.clone().or(result.update_cron);
+                result.update_jitter = b_ref.update_jitter.or(result.update_jitter);
                 result.merge = b_ref.merge.clone().or(result.merge);
                 result.script = b_ref.script.clone().or(result.script);
                 result.rules = b_ref.rules.clone().or(result.rules);
                 result.proxies = b_ref.proxies.clone().or(result.proxies);
                 result.groups = b_ref.groups.clone().or(result.groups);
                 result.timeout_seconds = b_ref.timeout_seconds.or(result.timeout_seconds);
+                result.headers = b_ref.headers.clone().or(result.headers);
+                result.auth = b_ref.auth.clone().or(result.auth);
+                result.fetch_proxy = b_ref.fetch_proxy.clone().or(result.fetch_proxy);
+                result.fetch_node = b_ref.fetch_node.clone().or(result.fetch_node);
                 Some(result)
             }
             (Some(a_ref), None) => Some(a_ref.clone()),
@@ -149,6 +376,59 @@ impl PrfOption {
             (None, None) => None,
         }
     }
+
+    /// 订阅请求附带的自定义请求头及认证信息，错误信息中不包含请求头的值
+    pub fn request_headers(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers.iter().flatten() {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let header_name =
+                HeaderName::from_bytes(name.as_bytes()).with_context(|| format!("invalid header name \"{name}\""))?;
+            let mut header_value = HeaderValue::from_str(value.trim())
+                .map_err(|_| anyhow::anyhow!("invalid value for header \"{name}\""))?;
+            header_value.set_sensitive(true);
+            headers.insert(header_name, header_value);
+        }
+
+        if let Some(auth) = self.auth.as_ref() {
+            let mut value =
+                HeaderValue::from_str(&auth.header_value()).map_err(|_| anyhow::anyhow!("invalid authorization"))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        Ok(headers)
+    }
+}
+
+/// Result of a conditional request for a remote profile
+#[derive(Debug)]
+pub enum RemoteFetch {
+    Fetched(Box<PrfItem>),
+    /// `304 Not Modified`, the server may still send the latest user info
+    NotModified(Option<PrfExtra>),
+}
+
+/// parse the Subscription UserInfo
+fn subscription_userinfo(header: &HeaderMap) -> Option<PrfExtra> {
+    header.iter().find_map(|(k, v)| {
+        let key_lower = k.as_str().to_ascii_lowercase();
+        // Accept standard custom-metadata prefixes (x-amz-meta-, x-obs-meta-, x-cos-meta-, etc.).
+        key_lower
+            .strip_suffix("subscription-userinfo")
+            .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('-'))
+            .then(|| {
+                let sub_info = v.to_str().unwrap_or("");
+                PrfExtra {
+                    upload: help::parse_str(sub_info, "upload").unwrap_or(0),
+                    download: help::parse_str(sub_info, "download").unwrap_or(0),
+                    total: help::parse_str(sub_info, "total").unwrap_or(0),
+                    expire: help::parse_str(sub_info, "expire").unwrap_or(0),
+                }
+            })
+    })
 }
 
 impl PrfItem {
@@ -172,18 +452,80 @@ impl PrfItem {
                 let name = item.name.as_ref();
                 let desc = item.desc.as_ref();
                 let option = item.option.as_ref();
-                Self::from_url(url, name, desc, option).await
+                let mut remote = Self::from_url(url, name, desc, option).await?;
+                remote.mirrors = item.mirrors.clone();
+                remote.set_grouping(item.tags.clone(), item.folder.clone());
+                Ok(remote)
             }
             "local" => {
                 let name = item.name.clone().unwrap_or_else(|| "Local File".into());
                 let desc = item.desc.clone().unwrap_or_else(|| "".into());
                 let option = item.option.as_ref();
-                Self::from_local(name, desc, file_data, option).await
+                let linked_path = item
+                    .linked_path
+                    .as_ref()
+                    .map(|path| path.trim())
+                    .filter(|path| !path.is_empty());
+                // 关联外部文件时以该文件的内容创建
+                let file_data = match (linked_path, file_data) {
+                    (Some(path), None) => Some(
+                        fs::read_to_string(path)
+                            .await
+                            .with_context(|| format!("failed to read the linked file \"{path}\""))?
+                            .into(),
+                    ),
+                    (_, file_data) => file_data,
+                };
+                let mut local = Self::from_local(name, desc, file_data, option).await?;
+                local.set_grouping(item.tags.clone(), item.folder.clone());
+                local.linked_path = linked_path.map(Into::into);
+                Ok(local)
             }
             typ => bail!("invalid profile item type \"{typ}\""),
         }
     }
 
+    /// 设置标签及文件夹，去除空白与重复的标签，空值视为未设置
+    pub fn set_grouping(&mut self, tags: Option<Vec<String>>, folder: Option<String>) {
+        self.tags = tags
+            .map(|tags| {
+                let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+                for tag in tags {
+                    let tag: String = tag.trim().into();
+                    if !tag.is_empty() && !normalized.contains(&tag) {
+                        normalized.push(tag);
+                    }
+                }
+                normalized
+            })
+            .filter(|tags| !tags.is_empty());
+        self.folder = folder
+            .map(|folder| {
+                folder
+                    .split('/')
+                    .map(str::trim)
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("/")
+                    .into()
+            })
+            .filter(|folder: &String| !folder.is_empty());
+    }
+
+    /// 是否包含全部 `tags`，并位于 `folder` 或其子文件夹中
+    pub fn matches_filter(&self, tags: &[String], folder: Option<&str>) -> bool {
+        let has_tags = tags
+            .iter()
+            .all(|tag| self.tags.as_ref().is_some_and(|own| own.contains(tag)));
+        let in_folder = folder.is_none_or(|folder| {
+            let folder = folder.trim_matches('/');
+            self.folder
+                .as_deref()
+                .is_some_and(|own| own == folder || own.strip_prefix(folder).is_some_and(|rest| rest.starts_with('/')))
+        });
+        has_tags && in_folder
+    }
+
     /// ## Local type
     /// create a new item from name/desc
     pub async fn from_local(
@@ -248,6 +590,7 @@ impl PrfItem {
             home: None,
             updated: Some(chrono::Local::now().timestamp() as usize),
             file_data: Some(file_data.unwrap_or_else(|| tmpl::ITEM_LOCAL.into())),
+            ..Default::default()
         })
     }
 
@@ -259,6 +602,21 @@ impl PrfItem {
         desc: Option<&String>,
         option: Option<&PrfOption>,
     ) -> Result<Self> {
+        match Self::from_url_conditional(url, name, desc, option, None).await? {
+            RemoteFetch::Fetched(item) => Ok(*item),
+            RemoteFetch::NotModified(_) => bail!("unexpected 304 response to an unconditional request"),
+        }
+    }
+
+    /// ## Remote type
+    /// 携带上次的 `ETag` / `Last-Modified` 发送条件请求
+    pub async fn from_url_conditional(
+        url: &str,
+        name: Option<&String>,
+        desc: Option<&String>,
+        option: Option<&PrfOption>,
+        previous: Option<&Self>,
+    ) -> Result<RemoteFetch> {
         let with_proxy = option.is_some_and(|o| o.with_proxy.unwrap_or(false));
         let self_proxy = option.is_some_and(|o| o.self_proxy.unwrap_or(false));
         let accept_invalid_certs = option.is_some_and(|o| o.danger_accept_invalid_certs.unwrap_or(false));
@@ -277,20 +635,38 @@ impl PrfItem {
             ProxyType::Localhost
         } else if with_proxy {
             ProxyType::System
+        } else if let Some(node) = option.and_then(|o| o.fetch_node.as_ref()) {
+            let url = fetch_node::fetch_node_proxy_url(node)
+                .await
+                .ok_or_else(|| anyhow::anyhow!("node \"{}\" is not available in the running core", node.name))?;
+            ProxyType::Custom(url)
+        } else if let Some(url) = option
+            .and_then(|o| o.fetch_proxy.clone())
+            .filter(|url| !url.trim().is_empty())
+        {
+            ProxyType::Custom(url)
         } else {
             ProxyType::None
         };
 
         let url = fix_dirty_url(url)?;
 
+        let mut headers = option.map(PrfOption::request_headers).transpose()?.unwrap_or_default();
+        let conditional = previous.map_or_else(HeaderMap::new, |previous| {
+            conditional_headers(previous.etag.as_deref(), previous.last_modified.as_deref())
+        });
+        let is_conditional = !conditional.is_empty();
+        headers.extend(conditional);
+
         // 使用网络管理器发送请求
         let resp = match NetworkManager::new()
-            .get_with_interrupt(
+            .get_with_headers(
                 url.as_str(),
                 proxy_type,
                 Some(timeout),
                 user_agent.clone(),
                 accept_invalid_certs,
+                &headers,
             )
             .await
         {
@@ -302,34 +678,18 @@ impl PrfItem {
         };
 
         let status_code = resp.status();
+        if status_code == StatusCode::NOT_MODIFIED && is_conditional {
+            return Ok(RemoteFetch::NotModified(subscription_userinfo(resp.headers())));
+        }
         if !status_code.is_success() {
             bail!("failed to fetch remote profile with status {status_code}")
         }
 
         let header = resp.headers();
+        let etag = header.get(ETAG).and_then(|v| v.to_str().ok()).map(Into::into);
+        let last_modified = header.get(LAST_MODIFIED).and_then(|v| v.to_str().ok()).map(Into::into);
 
-        // parse the Subscription UserInfo
-        let extra;
-        'extra: {
-            for (k, v) in header.iter() {
-                let key_lower = k.as_str().to_ascii_lowercase();
-                // Accept standard custom-metadata prefixes (x-amz-meta-, x-obs-meta-, x-cos-meta-, etc.).
-                if key_lower
-                    .strip_suffix("subscription-userinfo")
-                    .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('-'))
-                {
-                    let sub_info = v.to_str().unwrap_or("");
-                    extra = Some(PrfExtra {
-                        upload: help::parse_str(sub_info, "upload").unwrap_or(0),
-                        download: help::parse_str(sub_info, "download").unwrap_or(0),
-                        total: help::parse_str(sub_info, "total").unwrap_or(0),
-                        expire: help::parse_str(sub_info, "expire").unwrap_or(0),
-                    });
-                    break 'extra;
-                }
-            }
-            extra = None;
-        }
+        let extra = subscription_userinfo(header);
 
         // parse the Content-Disposition
         let filename = match header.get("Content-Disposition") {
@@ -417,7 +777,7 @@ impl PrfItem {
             groups = groups_item.uid.clone();
         }
 
-        Ok(Self {
+        Ok(RemoteFetch::Fetched(Box::new(Self {
             uid: Some(uid),
             itype: Some("remote".into()),
             name: Some(name),
@@ -434,12 +794,21 @@ impl PrfItem {
                 proxies,
                 groups,
                 allow_auto_update,
+                update_cron: option.and_then(|o| o.update_cron.clone()),
+                update_jitter: option.and_then(|o| o.update_jitter),
+                headers: option.and_then(|o| o.headers.clone()),
+                auth: option.and_then(|o| o.auth.clone()),
+                fetch_proxy: option.and_then(|o| o.fetch_proxy.clone()),
+                fetch_node: option.and_then(|o| o.fetch_node.clone()),
                 ..PrfOption::default()
             }),
             home,
             updated: Some(chrono::Local::now().timestamp() as usize),
+            etag,
+            last_modified,
             file_data: Some(data.into()),
-        })
+            ..Default::default()
+        })))
     }
 
     /// ## Merge type (enhance)
@@ -575,6 +944,39 @@ impl PrfItem {
     pub fn current_groups(&self) -> Option<&String> {
         self.option.as_ref().and_then(|o| o.groups.as_ref())
     }
+
+    /// 更新订阅时依次尝试的地址：主地址在前，镜像按顺序在后
+    /// 主地址持续失败时，上次成功的镜像提前到主地址之前，并定期让主地址重新排在最前
+    pub fn update_urls(&self, primary: &str) -> Vec<String> {
+        let mirrors = self.mirrors.as_deref().unwrap_or_default();
+        let mut urls: Vec<String> = Vec::with_capacity(mirrors.len() + 1);
+
+        let failures = self.primary_failures.unwrap_or(0);
+        let promote = failures >= MIRROR_PROMOTE_AFTER && !failures.is_multiple_of(PRIMARY_PROBE_EVERY);
+        if let Some(promoted) = self
+            .active_mirror
+            .as_ref()
+            .filter(|mirror| promote && mirrors.contains(*mirror))
+        {
+            urls.push(promoted.clone());
+        }
+        urls.push(primary.into());
+        for mirror in mirrors {
+            if !mirror.trim().is_empty() && !urls.contains(mirror) {
+                urls.push(mirror.clone());
+            }
+        }
+        urls
+    }
+
+    /// 本次更新后的 `primary_failures`，主地址成功时清零，否则加一
+    pub const fn next_primary_failures(&self, primary_succeeded: bool) -> u32 {
+        match (primary_succeeded, self.primary_failures) {
+            (true, _) => 0,
+            (false, Some(failures)) => failures.saturating_add(1),
+            (false, None) => 1,
+        }
+    }
 }
 
 // 向前兼容，默认为订阅启用自动更新
@@ -611,3 +1013,148 @@ fn fix_dirty_url(input: &str) -> Result<Url> {
 
     Ok(url)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_subscription_userinfo() {
+        let mut header = HeaderMap::new();
+        assert!(subscription_userinfo(&header).is_none());
+
+        header.insert(
+            HeaderName::from_static("x-amz-meta-subscription-userinfo"),
+            HeaderValue::from_static("upload=1; download=2; total=30; expire=1700000000"),
+        );
+        let extra = subscription_userinfo(&header);
+        assert_eq!(
+            extra.map(|extra| (extra.upload, extra.download, extra.total, extra.expire)),
+            Some((1, 2, 30, 1_700_000_000))
+        );
+
+        header.clear();
+        header.insert(
+            HeaderName::from_static("x-subscription-userinfo-legacy"),
+            HeaderValue::from_static("upload=1"),
+        );
+        assert!(subscription_userinfo(&header).is_none());
+    }
+
+    #[test]
+    fn build_request_headers() {
+        let option = PrfOption {
+            headers: Some(BTreeMap::from([("X-Device-Id".into(), " device-1 ".into())])),
+            auth: Some(PrfAuth::Basic {
+                username: "user".into(),
+                password: "pass".into(),
+            }),
+            ..PrfOption::default()
+        };
+        let headers = option.request_headers().unwrap_or_default();
+        assert_eq!(
+            headers.get("x-device-id").and_then(|v| v.to_str().ok()),
+            Some("device-1")
+        );
+        assert_eq!(
+            headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()),
+            Some("Basic dXNlcjpwYXNz")
+        );
+
+        let invalid = PrfOption {
+            headers: Some(BTreeMap::from([("X-Token".into(), "secret\nvalue".into())])),
+            ..PrfOption::default()
+        };
+        let err = invalid
+            .request_headers()
+            .err()
+            .map(|err| err.to_string())
+            .unwrap_or_default();
+        assert!(err.contains("X-Token") && !err.contains("secret"));
+    }
+
+    #[test]
+    fn group_by_tags_and_folder() {
+        let mut item = PrfItem::default();
+        item.set_grouping(
+            Some(vec![" work ".into(), "".into(), "fast".into(), "work".into()]),
+            Some("/office/ backup//".into()),
+        );
+        assert_eq!(item.tags, Some(vec!["work".into(), "fast".into()]));
+        assert_eq!(item.folder, Some("office/backup".into()));
+
+        assert!(item.matches_filter(&[], None));
+        assert!(item.matches_filter(&["work".into()], Some("office")));
+        assert!(item.matches_filter(&["work".into(), "fast".into()], Some("office/backup/")));
+        assert!(!item.matches_filter(&["home".into()], None));
+        assert!(!item.matches_filter(&[], Some("off")));
+
+        item.set_grouping(Some(vec![" ".into()]), Some("/".into()));
+        assert_eq!((item.tags, item.folder), (None, None));
+    }
+
+    #[test]
+    fn promote_mirror_after_primary_failures() {
+        let mut item = PrfItem {
+            mirrors: Some(vec![
+                "https://b.example.com".into(),
+                "https://c.example.com".into(),
+                "".into(),
+            ]),
+            active_mirror: Some("https://c.example.com".into()),
+            primary_failures: Some(1),
+            ..PrfItem::default()
+        };
+        assert_eq!(
+            item.update_urls("https://a.example.com"),
+            vec![
+                "https://a.example.com",
+                "https://b.example.com",
+                "https://c.example.com"
+            ]
+        );
+
+        item.primary_failures = Some(MIRROR_PROMOTE_AFTER);
+        assert_eq!(
+            item.update_urls("https://a.example.com"),
+            vec![
+                "https://c.example.com",
+                "https://a.example.com",
+                "https://b.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn demote_mirror_when_primary_recovers() {
+        let primary = "https://a.example.com";
+        let mirror = "https://b.example.com";
+        let mut item = PrfItem {
+            mirrors: Some(vec![mirror.into()]),
+            ..PrfItem::default()
+        };
+
+        // 主地址不可用，每次都由镜像完成更新
+        let mut probes = 0;
+        for _ in 0..12 {
+            let urls = item.update_urls(primary);
+            if urls[0] == primary {
+                probes += 1;
+            }
+            item.primary_failures = Some(item.next_primary_failures(false));
+            item.active_mirror = Some(mirror.into());
+        }
+        // 前 3 次及之后每 5 次都先尝试主地址
+        assert_eq!(item.primary_failures, Some(12));
+        assert_eq!(probes, 3 + 2);
+
+        // 镜像优先期间仍会定期探测主地址，主地址成功后取消镜像优先
+        while item.update_urls(primary)[0] != primary {
+            item.primary_failures = Some(item.next_primary_failures(false));
+        }
+        item.primary_failures = Some(item.next_primary_failures(true));
+        item.active_mirror = None;
+        assert_eq!(item.primary_failures, Some(0));
+        assert_eq!(item.update_urls(primary), vec![primary, mirror]);
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Serialize;
+use smartstring::alias::String;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileUpdateStatus {
+    /// new content was downloaded
+    Updated,
+    /// the server answered `304 Not Modified`
+    NotModified,
+    /// not a remote profile, or auto update is disabled
+    #[default]
+    Skipped,
+    /// every url and proxy route failed
+    Failed,
+}
+
+/// Result of updating a single profile
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProfileUpdateReport {
+    pub uid: String,
+    pub name: String,
+    pub status: ProfileUpdateStatus,
+
+    /// proxy route of the successful request: `direct`, `clash_proxy` or `system_proxy`
+    pub route: Option<String>,
+
+    /// the mirror that served the update, None for the primary url
+    pub mirror: Option<String>,
+
+    /// reason of the failure
+    pub error: Option<String>,
+
+    pub is_current: bool,
+}
+
+impl ProfileUpdateReport {
+    pub fn new(uid: &String, name: Option<&String>) -> Self {
+        Self {
+            uid: uid.clone(),
+            name: name.unwrap_or(uid).clone(),
+            ..Self::default()
+        }
+    }
+
+    /// 当前订阅内容发生变化或更新失败时需要重新生成内核配置
+    pub const fn needs_refresh(&self) -> bool {
+        self.is_current
+            && !matches!(
+                self.status,
+                ProfileUpdateStatus::NotModified | ProfileUpdateStatus::Skipped
+            )
+    }
+}
@@ -0,0 +1,177 @@
+use super::PrfExtra;
+use crate::utils::dirs;
+use anyhow::{Context as _, Result, bail};
+use clash_verge_logging::{Type, logging};
+use serde::{Deserialize, Serialize};
+use smartstring::alias::String;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio::{fs, sync::Mutex};
+
+/// 每个订阅默认保留的历史版本数
+pub const DEFAULT_HISTORY_LIMIT: usize = 5;
+
+const INDEX_FILE: &str = "index.yaml";
+
+// 定时更新与手动更新可能同时写入 index.yaml
+static HISTORY_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// A stored version of a remote profile file
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProfileVersion {
+    /// millisecond timestamp, also the file stem of the stored copy
+    pub id: String,
+
+    /// fetch time in seconds
+    pub time: i64,
+
+    /// how the version was obtained: `direct`, `clash_proxy`, `system_proxy` or `previous`
+    pub status: String,
+
+    /// subscription user info at the time of fetching
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra: Option<PrfExtra>,
+
+    /// file size in bytes
+    pub size: usize,
+}
+
+fn history_dir(uid: &str) -> Result<PathBuf> {
+    if uid.is_empty() || !uid.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        bail!("invalid profile uid \"{uid}\"");
+    }
+    Ok(dirs::app_profile_history_dir()?.join(uid))
+}
+
+fn version_path(uid: &str, id: &str) -> Result<PathBuf> {
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        bail!("invalid profile version \"{id}\"");
+    }
+    Ok(history_dir(uid)?.join(format!("{id}.yaml")))
+}
+
+async fn load_index(dir: &Path) -> Vec<ProfileVersion> {
+    let Ok(content) = fs::read_to_string(dir.join(INDEX_FILE)).await else {
+        return Vec::new();
+    };
+    serde_yaml_ng::from_str(&content).unwrap_or_else(|err| {
+        logging!(warn, Type::Config, "Warning: 订阅历史索引损坏 {:?}: {}", dir, err);
+        Vec::new()
+    })
+}
+
+async fn save_index(dir: &Path, versions: &[ProfileVersion]) -> Result<()> {
+    fs::write(dir.join(INDEX_FILE), serde_yaml_ng::to_string(versions)?).await?;
+    Ok(())
+}
+
+/// 删除超出保留数量的旧版本，返回删除的文件数
+async fn prune(dir: &Path, versions: &mut Vec<ProfileVersion>, limit: usize) -> usize {
+    let mut deleted = 0;
+    while versions.len() > limit {
+        let Some(version) = versions.pop() else {
+            break;
+        };
+        if fs::remove_file(dir.join(format!("{}.yaml", version.id))).await.is_ok() {
+            deleted += 1;
+        }
+    }
+    deleted
+}
+
+/// List stored versions of a profile, newest first
+pub async fn list_versions(uid: &str) -> Result<Vec<ProfileVersion>> {
+    Ok(load_index(&history_dir(uid)?).await)
+}
+
+/// Read the content of a stored version
+pub async fn read_version(uid: &str, id: &str) -> Result<std::string::String> {
+    let path = version_path(uid, id)?;
+    fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("profile version \"{id}\" not found"))
+}
+
+/// 保存一个新版本，内容与最新版本相同时只更新其元数据
+pub async fn record_version(uid: &str, content: &str, mut version: ProfileVersion, limit: usize) -> Result<()> {
+    if limit == 0 {
+        return Ok(());
+    }
+
+    let _guard = HISTORY_LOCK.lock().await;
+    let dir = history_dir(uid)?;
+    fs::create_dir_all(&dir).await?;
+    let mut versions = load_index(&dir).await;
+
+    if let Some(latest) = versions.first_mut()
+        && fs::read_to_string(dir.join(format!("{}.yaml", latest.id)))
+            .await
+            .is_ok_and(|previous| previous == content)
+    {
+        latest.time = version.time;
+        latest.status = version.status;
+        latest.extra = version.extra;
+    } else {
+        let mut id = chrono::Local::now().timestamp_millis();
+        if let Some(latest) = versions.first()
+            && let Ok(latest_id) = latest.id.parse::<i64>()
+        {
+            id = id.max(latest_id + 1);
+        }
+        version.id = id.to_string().into();
+        version.size = content.len();
+        fs::write(dir.join(format!("{id}.yaml")), content).await?;
+        versions.insert(0, version);
+        prune(&dir, &mut versions, limit).await;
+    }
+
+    save_index(&dir, &versions).await
+}
+
+/// 清理已删除订阅的历史记录，并按保留数量裁剪，返回 (文件总数, 删除数, 失败数)
+pub async fn cleanup(active_uids: &HashSet<&str>, limit: usize) -> Result<(usize, usize, usize)> {
+    let root = dirs::app_profile_history_dir()?;
+    if !root.exists() {
+        return Ok((0, 0, 0));
+    }
+
+    let _guard = HISTORY_LOCK.lock().await;
+    let (mut total, mut deleted, mut failed) = (0, 0, 0);
+    let mut entries = fs::read_dir(&root).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut versions = load_index(&dir).await;
+        total += versions.len();
+
+        let is_active = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|uid| active_uids.contains(uid));
+        if !is_active || limit == 0 {
+            match fs::remove_dir_all(&dir).await {
+                Ok(_) => {
+                    deleted += versions.len();
+                    logging!(debug, Type::Config, "已清理订阅历史: {:?}", dir);
+                }
+                Err(e) => {
+                    failed += versions.len();
+                    logging!(warn, Type::Config, "Warning: 清理订阅历史失败: {:?} - {e}", dir);
+                }
+            }
+            continue;
+        }
+
+        if versions.len() > limit {
+            deleted += prune(&dir, &mut versions, limit).await;
+            save_index(&dir, &versions).await?;
+        }
+    }
+
+    Ok((total, deleted, failed))
+}
@@ -1,7 +1,13 @@
-use super::{PrfOption, prfitem::PrfItem};
-use crate::utils::{
-    dirs::{self, PathBufExec as _},
-    help,
+use super::{
+    Config, PrfChangeReport, PrfOption, PrfQuotaAlert, PrfSelected, prfchange, prfitem::PrfItem, profile_history,
+    selected,
+};
+use crate::{
+    core::handle,
+    utils::{
+        dirs::{self, PathBufExec as _},
+        help,
+    },
 };
 use anyhow::{Context as _, Result, bail};
 use clash_verge_logging::{Type, logging};
@@ -24,6 +30,7 @@ pub struct IProfiles {
 pub struct IProfilePreview<'a> {
     pub uid: &'a String,
     pub name: &'a String,
+    pub folder: Option<&'a String>,
     pub is_current: bool,
 }
 
@@ -44,6 +51,9 @@ macro_rules! patch {
 }
 
 impl IProfiles {
+    /// profiles.yaml 的结构版本，字段含义发生不兼容变化时递增
+    pub const SCHEMA_VERSION: u32 = 1;
+
     // Helper to find and remove an item by uid from the items vec, returning its file name (if any).
     fn take_item_file_by_uid(items: &mut Vec<PrfItem>, target_uid: Option<&str>) -> Option<String> {
         let index = items.iter().position(|item| item.uid.as_deref() == target_uid)?;
@@ -200,10 +210,20 @@ impl IProfiles {
                 patch!(each, item, desc);
                 patch!(each, item, file);
                 patch!(each, item, url);
+                patch!(each, item, mirrors);
+                patch!(each, item, tags);
+                patch!(each, item, folder);
+                patch!(each, item, linked_path);
                 patch!(each, item, selected);
                 patch!(each, item, extra);
                 patch!(each, item, updated);
                 patch!(each, item, option);
+                // 传入空的标签或文件夹时清除
+                let (tags, folder) = (each.tags.take(), each.folder.take());
+                each.set_grouping(tags, folder);
+                if each.linked_path.as_ref().is_some_and(|path| path.trim().is_empty()) {
+                    each.linked_path = None;
+                }
 
                 self.items = Some(items);
                 return self.save_file().await;
@@ -232,6 +252,10 @@ impl IProfiles {
                     each.extra = item.extra;
                     each.updated = item.updated;
                     each.home = item.home.to_owned();
+                    each.etag = item.etag.take();
+                    each.last_modified = item.last_modified.take();
+                    each.update_failures = item.update_failures;
+                    each.update_error = item.update_error.take();
                     each.option = PrfOption::merge(each.option.as_ref(), item.option.as_ref());
                     // save the file data
                     // move the field value after save
@@ -245,9 +269,26 @@ impl IProfiles {
 
                         let path = dirs::app_profiles_dir()?.join(file.as_str());
 
+                        // 更新前的内容，用于生成变更报告及恢复被重命名节点的选择
+                        let previous = fs::read_to_string(&path).await.ok();
+
                         fs::write(&path, file_data.as_bytes())
                             .await
                             .with_context(|| format!("failed to write to file \"{file}\""))?;
+
+                        if let Some(previous) = previous
+                            && let Ok(previous) = serde_yaml_ng::from_str::<Mapping>(&previous)
+                            && let Ok(current) = serde_yaml_ng::from_str::<Mapping>(&file_data)
+                        {
+                            let name = each.name.clone().unwrap_or_else(|| uid.clone());
+                            let report = prfchange::compute_change_report(&previous, &current);
+                            report_changes(uid, &name, &report);
+                            each.change_report = Some(report);
+
+                            if let Some(selected) = each.selected.as_ref().filter(|selected| !selected.is_empty()) {
+                                each.selected = Some(restore_selected(&name, &previous, &current, selected));
+                            }
+                        }
                     }
 
                     break;
@@ -258,9 +299,86 @@ impl IProfiles {
         self.save_file().await
     }
 
+    /// 记录本次更新使用的镜像及主地址连续失败次数
+    pub async fn update_mirror_state(
+        &mut self,
+        uid: &String,
+        active_mirror: Option<String>,
+        primary_failures: u32,
+    ) -> Result<()> {
+        let Some(item) = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|each| each.uid.as_ref() == Some(uid)))
+        else {
+            bail!("failed to find the profile item \"uid:{uid}\"");
+        };
+        if item.active_mirror == active_mirror && item.primary_failures.unwrap_or(0) == primary_failures {
+            return Ok(());
+        }
+        item.active_mirror = active_mirror;
+        item.primary_failures = (primary_failures > 0).then_some(primary_failures);
+        self.save_file().await
+    }
+
+    /// 记录已发送的流量及到期提醒
+    pub async fn update_quota_alert(&mut self, uid: &String, alert: Option<PrfQuotaAlert>) -> Result<()> {
+        let Some(item) = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|each| each.uid.as_ref() == Some(uid)))
+        else {
+            bail!("failed to find the profile item \"uid:{uid}\"");
+        };
+        if item.quota_alert == alert {
+            return Ok(());
+        }
+        item.quota_alert = alert;
+        self.save_file().await
+    }
+
+    /// 记录一次定时更新失败，返回连续失败次数
+    pub async fn record_update_failure(&mut self, uid: &String, error: String) -> Result<u32> {
+        let Some(item) = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|each| each.uid.as_ref() == Some(uid)))
+        else {
+            bail!("failed to find the profile item \"uid:{uid}\"");
+        };
+        let failures = item.update_failures.unwrap_or(0).saturating_add(1);
+        item.update_failures = Some(failures);
+        item.update_error = Some(error);
+        self.save_file().await?;
+        Ok(failures)
+    }
+
     /// delete item
     /// if delete the current then return true
     pub async fn delete_item(&mut self, uid: &String) -> Result<bool> {
+        let deleted_current = self.remove_item(uid).await?;
+        self.save_file().await?;
+        Ok(deleted_current)
+    }
+
+    /// 批量删除订阅，单个失败时继续删除其余订阅，最后统一保存一次
+    /// 返回被删除的当前订阅及各订阅的错误信息
+    pub async fn delete_items(&mut self, uids: &[String]) -> Result<(Option<String>, Vec<String>)> {
+        let mut deleted_current = None;
+        let mut errors = Vec::new();
+        for uid in uids {
+            match self.remove_item(uid).await {
+                Ok(true) => deleted_current = Some(uid.clone()),
+                Ok(false) => {}
+                Err(e) => errors.push(format!("{uid}: {e}").into()),
+            }
+        }
+        self.save_file().await?;
+        Ok((deleted_current, errors))
+    }
+
+    /// 移除订阅及其附属的文件，不保存
+    async fn remove_item(&mut self, uid: &String) -> Result<bool> {
         let current = self.current.as_ref().unwrap_or(uid);
         let current = current.clone();
         let delete_uids = {
@@ -303,7 +421,6 @@ impl IProfiles {
         }
 
         self.items = Some(items);
-        self.save_file().await?;
         Ok(current == *uid)
     }
 
@@ -329,7 +446,7 @@ impl IProfiles {
         self.current.as_ref() == Some(index)
     }
 
-    /// 获取所有的profiles(uid，名称, 是否为 current)
+    /// 获取所有的profiles(uid，名称, 文件夹, 是否为 current)
     pub fn profiles_preview(&self) -> Option<Vec<IProfilePreview<'_>>> {
         self.items.as_ref().map(|items| {
             items
@@ -337,7 +454,12 @@ impl IProfiles {
                 .filter_map(|e| {
                     if let (Some(uid), Some(name)) = (e.uid.as_ref(), e.name.as_ref()) {
                         let is_current = self.is_current_profile_index(uid);
-                        let preview = IProfilePreview { uid, name, is_current };
+                        let preview = IProfilePreview {
+                            uid,
+                            name,
+                            folder: e.folder.as_ref(),
+                            is_current,
+                        };
                         Some(preview)
                     } else {
                         None
@@ -347,6 +469,55 @@ impl IProfiles {
         })
     }
 
+    /// 筛选包含全部 `tags` 且位于 `folder` 或其子文件夹中的订阅
+    pub fn filter_items(&self, tags: &[String], folder: Option<&str>) -> Vec<&PrfItem> {
+        self.items
+            .iter()
+            .flatten()
+            .filter(|item| item.matches_filter(tags, folder))
+            .collect()
+    }
+
+    /// 为多个订阅批量添加或移除标签
+    pub async fn tag_items(&mut self, uids: &[String], add: &[String], remove: &[String]) -> Result<()> {
+        for item in self.items.iter_mut().flatten() {
+            if !item.uid.as_ref().is_some_and(|uid| uids.contains(uid)) {
+                continue;
+            }
+            let mut tags = item.tags.take().unwrap_or_default();
+            tags.retain(|tag| !remove.contains(tag));
+            tags.extend(add.iter().cloned());
+            let folder = item.folder.take();
+            item.set_grouping(Some(tags), folder);
+        }
+        self.save_file().await
+    }
+
+    /// 合并从备份中恢复的订阅项，相同 uid 的替换，其余追加，不影响现有的其它订阅
+    pub fn merge_items(&mut self, restored: Vec<PrfItem>) {
+        let items = self.items.get_or_insert_with(Vec::new);
+        for item in restored {
+            match items
+                .iter_mut()
+                .find(|existing| existing.uid.is_some() && existing.uid == item.uid)
+            {
+                Some(existing) => *existing = item,
+                None => items.push(item),
+            }
+        }
+        if self.current.is_none() {
+            self.current = items
+                .iter()
+                .find(|item| matches!(item.itype.as_deref(), Some("remote" | "local")))
+                .and_then(|item| item.uid.clone());
+        }
+    }
+
+    pub async fn merge_restored_items(&mut self, restored: Vec<PrfItem>) -> Result<()> {
+        self.merge_items(restored);
+        self.save_file().await
+    }
+
     /// 通过 uid 获取名称
     pub fn get_name_by_uid(&self, uid: &String) -> Option<&String> {
         if let Some(items) = &self.items {
@@ -413,6 +584,28 @@ impl IProfiles {
             }
         }
 
+        // 订阅历史版本：删除已不存在的订阅的历史，并按保留数量裁剪
+        let remote_uids: HashSet<&str> = self
+            .items
+            .iter()
+            .flatten()
+            .filter(|item| item.itype.as_deref() == Some("remote"))
+            .filter_map(|item| item.uid.as_deref())
+            .collect();
+        let history_limit = Config::verge()
+            .await
+            .latest_arc()
+            .profile_history_limit
+            .unwrap_or(profile_history::DEFAULT_HISTORY_LIMIT);
+        match profile_history::cleanup(&remote_uids, history_limit).await {
+            Ok((total, deleted, failed)) => {
+                total_files += total;
+                deleted_files += deleted;
+                failed_deletions += failed;
+            }
+            Err(e) => logging!(warn, Type::Config, "Warning: 清理订阅历史失败: {e}"),
+        }
+
         let result = CleanupResult {
             total_files,
             deleted_files,
@@ -563,6 +756,16 @@ pub async fn profiles_delete_item_safe(index: &String) -> Result<bool> {
         .await
 }
 
+pub async fn profiles_delete_items_safe(uids: &[String]) -> Result<(Option<String>, Vec<String>)> {
+    Config::profiles()
+        .await
+        .with_data_modify(|mut profiles| async move {
+            let result = profiles.delete_items(uids).await?;
+            Ok((profiles, result))
+        })
+        .await
+}
+
 pub async fn profiles_reorder_safe(active_id: &String, over_id: &String) -> Result<()> {
     Config::profiles()
         .await
@@ -583,6 +786,124 @@ pub async fn profiles_save_file_safe() -> Result<()> {
         .await
 }
 
+pub async fn profiles_merge_restored_items_safe(items: Vec<PrfItem>) -> Result<()> {
+    Config::profiles()
+        .await
+        .with_data_modify(|mut profiles| async move {
+            profiles.merge_restored_items(items).await?;
+            Ok((profiles, ()))
+        })
+        .await
+}
+
+/// 记录订阅变更，节点被移除时通知前端
+fn report_changes(uid: &String, profile_name: &str, report: &PrfChangeReport) {
+    if report.is_empty() {
+        logging!(info, Type::Config, "[订阅更新] {} 节点与代理组无变化", profile_name);
+        return;
+    }
+
+    logging!(
+        info,
+        Type::Config,
+        "[订阅更新] {} 变更: {}",
+        profile_name,
+        report.summary()
+    );
+    handle::Handle::notify_profile_change_report(uid, report);
+    if !report.removed.is_empty() {
+        handle::Handle::notice_message(
+            "update_changes::nodes_removed",
+            format!("{profile_name} - {}", report.summary()),
+        );
+    }
+}
+
+/// 订阅更新后重新匹配各代理组选中的节点，无法恢复的选择会通知前端
+fn restore_selected(
+    profile_name: &str,
+    previous: &Mapping,
+    current: &Mapping,
+    selected: &[PrfSelected],
+) -> Vec<PrfSelected> {
+    let result = selected::remap_selected(previous, current, selected);
+    for (group, from, to) in &result.remapped {
+        logging!(
+            info,
+            Type::Config,
+            "[订阅更新] {} 代理组 {} 的选择已重新匹配: {} -> {}",
+            profile_name,
+            group,
+            from,
+            to
+        );
+    }
+    if !result.lost.is_empty() {
+        let lost = result
+            .lost
+            .iter()
+            .map(|(group, now)| format!("{group}: {now}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        logging!(
+            warn,
+            Type::Config,
+            "Warning: [订阅更新] {} 无法恢复以下节点选择: {}",
+            profile_name,
+            lost
+        );
+        handle::Handle::notice_message("update_selected::not_restored", format!("{profile_name} - {lost}"));
+    }
+
+    result.selected
+}
+
+pub async fn profiles_draft_update_mirror_state_safe(
+    index: &String,
+    active_mirror: Option<String>,
+    primary_failures: u32,
+) -> Result<()> {
+    Config::profiles()
+        .await
+        .with_data_modify(|mut profiles| async move {
+            profiles
+                .update_mirror_state(index, active_mirror, primary_failures)
+                .await?;
+            Ok((profiles, ()))
+        })
+        .await
+}
+
+pub async fn profiles_draft_update_quota_alert_safe(index: &String, alert: Option<PrfQuotaAlert>) -> Result<()> {
+    Config::profiles()
+        .await
+        .with_data_modify(|mut profiles| async move {
+            profiles.update_quota_alert(index, alert).await?;
+            Ok((profiles, ()))
+        })
+        .await
+}
+
+pub async fn profiles_draft_record_update_failure_safe(index: &String, error: String) -> Result<u32> {
+    Config::profiles()
+        .await
+        .with_data_modify(|mut profiles| async move {
+            let failures = profiles.record_update_failure(index, error).await?;
+            Ok((profiles, failures))
+        })
+        .await
+}
+
+pub async fn profiles_tag_items_safe(uids: &[String], add: &[String], remove: &[String]) -> Result<()> {
+    Config::profiles()
+        .await
+        .with_data_modify(|mut profiles| async move {
+            profiles.tag_items(uids, add, remove).await?;
+            Ok((profiles, ()))
+        })
+        .await
+}
+
 pub async fn profiles_draft_update_item_safe(index: &String, item: &mut PrfItem) -> Result<()> {
     Config::profiles()
         .await
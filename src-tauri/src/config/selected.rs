@@ -0,0 +1,367 @@
+use super::{PrfSelected, prfchange::proxy_nodes};
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use std::collections::HashMap;
+
+/// 名称相似度低于该值时不做自动映射
+const MIN_NAME_SIMILARITY: f64 = 0.8;
+
+/// 常见地区名称统一为缩写，单词及双词短语
+const REGION_ALIASES: &[(&str, &str)] = &[
+    ("hongkong", "hk"),
+    ("香港", "hk"),
+    ("taiwan", "tw"),
+    ("台湾", "tw"),
+    ("臺灣", "tw"),
+    ("japan", "jp"),
+    ("日本", "jp"),
+    ("singapore", "sg"),
+    ("新加坡", "sg"),
+    ("狮城", "sg"),
+    ("korea", "kr"),
+    ("韩国", "kr"),
+    ("america", "us"),
+    ("usa", "us"),
+    ("美国", "us"),
+    ("britain", "uk"),
+    ("gb", "uk"),
+    ("英国", "uk"),
+    ("germany", "de"),
+    ("德国", "de"),
+];
+const REGION_PHRASES: &[(&str, &str, &str)] = &[
+    ("hong", "kong", "hk"),
+    ("united", "states", "us"),
+    ("united", "kingdom", "uk"),
+    ("great", "britain", "uk"),
+    ("south", "korea", "kr"),
+];
+
+/// Result of re-matching the selections against an updated profile
+#[derive(Debug, Default)]
+pub struct SelectedRemap {
+    pub selected: Vec<PrfSelected>,
+    /// (group, previous node, new node)
+    pub remapped: Vec<(String, String, String)>,
+    /// (group, node) that could not be restored, the entries are kept as is
+    pub lost: Vec<(String, String)>,
+}
+
+struct GroupInfo {
+    members: Vec<String>,
+    /// the group also pulls nodes from proxy providers, which are not visible here
+    has_providers: bool,
+}
+
+/// 订阅更新后节点可能被重命名，按以下顺序为每个代理组重新匹配选中的节点：
+/// 1. 新配置中仍存在同名节点，保持不变
+/// 2. 旧节点的 type/server/port 与新节点一致
+/// 3. 归一化后的名称相同或足够相似
+pub fn remap_selected(old: &Mapping, new: &Mapping, selected: &[PrfSelected]) -> SelectedRemap {
+    let groups = collect_groups(new);
+    let identities = |config: &Mapping| -> HashMap<String, String> {
+        proxy_nodes(config)
+            .into_iter()
+            .map(|node| (node.name.clone(), node.identity()))
+            .collect()
+    };
+    let old_identities = identities(old);
+    let new_identities = identities(new);
+
+    let mut result = SelectedRemap::default();
+    for entry in selected {
+        let (Some(group_name), Some(now)) = (entry.name.as_ref(), entry.now.as_ref()) else {
+            result.selected.push(entry.clone());
+            continue;
+        };
+
+        let Some((group_name, group)) = groups
+            .get_key_value(group_name)
+            .or_else(|| find_by_name(group_name, groups.keys()).and_then(|name| groups.get_key_value(name)))
+        else {
+            result.lost.push((group_name.clone(), now.clone()));
+            result.selected.push(entry.clone());
+            continue;
+        };
+
+        if group.members.contains(now) || group.has_providers {
+            result.selected.push(PrfSelected {
+                name: Some(group_name.clone()),
+                now: Some(now.clone()),
+            });
+            continue;
+        }
+
+        let by_identity = old_identities.get(now).and_then(|identity| {
+            let mut matches = group
+                .members
+                .iter()
+                .filter(|member| new_identities.get(*member) == Some(identity));
+            match (matches.next(), matches.next()) {
+                (Some(found), None) => Some(found),
+                _ => None,
+            }
+        });
+
+        match by_identity.or_else(|| find_by_name(now, group.members.iter())) {
+            Some(found) => {
+                result.remapped.push((group_name.clone(), now.clone(), found.clone()));
+                result.selected.push(PrfSelected {
+                    name: Some(group_name.clone()),
+                    now: Some(found.clone()),
+                });
+            }
+            None => {
+                result.lost.push((group_name.clone(), now.clone()));
+                result.selected.push(entry.clone());
+            }
+        }
+    }
+
+    result
+}
+
+fn collect_groups(config: &Mapping) -> HashMap<String, GroupInfo> {
+    let mut groups = HashMap::new();
+    let Some(seq) = config.get("proxy-groups").and_then(Value::as_sequence) else {
+        return groups;
+    };
+
+    for group in seq.iter().filter_map(Value::as_mapping) {
+        let Some(name) = group.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let members = group
+            .get("proxies")
+            .and_then(Value::as_sequence)
+            .map(|seq| seq.iter().filter_map(Value::as_str).map(Into::into).collect())
+            .unwrap_or_default();
+        let has_providers = group
+            .get("use")
+            .and_then(Value::as_sequence)
+            .is_some_and(|seq| !seq.is_empty())
+            || group.get("include-all").and_then(Value::as_bool).unwrap_or(false)
+            || group
+                .get("include-all-providers")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+        groups.insert(name.into(), GroupInfo { members, has_providers });
+    }
+    groups
+}
+
+/// 在候选名称中查找与 `target` 最相似的唯一一项
+fn find_by_name<'a>(target: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    let target_tokens = tokenize(target);
+    let target_key = target_tokens.join(" ");
+    let target_digits = digits_of(&target_tokens);
+
+    let mut best: Option<(&String, f64)> = None;
+    let mut tied = false;
+    for candidate in candidates {
+        let tokens = tokenize(candidate);
+        // 编号不同的节点视为不同节点，例如 HK 01 与 HK 02
+        if digits_of(&tokens) != target_digits {
+            continue;
+        }
+        let score = similarity(&target_key, &tokens.join(" "));
+        if score < MIN_NAME_SIMILARITY {
+            continue;
+        }
+        match best {
+            Some((_, best_score)) if (score - best_score).abs() < f64::EPSILON => tied = true,
+            Some((_, best_score)) if score < best_score => {}
+            _ => {
+                best = Some((candidate, score));
+                tied = false;
+            }
+        }
+    }
+
+    if tied {
+        None
+    } else {
+        best.map(|(candidate, _)| candidate)
+    }
+}
+
+/// 小写、去除 emoji 与标点、统一地区名称、去掉编号前导零
+fn tokenize(name: &str) -> Vec<std::string::String> {
+    let mut raw = Vec::new();
+    let mut current = std::string::String::new();
+    let mut current_is_digit = false;
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                raw.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let is_digit = c.is_ascii_digit();
+        if !current.is_empty() && is_digit != current_is_digit {
+            raw.push(std::mem::take(&mut current));
+        }
+        current_is_digit = is_digit;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        raw.push(current);
+    }
+
+    let mut tokens = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if let Some(next) = raw.get(i + 1)
+            && let Some((_, _, alias)) = REGION_PHRASES
+                .iter()
+                .find(|(first, second, _)| raw[i] == *first && next == second)
+        {
+            tokens.push((*alias).to_owned());
+            i += 2;
+            continue;
+        }
+
+        let token = &raw[i];
+        let token = match REGION_ALIASES.iter().find(|(name, _)| token == name) {
+            Some((_, alias)) => (*alias).to_owned(),
+            None if token.bytes().all(|b| b.is_ascii_digit()) => {
+                let trimmed = token.trim_start_matches('0');
+                if trimmed.is_empty() {
+                    "0".to_owned()
+                } else {
+                    trimmed.to_owned()
+                }
+            }
+            None => token.clone(),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    tokens
+}
+
+fn digits_of(tokens: &[std::string::String]) -> Vec<&str> {
+    tokens
+        .iter()
+        .filter(|token| token.bytes().all(|b| b.is_ascii_digit()))
+        .map(std::string::String::as_str)
+        .collect()
+}
+
+/// 基于编辑距离的相似度，取值 0.0 ~ 1.0
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    1.0 - prev[b.len()] as f64 / max_len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(content: &str) -> Mapping {
+        serde_yaml_ng::from_str(content).unwrap_or_default()
+    }
+
+    fn selected(group: &str, now: &str) -> PrfSelected {
+        PrfSelected {
+            name: Some(group.into()),
+            now: Some(now.into()),
+        }
+    }
+
+    #[test]
+    fn normalize_region_names() {
+        assert_eq!(tokenize("🇭🇰 Hong Kong 01"), tokenize("HK 1"));
+        assert_eq!(tokenize("香港-02"), vec!["hk", "2"]);
+        assert_eq!(tokenize("United States 03 [x2]"), vec!["us", "3", "x", "2"]);
+    }
+
+    #[test]
+    fn remap_by_server_identity() {
+        let old = yaml(
+            "
+proxies:
+  - { name: HK 01, type: ss, server: a.example.com, port: 443 }
+",
+        );
+        let new = yaml(
+            "
+proxies:
+  - { name: Premium Line A, type: ss, server: A.example.com, port: 443 }
+  - { name: Premium Line B, type: ss, server: b.example.com, port: 443 }
+proxy-groups:
+  - { name: Proxy, type: select, proxies: [Premium Line A, Premium Line B] }
+",
+        );
+
+        let result = remap_selected(&old, &new, &[selected("Proxy", "HK 01")]);
+        assert_eq!(
+            result.remapped,
+            vec![("Proxy".into(), "HK 01".into(), "Premium Line A".into())]
+        );
+        assert!(result.lost.is_empty());
+    }
+
+    #[test]
+    fn remap_by_normalized_name() {
+        let new = yaml(
+            "
+proxy-groups:
+  - { name: 🚀 Proxy, type: select, proxies: [🇭🇰 Hong Kong 01, 🇭🇰 Hong Kong 02, DIRECT] }
+  - { name: Streaming, type: select, proxies: [🇯🇵 Japan 01] }
+",
+        );
+        let current = vec![
+            selected("Proxy", "HK 01"),
+            selected("Streaming", "DIRECT"),
+            selected("Gone", "HK 02"),
+        ];
+
+        let result = remap_selected(&Mapping::new(), &new, &current);
+        assert_eq!(
+            result.selected,
+            vec![
+                selected("🚀 Proxy", "🇭🇰 Hong Kong 01"),
+                selected("Streaming", "DIRECT"),
+                selected("Gone", "HK 02"),
+            ]
+        );
+        assert_eq!(
+            result.lost,
+            vec![("Streaming".into(), "DIRECT".into()), ("Gone".into(), "HK 02".into())]
+        );
+    }
+
+    #[test]
+    fn keep_existing_and_provider_selections() {
+        let new = yaml(
+            "
+proxy-groups:
+  - { name: Proxy, type: select, proxies: [HK 01] }
+  - { name: Auto, type: select, use: [provider] }
+",
+        );
+        let current = vec![selected("Proxy", "HK 01"), selected("Auto", "anything")];
+
+        let result = remap_selected(&Mapping::new(), &new, &current);
+        assert_eq!(result.selected, current);
+        assert!(result.remapped.is_empty() && result.lost.is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::config::Config;
 use crate::{
     config::{DEFAULT_PAC, deserialize_encrypted, serialize_encrypted},
-    utils::{dirs, help},
+    utils::{dirs, help, hosts::HostsImportMode},
 };
 use anyhow::Result;
 use clash_verge_logging::{Type, logging};
@@ -85,6 +85,15 @@ pub struct IVerge {
     /// clash tun mode
     pub enable_tun_mode: Option<bool>,
 
+    /// generate tun route-exclude-address from local interfaces
+    pub tun_auto_route_exclude: Option<bool>,
+
+    /// extra CIDRs appended to tun route-exclude-address
+    pub tun_route_exclude_extra: Option<Vec<String>>,
+
+    /// `include` or `exclude`, generate tun include-interface / exclude-interface (linux only)
+    pub tun_interface_filter: Option<String>,
+
     /// can the app auto startup
     pub enable_auto_launch: Option<bool>,
 
@@ -103,6 +112,19 @@ pub struct IVerge {
     /// enable dns settings - this controls whether dns_config.yaml is applied
     pub enable_dns_settings: Option<bool>,
 
+    /// number of versions kept for each remote profile, 0 disables the history
+    pub profile_history_limit: Option<usize>,
+
+    /// usage percentages of subscription traffic that trigger an alert, empty disables the alert
+    pub subscription_quota_thresholds: Option<Vec<u8>>,
+
+    /// alert when a subscription expires within the days, 0 disables the alert
+    pub subscription_expiry_days: Option<u32>,
+
+    /// hosts lists imported from url, refreshed by the timer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hosts_sources: Option<Vec<IVergeHostsSource>>,
+
     /// always use default bypass
     pub use_default_bypass: Option<bool>,
 
@@ -183,6 +205,24 @@ pub struct IVerge {
     /// Create backups automatically when critical configs change
     pub auto_backup_on_change: Option<bool>,
 
+    /// 各类变更是否触发自动备份，需同时开启 `auto_backup_on_change`
+    pub auto_backup_triggers: Option<IVergeAutoBackupTriggers>,
+
+    /// Upload scheduled automatic backups to WebDAV
+    pub auto_backup_webdav: Option<bool>,
+
+    /// 自动备份写入增量仓库，未变化的文件只保存一份
+    pub auto_backup_incremental: Option<bool>,
+
+    /// 备份保留策略，作用于本地自动备份与 WebDAV 备份
+    pub backup_retention: Option<IVergeBackupRetention>,
+
+    /// Folder used as a backup target, e.g. synced by Syncthing or a mounted network share
+    pub backup_directory: Option<String>,
+
+    /// 监视备份目录，出现其他设备的新备份时提示恢复
+    pub backup_directory_watch: Option<bool>,
+
     /// verge 的各种 port 用于覆盖 clash 的各种 port
     #[cfg(not(target_os = "windows"))]
     pub verge_redir_port: Option<u16>,
@@ -233,6 +273,69 @@ pub struct IVerge {
     )]
     pub webdav_password: Option<String>,
 
+    /// S3 兼容存储的服务地址 (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub s3_endpoint: Option<String>,
+
+    /// S3 存储桶 (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub s3_bucket: Option<String>,
+
+    /// S3 对象键前缀 (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub s3_prefix: Option<String>,
+
+    /// S3 区域 (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub s3_region: Option<String>,
+
+    /// S3 Access Key ID (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub s3_access_key_id: Option<String>,
+
+    /// S3 Secret Access Key (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub s3_secret_access_key: Option<String>,
+
+    /// 备份加密口令 (加密存储)，为空时不加密备份
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub backup_passphrase: Option<String>,
+
     #[cfg(target_os = "macos")]
     pub enable_tray_speed: Option<bool>,
 
@@ -267,6 +370,52 @@ pub struct IVergeTestItem {
     pub url: Option<String>,
 }
 
+/// Which changes create an automatic backup.
+/// Unset global extend triggers are enabled, the others must be turned on explicitly
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IVergeAutoBackupTriggers {
+    /// 保存全局扩展配置 (Merge)
+    pub global_merge: Option<bool>,
+    /// 保存全局扩展脚本 (Script)
+    pub global_script: Option<bool>,
+    /// 覆盖订阅自己的扩展配置/脚本/规则/节点/代理组之前
+    pub profile_enhancement: Option<bool>,
+    /// 删除订阅之前
+    pub profile_delete: Option<bool>,
+    /// 切换内核之前
+    pub core_change: Option<bool>,
+    /// 安装应用更新之前
+    pub app_update: Option<bool>,
+}
+
+/// Backup retention rules, a backup is kept if any rule selects it
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IVergeBackupRetention {
+    /// 保留最近的 N 个备份
+    pub keep_last: Option<usize>,
+    /// 最近 D 天内每天保留最新的一个
+    pub keep_daily: Option<u32>,
+    /// 最近 W 周内每周保留最新的一个
+    pub keep_weekly: Option<u32>,
+}
+
+impl IVergeBackupRetention {
+    /// 未设置任何规则时不清理备份
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.unwrap_or(0) == 0 && self.keep_daily.unwrap_or(0) == 0 && self.keep_weekly.unwrap_or(0) == 0
+    }
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct IVergeHostsSource {
+    pub uid: Option<String>,
+    pub url: Option<String>,
+    pub mode: Option<HostsImportMode>,
+    /// refresh interval in minutes, `0` or `None` disables auto refresh
+    pub update_interval: Option<u64>,
+    pub updated: Option<usize>,
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct IVergeTheme {
     pub primary_color: Option<String>,
@@ -284,6 +433,9 @@ pub struct IVergeTheme {
 }
 
 impl IVerge {
+    /// verge.yaml 的结构版本，字段含义发生不兼容变化时递增
+    pub const SCHEMA_VERSION: u32 = 1;
+
     /// 有效的clash核心名称
     pub const VALID_CLASH_CORES: &'static [&'static str] = &["verge-mihomo", "verge-mihomo-alpha"];
 
@@ -435,9 +587,19 @@ impl IVerge {
             enable_auto_backup_schedule: Some(false),
             auto_backup_interval_hours: Some(24),
             auto_backup_on_change: Some(true),
+            auto_backup_webdav: Some(false),
+            auto_backup_incremental: Some(false),
+            backup_directory_watch: Some(false),
             webdav_url: None,
             webdav_username: None,
             webdav_password: None,
+            backup_passphrase: None,
+            s3_endpoint: None,
+            s3_bucket: None,
+            s3_prefix: None,
+            s3_region: None,
+            s3_access_key_id: None,
+            s3_secret_access_key: None,
             #[cfg(target_os = "macos")]
             enable_tray_speed: Some(false),
             // enable_tray_icon: Some(true),
@@ -495,6 +657,9 @@ impl IVerge {
         patch!(tun_tray_icon);
 
         patch!(enable_tun_mode);
+        patch!(tun_auto_route_exclude);
+        patch!(tun_route_exclude_extra);
+        patch!(tun_interface_filter);
         patch!(enable_auto_launch);
         patch!(enable_silent_start);
         patch!(enable_hover_jump_navigator);
@@ -540,10 +705,23 @@ impl IVerge {
         patch!(enable_auto_backup_schedule);
         patch!(auto_backup_interval_hours);
         patch!(auto_backup_on_change);
+        patch!(auto_backup_triggers);
+        patch!(auto_backup_webdav);
+        patch!(auto_backup_incremental);
+        patch!(backup_retention);
+        patch!(backup_directory);
+        patch!(backup_directory_watch);
 
         patch!(webdav_url);
         patch!(webdav_username);
         patch!(webdav_password);
+        patch!(backup_passphrase);
+        patch!(s3_endpoint);
+        patch!(s3_bucket);
+        patch!(s3_prefix);
+        patch!(s3_region);
+        patch!(s3_access_key_id);
+        patch!(s3_secret_access_key);
         #[cfg(target_os = "macos")]
         patch!(enable_tray_speed);
         // patch!(enable_tray_icon);
@@ -552,6 +730,10 @@ impl IVerge {
         patch!(enable_auto_light_weight_mode);
         patch!(auto_light_weight_minutes);
         patch!(enable_dns_settings);
+        patch!(profile_history_limit);
+        patch!(subscription_quota_thresholds);
+        patch!(subscription_expiry_days);
+        patch!(hosts_sources);
         patch!(home_cards);
         patch!(enable_external_controller);
     }
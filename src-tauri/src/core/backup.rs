@@ -1,17 +1,28 @@
 use crate::constants::files::DNS_CONFIG;
-use crate::{config::Config, process::AsyncHandler, utils::dirs};
+use crate::{
+    config::Config,
+    core::{
+        backup_crypto,
+        backup_manifest::{BackupManifest, MANIFEST_FILE, ManifestCheck, verify_archive},
+        backup_storage::{BackupStorage, RemoteBackupFile},
+    },
+    process::AsyncHandler,
+    utils::dirs,
+};
 use anyhow::Error;
 use arc_swap::{ArcSwap, ArcSwapOption};
+use async_trait::async_trait;
 use backon::{ConstantBuilder, Retryable as _};
 use clash_verge_logging::{Type, logging};
 use once_cell::sync::OnceCell;
-use reqwest_dav::list_cmd::{ListEntity, ListFile};
+use percent_encoding::percent_decode_str;
+use reqwest_dav::list_cmd::ListEntity;
 use smartstring::alias::String;
 use std::{
     collections::HashMap,
     env::{consts::OS, temp_dir},
-    io::Write as _,
-    path::PathBuf,
+    io::{Cursor, Write as _},
+    path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
@@ -21,6 +32,24 @@ use zip::write::SimpleFileOptions;
 // 应用版本常量，来自 tauri.conf.json
 const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// 解密后的备份压缩包
+pub type BackupArchive = zip::ZipArchive<Cursor<Vec<u8>>>;
+
+/// 仅属于本机的 verge 设置，不写入备份
+pub const LOCAL_ONLY_VERGE_KEYS: [&str; 11] = [
+    "webdav_url",
+    "webdav_username",
+    "webdav_password",
+    "backup_passphrase",
+    "backup_directory",
+    "s3_endpoint",
+    "s3_bucket",
+    "s3_prefix",
+    "s3_region",
+    "s3_access_key_id",
+    "s3_secret_access_key",
+];
+
 const TIMEOUT_UPLOAD: u64 = 300; // 上传超时 5 分钟
 const TIMEOUT_DOWNLOAD: u64 = 300; // 下载超时 5 分钟
 const TIMEOUT_LIST: u64 = 3; // 列表超时 30 秒
@@ -157,13 +186,20 @@ impl WebDavClient {
 
         Ok(client)
     }
+}
+
+#[async_trait]
+impl BackupStorage for WebDavClient {
+    fn name(&self) -> &'static str {
+        "WebDAV"
+    }
 
-    pub fn reset(&self) {
+    fn reset(&self) {
         self.config.store(None);
         self.clients.store(Arc::new(HashMap::new()));
     }
 
-    pub async fn upload(&self, file_path: PathBuf, file_name: String) -> Result<(), Error> {
+    async fn upload(&self, file_path: PathBuf, file_name: String) -> Result<(), Error> {
         let client = self.get_client(Operation::Upload).await?;
         let webdav_path: String = format!("{}/{}", dirs::BACKUP_DIR, file_name).into();
 
@@ -188,7 +224,7 @@ impl WebDavClient {
         .await
     }
 
-    pub async fn download(&self, filename: String, storage_path: PathBuf) -> Result<(), Error> {
+    async fn download(&self, filename: String, storage_path: PathBuf) -> Result<(), Error> {
         let client = self.get_client(Operation::Download).await?;
         let path = format!("{}/{}", dirs::BACKUP_DIR, filename);
 
@@ -203,7 +239,7 @@ impl WebDavClient {
         Ok(())
     }
 
-    pub async fn list(&self) -> Result<Vec<ListFile>, Error> {
+    async fn list(&self) -> Result<Vec<RemoteBackupFile>, Error> {
         let client = self.get_client(Operation::List).await?;
         let path = format!("{}/", dirs::BACKUP_DIR);
 
@@ -212,16 +248,25 @@ impl WebDavClient {
             let mut final_files = Vec::new();
             for file in files {
                 if let ListEntity::File(file) = file {
-                    final_files.push(file);
+                    let encoded = file.href.rsplit('/').next().unwrap_or_default();
+                    let filename = percent_decode_str(encoded).decode_utf8_lossy();
+                    if filename.is_empty() {
+                        continue;
+                    }
+                    final_files.push(RemoteBackupFile {
+                        filename: filename.as_ref().into(),
+                        last_modified: file.last_modified.to_rfc3339().into(),
+                        content_length: u64::try_from(file.content_length).unwrap_or_default(),
+                    });
                 }
             }
-            Ok::<Vec<ListFile>, Error>(final_files)
+            Ok::<Vec<RemoteBackupFile>, Error>(final_files)
         };
 
         timeout(Duration::from_secs(TIMEOUT_LIST), fut).await?
     }
 
-    pub async fn delete(&self, file_name: String) -> Result<(), Error> {
+    async fn delete(&self, file_name: String) -> Result<(), Error> {
         let client = self.get_client(Operation::Delete).await?;
         let path = format!("{}/{}", dirs::BACKUP_DIR, file_name);
 
@@ -231,19 +276,59 @@ impl WebDavClient {
     }
 }
 
+/// 备份中的一个文件
+#[derive(Debug, Clone)]
+pub struct BackupEntry {
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+impl BackupEntry {
+    fn new(name: &str, content: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            content,
+        }
+    }
+}
+
+/// 不含扩展名的备份文件名，形如 `{OS}-backup-%Y-%m-%d_%H-%M-%S`
+pub fn backup_file_stem() -> String {
+    let now = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    format!("{OS}-backup-{now}").into()
+}
+
 pub async fn create_backup() -> Result<(String, PathBuf), Error> {
-    let now = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-    let zip_file_name: String = format!("{OS}-backup-{now}.zip").into();
+    let stem = backup_file_stem();
+    let passphrase = Config::verge()
+        .await
+        .latest_arc()
+        .backup_passphrase
+        .clone()
+        .filter(|passphrase| !passphrase.is_empty());
+
+    let entries = collect_backup_entries().await?;
+    let mut content = build_archive(BackupManifest::new(), &entries)?;
+
+    // 设置了口令时加密整个压缩包，订阅链接中的 token 不会以明文形式上传
+    let zip_file_name: String = match passphrase {
+        Some(passphrase) => {
+            content =
+                AsyncHandler::spawn_blocking(move || backup_crypto::encrypt_backup(&content, &passphrase)).await??;
+            format!("{stem}.{}", backup_crypto::ENCRYPTED_BACKUP_EXT).into()
+        }
+        None => format!("{stem}.zip").into(),
+    };
     let zip_path = temp_dir().join(zip_file_name.as_str());
+    fs::write(&zip_path, content).await?;
+    Ok((zip_file_name, zip_path))
+}
 
-    let value = zip_path.clone();
-    let file = AsyncHandler::spawn_blocking(move || std::fs::File::create(&value)).await??;
-    let mut zip = zip::ZipWriter::new(file);
-    zip.add_directory("profiles/", SimpleFileOptions::default())?;
-    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
-
-    if let Ok(mut entries) = fs::read_dir(dirs::app_profiles_dir()?).await {
-        while let Some(entry) = entries.next_entry().await? {
+/// Read every file that goes into a backup, local-only settings are stripped from verge.yaml
+pub async fn collect_backup_entries() -> Result<Vec<BackupEntry>, Error> {
+    let mut entries = Vec::new();
+    if let Ok(mut dir) = fs::read_dir(dirs::app_profiles_dir()?).await {
+        while let Some(entry) = dir.next_entry().await? {
             let path = entry.path();
             if path.is_file() {
                 let file_name_os = entry.file_name();
@@ -251,33 +336,98 @@ pub async fn create_backup() -> Result<(String, PathBuf), Error> {
                     .to_str()
                     .ok_or_else(|| anyhow::Error::msg("Invalid file name encoding"))?;
                 let backup_path = format!("profiles/{}", file_name);
-                zip.start_file(backup_path, options)?;
-                let file_content = fs::read(&path).await?;
-                zip.write_all(&file_content)?;
+                entries.push(BackupEntry::new(&backup_path, fs::read(&path).await?));
             }
         }
     }
-    zip.start_file(dirs::CLASH_CONFIG, options)?;
-    zip.write_all(fs::read(dirs::clash_path()?).await?.as_slice())?;
+    entries.push(BackupEntry::new(
+        dirs::CLASH_CONFIG,
+        fs::read(dirs::clash_path()?).await?,
+    ));
 
     let verge_text = fs::read_to_string(dirs::verge_path()?).await?;
     let mut verge_config: serde_json::Value = serde_yaml_ng::from_str(&verge_text)?;
     if let Some(obj) = verge_config.as_object_mut() {
-        obj.remove("webdav_username");
-        obj.remove("webdav_password");
-        obj.remove("webdav_url");
+        for key in LOCAL_ONLY_VERGE_KEYS {
+            obj.remove(key);
+        }
     }
-    zip.start_file(dirs::VERGE_CONFIG, options)?;
-    zip.write_all(serde_yaml_ng::to_string(&verge_config)?.as_bytes())?;
+    let verge_content = serde_yaml_ng::to_string(&verge_config)?;
+    entries.push(BackupEntry::new(dirs::VERGE_CONFIG, verge_content.into_bytes()));
 
     let dns_config_path = dirs::app_home_dir()?.join(DNS_CONFIG);
     if dns_config_path.exists() {
-        zip.start_file(DNS_CONFIG, options)?;
-        zip.write_all(fs::read(&dns_config_path).await?.as_slice())?;
+        entries.push(BackupEntry::new(DNS_CONFIG, fs::read(&dns_config_path).await?));
     }
 
-    zip.start_file(dirs::PROFILE_YAML, options)?;
-    zip.write_all(fs::read(dirs::profiles_path()?).await?.as_slice())?;
-    zip.finish()?;
-    Ok((zip_file_name, zip_path))
+    entries.push(BackupEntry::new(
+        dirs::PROFILE_YAML,
+        fs::read(dirs::profiles_path()?).await?,
+    ));
+    Ok(entries)
+}
+
+/// Pack the entries into a backup archive, recording each of them in the manifest
+pub fn build_archive(mut manifest: BackupManifest, entries: &[BackupEntry]) -> Result<Vec<u8>, Error> {
+    manifest.entries.clear();
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.add_directory("profiles/", SimpleFileOptions::default())?;
+    for entry in entries {
+        write_entry(&mut zip, &mut manifest, &entry.name, &entry.content)?;
+    }
+    zip.start_file(MANIFEST_FILE, SimpleFileOptions::default())?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// 是否为备份文件 (`.zip` 或加密的 `.zip.enc`)
+pub fn is_backup_file(file_name: &str) -> bool {
+    let lower = file_name.to_ascii_lowercase();
+    lower.ends_with(".zip") || lower.ends_with(backup_crypto::ENCRYPTED_BACKUP_EXT)
+}
+
+/// 写入压缩包并记录到备份清单
+fn write_entry(
+    zip: &mut zip::ZipWriter<Cursor<Vec<u8>>>,
+    manifest: &mut BackupManifest,
+    name: &str,
+    content: &[u8],
+) -> Result<(), Error> {
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.start_file(name, options)?;
+    zip.write_all(content)?;
+    manifest.add_entry(name, content);
+    Ok(())
+}
+
+/// Open a backup archive, decrypting it first if needed, and verify it against its manifest.
+/// Without an explicit passphrase the one configured in the settings is used.
+pub async fn open_backup_archive(
+    path: &Path,
+    passphrase: Option<String>,
+) -> Result<(BackupArchive, ManifestCheck), Error> {
+    open_backup_content(fs::read(path).await?, passphrase).await
+}
+
+/// Same as [`open_backup_archive`] for a backup already read into memory
+pub async fn open_backup_content(
+    content: Vec<u8>,
+    passphrase: Option<String>,
+) -> Result<(BackupArchive, ManifestCheck), Error> {
+    let passphrase = match passphrase.filter(|passphrase| !passphrase.is_empty()) {
+        Some(passphrase) => Some(passphrase),
+        None if backup_crypto::is_encrypted(&content) => Config::verge().await.latest_arc().backup_passphrase.clone(),
+        None => None,
+    };
+    AsyncHandler::spawn_blocking(move || {
+        let content = if backup_crypto::is_encrypted(&content) {
+            backup_crypto::decrypt_backup(&content, passphrase.as_deref().unwrap_or_default())?
+        } else {
+            content
+        };
+        let mut zip = zip::ZipArchive::new(Cursor::new(content))?;
+        let check = verify_archive(&mut zip)?;
+        Ok::<_, Error>((zip, check))
+    })
+    .await?
 }
@@ -0,0 +1,192 @@
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead as _, KeyInit as _, Payload},
+};
+use anyhow::{Result, anyhow, bail};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+/// 加密备份文件的扩展名
+pub const ENCRYPTED_BACKUP_EXT: &str = "zip.enc";
+
+/// File header: magic, format version, argon2 params, salt, passphrase check, nonce
+const MAGIC: &[u8; 6] = b"CVBAK\0";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const KEY_LENGTH: usize = 32;
+const CHECK_LENGTH: usize = 32;
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 3 * 4 + SALT_LENGTH + CHECK_LENGTH + NONCE_LENGTH;
+
+/// 解密时允许的最大内存开销 (KiB)，避免被构造的文件耗尽内存
+const MAX_M_COST: u32 = 1024 * 1024;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KdfParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// 判断数据是否为加密备份
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypt a backup archive with a key derived from the passphrase (argon2id + AES-256-GCM).
+/// The header is authenticated as associated data.
+pub fn encrypt_backup(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if passphrase.is_empty() {
+        bail!("Backup passphrase must not be empty");
+    }
+
+    let params = KdfParams::default();
+    let mut salt = [0u8; SALT_LENGTH];
+    let mut nonce = [0u8; NONCE_LENGTH];
+    getrandom::fill(&mut salt).map_err(|e| anyhow!("Failed to generate salt: {e}"))?;
+    getrandom::fill(&mut nonce).map_err(|e| anyhow!("Failed to generate nonce: {e}"))?;
+    let (key, check) = derive_key(passphrase, &salt, params)?;
+
+    let mut output = Vec::with_capacity(HEADER_LENGTH + data.len() + 16);
+    output.extend_from_slice(MAGIC);
+    output.push(FORMAT_VERSION);
+    output.extend_from_slice(&params.m_cost.to_le_bytes());
+    output.extend_from_slice(&params.t_cost.to_le_bytes());
+    output.extend_from_slice(&params.p_cost.to_le_bytes());
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&check);
+    output.extend_from_slice(&nonce);
+
+    let ciphertext = cipher(&key)?
+        .encrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: data,
+                aad: &output,
+            },
+        )
+        .map_err(|e| anyhow!("Failed to encrypt backup: {e}"))?;
+    output.extend(ciphertext);
+    Ok(output)
+}
+
+/// 解密备份文件，口令错误与文件损坏会给出不同的错误
+pub fn decrypt_backup(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(data) {
+        bail!("Backup file is not encrypted");
+    }
+    if data.len() < HEADER_LENGTH {
+        bail!("Encrypted backup file is truncated");
+    }
+    let (header, ciphertext) = data.split_at(HEADER_LENGTH);
+    let mut cursor = &header[MAGIC.len()..];
+
+    let version = take::<1>(&mut cursor)[0];
+    if version != FORMAT_VERSION {
+        bail!("Unsupported encrypted backup format version {version}, please upgrade the app");
+    }
+    let params = KdfParams {
+        m_cost: u32::from_le_bytes(take(&mut cursor)),
+        t_cost: u32::from_le_bytes(take(&mut cursor)),
+        p_cost: u32::from_le_bytes(take(&mut cursor)),
+    };
+    if params.m_cost > MAX_M_COST || params.t_cost > MAX_T_COST || params.p_cost > MAX_P_COST {
+        bail!("Encrypted backup uses unsupported key derivation parameters");
+    }
+    let salt = take::<SALT_LENGTH>(&mut cursor);
+    let stored_check = take::<CHECK_LENGTH>(&mut cursor);
+    let nonce = take::<NONCE_LENGTH>(&mut cursor);
+
+    if passphrase.is_empty() {
+        bail!("This backup is encrypted, please enter the backup passphrase");
+    }
+    let (key, check) = derive_key(passphrase, &salt, params)?;
+    if check != stored_check {
+        bail!("Incorrect backup passphrase");
+    }
+
+    cipher(&key)?
+        .decrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| anyhow!("Encrypted backup file is corrupted or has been tampered with"))
+}
+
+/// 派生 64 字节：前半部分为加密密钥，后半部分写入文件头用于校验口令
+fn derive_key(passphrase: &str, salt: &[u8], params: KdfParams) -> Result<([u8; KEY_LENGTH], [u8; CHECK_LENGTH])> {
+    let argon_params = Params::new(
+        params.m_cost,
+        params.t_cost,
+        params.p_cost,
+        Some(KEY_LENGTH + CHECK_LENGTH),
+    )
+    .map_err(|e| anyhow!("Invalid key derivation parameters: {e}"))?;
+    let mut output = [0u8; KEY_LENGTH + CHECK_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut output)
+        .map_err(|e| anyhow!("Failed to derive backup key: {e}"))?;
+
+    let mut key = [0u8; KEY_LENGTH];
+    let mut check = [0u8; CHECK_LENGTH];
+    key.copy_from_slice(&output[..KEY_LENGTH]);
+    check.copy_from_slice(&output[KEY_LENGTH..]);
+    Ok((key, check))
+}
+
+fn cipher(key: &[u8; KEY_LENGTH]) -> Result<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(key).map_err(|e| anyhow!("Invalid backup key: {e}"))
+}
+
+/// 从文件头中读取定长字段，调用前已保证长度足够
+fn take<const N: usize>(cursor: &mut &[u8]) -> [u8; N] {
+    let mut out = [0u8; N];
+    let (head, rest) = cursor.split_at(N);
+    out.copy_from_slice(head);
+    *cursor = rest;
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_backup_round_trip() {
+        let data = b"PK\x03\x04 backup archive".to_vec();
+        let encrypted = encrypt_backup(&data, "correct horse").unwrap_or_default();
+        assert!(is_encrypted(&encrypted));
+        assert!(!is_encrypted(&data));
+        assert_eq!(decrypt_backup(&encrypted, "correct horse").ok(), Some(data));
+
+        let wrong = decrypt_backup(&encrypted, "battery staple")
+            .err()
+            .map(|e| e.to_string());
+        assert_eq!(wrong.as_deref(), Some("Incorrect backup passphrase"));
+
+        let mut tampered = encrypted.clone();
+        if let Some(last) = tampered.last_mut() {
+            *last ^= 0xff;
+        }
+        let corrupted = decrypt_backup(&tampered, "correct horse").err().map(|e| e.to_string());
+        assert_eq!(
+            corrupted.as_deref(),
+            Some("Encrypted backup file is corrupted or has been tampered with")
+        );
+        assert!(decrypt_backup(&encrypted[..HEADER_LENGTH - 1], "correct horse").is_err());
+    }
+}
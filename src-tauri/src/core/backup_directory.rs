@@ -0,0 +1,173 @@
+use crate::{
+    config::Config,
+    core::{
+        backup::is_backup_file,
+        backup_storage::{BackupStorage, RemoteBackupFile},
+    },
+};
+use anyhow::{Context as _, Result, bail};
+use async_trait::async_trait;
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use smartstring::alias::String;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+
+/// A local folder used as a backup target, usually synced between devices
+/// by Syncthing or mounted from a network share
+#[derive(Default)]
+pub struct DirectoryStorage {
+    /// 本机写入的备份，监视目录时不会把它们当作其他设备的备份
+    uploaded: Mutex<HashSet<String>>,
+}
+
+impl DirectoryStorage {
+    pub fn global() -> &'static Self {
+        static DIRECTORY_STORAGE: OnceCell<DirectoryStorage> = OnceCell::new();
+        DIRECTORY_STORAGE.get_or_init(Self::default)
+    }
+
+    /// 当前配置的备份目录
+    pub async fn directory() -> Result<PathBuf> {
+        Config::verge()
+            .await
+            .latest_arc()
+            .backup_directory
+            .as_deref()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .context("Backup directory is not configured")
+    }
+
+    pub fn is_own_upload(&self, file_name: &str) -> bool {
+        self.uploaded.lock().contains(file_name)
+    }
+}
+
+#[async_trait]
+impl BackupStorage for DirectoryStorage {
+    fn name(&self) -> &'static str {
+        "directory"
+    }
+
+    fn reset(&self) {}
+
+    async fn upload(&self, file_path: PathBuf, file_name: String) -> Result<()> {
+        write_atomic(&Self::directory().await?, &file_path, &file_name).await?;
+        self.uploaded.lock().insert(file_name);
+        Ok(())
+    }
+
+    async fn download(&self, file_name: String, storage_path: PathBuf) -> Result<()> {
+        let path = backup_path(&Self::directory().await?, &file_name)?;
+        fs::copy(&path, &storage_path)
+            .await
+            .with_context(|| format!("Failed to read backup file {}", path.display()))?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<RemoteBackupFile>> {
+        list_backups(&Self::directory().await?).await
+    }
+
+    async fn delete(&self, file_name: String) -> Result<()> {
+        let path = backup_path(&Self::directory().await?, &file_name)?;
+        fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
+
+/// 只允许目录中的文件名，防止路径穿越
+fn backup_path(dir: &Path, file_name: &str) -> Result<PathBuf> {
+    if file_name.is_empty() || file_name.contains(['/', '\\']) || file_name.starts_with('.') {
+        bail!("Invalid backup file name: {file_name}");
+    }
+    Ok(dir.join(file_name))
+}
+
+/// Copy into a hidden temp file first and rename it once fully written,
+/// so sync tools and other devices never see a partial archive.
+async fn write_atomic(dir: &Path, source: &Path, file_name: &str) -> Result<()> {
+    let target = backup_path(dir, file_name)?;
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("Failed to create backup directory {}", dir.display()))?;
+
+    let temp = dir.join(format!(".{file_name}.{}.tmp", nanoid::nanoid!(8)));
+    let result = async {
+        fs::copy(source, &temp).await?;
+        fs::OpenOptions::new().write(true).open(&temp).await?.sync_all().await?;
+        fs::rename(&temp, &target).await
+    }
+    .await;
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp).await;
+        return Err(err).with_context(|| format!("Failed to write backup file {}", target.display()));
+    }
+    Ok(())
+}
+
+async fn list_backups(dir: &Path) -> Result<Vec<RemoteBackupFile>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        let Ok(file_name) = entry.file_name().into_string() else {
+            continue;
+        };
+        // 跳过临时文件及同步工具的隐藏文件
+        if !metadata.is_file() || file_name.starts_with('.') || !is_backup_file(&file_name) {
+            continue;
+        }
+        let last_modified = metadata
+            .modified()
+            .map(|time| chrono::DateTime::<Utc>::from(time).to_rfc3339())
+            .unwrap_or_default();
+        files.push(RemoteBackupFile {
+            filename: file_name.into(),
+            last_modified: last_modified.into(),
+            content_length: metadata.len(),
+        });
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn write_list_and_reject_unsafe_names() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("backup-directory-{}", nanoid::nanoid!()));
+        let source = std::env::temp_dir().join(format!("backup-source-{}", nanoid::nanoid!()));
+        fs::write(&source, b"PK backup").await?;
+
+        write_atomic(&dir, &source, "linux-backup-2026-03-10_08-00-00.zip").await?;
+        fs::write(dir.join(".syncthing.linux-backup.zip.tmp"), b"partial").await?;
+        fs::write(dir.join("notes.txt"), b"").await?;
+
+        let files = list_backups(&dir).await?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files.first().map(|file| file.filename.as_str()),
+            Some("linux-backup-2026-03-10_08-00-00.zip")
+        );
+        assert_eq!(files.first().map(|file| file.content_length), Some(9));
+
+        assert!(backup_path(&dir, "../verge.yaml").is_err());
+        assert!(backup_path(&dir, "..\\verge.yaml").is_err());
+        assert!(write_atomic(&dir, &source, "nested/backup.zip").await.is_err());
+
+        fs::remove_dir_all(&dir).await?;
+        fs::remove_file(&source).await?;
+        Ok(())
+    }
+}
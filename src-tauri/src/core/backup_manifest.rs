@@ -0,0 +1,196 @@
+use crate::config::{IProfiles, IVerge};
+use anyhow::{Context as _, Result, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use smartstring::alias::String;
+use std::{
+    collections::HashSet,
+    env::consts::OS,
+    io::{Read, Seek},
+};
+use zip::ZipArchive;
+
+/// 备份清单在压缩包中的文件名
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Describes a backup archive: where it was created and a checksum of every entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub app_version: String,
+    pub os: String,
+    pub created_at: i64,
+    pub verge_schema: u32,
+    pub profiles_schema: u32,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl BackupManifest {
+    pub fn new() -> Self {
+        Self {
+            app_version: APP_VERSION.into(),
+            os: OS.into(),
+            created_at: chrono::Local::now().timestamp(),
+            verge_schema: IVerge::SCHEMA_VERSION,
+            profiles_schema: IProfiles::SCHEMA_VERSION,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add_entry(&mut self, name: &str, content: &[u8]) {
+        self.entries.push(ManifestEntry {
+            name: name.into(),
+            size: content.len() as u64,
+            sha256: sha256_hex(content),
+        });
+    }
+}
+
+impl Default for BackupManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 完整性校验的结果，`warnings` 中的问题不影响恢复
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ManifestCheck {
+    pub manifest: Option<BackupManifest>,
+    pub warnings: Vec<String>,
+}
+
+/// Verify every entry of the archive against its manifest.
+/// A corrupted archive is rejected; version or platform differences only produce warnings.
+pub fn verify_archive<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<ManifestCheck> {
+    let Some(manifest) = read_manifest(zip)? else {
+        return Ok(ManifestCheck {
+            manifest: None,
+            warnings: vec![
+                "The backup has no manifest (created by an older version), its integrity cannot be verified".into(),
+            ],
+        });
+    };
+
+    let mut listed = HashSet::new();
+    for entry in &manifest.entries {
+        let mut file = zip
+            .by_name(&entry.name)
+            .with_context(|| format!("Backup is corrupted: \"{}\" is missing", entry.name))?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)
+            .with_context(|| format!("Backup is corrupted: failed to read \"{}\"", entry.name))?;
+        if content.len() as u64 != entry.size || sha256_hex(&content) != entry.sha256 {
+            bail!("Backup is corrupted: checksum mismatch for \"{}\"", entry.name);
+        }
+        listed.insert(entry.name.as_str());
+    }
+    for name in zip.file_names() {
+        if name != MANIFEST_FILE && !name.ends_with('/') && !listed.contains(name) {
+            bail!("Backup is corrupted: unexpected entry \"{name}\"");
+        }
+    }
+
+    let mut warnings = Vec::new();
+    if manifest.app_version != APP_VERSION {
+        warnings.push(
+            format!(
+                "The backup was created by version {}, current version is {APP_VERSION}",
+                manifest.app_version
+            )
+            .into(),
+        );
+    }
+    if manifest.os != OS {
+        warnings.push(
+            format!(
+                "The backup was created on {}, platform specific settings may not apply on {OS}",
+                manifest.os
+            )
+            .into(),
+        );
+    }
+    if manifest.verge_schema > IVerge::SCHEMA_VERSION || manifest.profiles_schema > IProfiles::SCHEMA_VERSION {
+        warnings.push("The backup was created by a newer version, some settings may be ignored".into());
+    }
+
+    Ok(ManifestCheck {
+        manifest: Some(manifest),
+        warnings,
+    })
+}
+
+fn read_manifest<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<Option<BackupManifest>> {
+    if zip.index_for_name(MANIFEST_FILE).is_none() {
+        return Ok(None);
+    }
+    let mut content = Vec::new();
+    zip.by_name(MANIFEST_FILE)?.read_to_end(&mut content)?;
+    let manifest = serde_json::from_slice(&content).context("Backup is corrupted: invalid manifest")?;
+    Ok(Some(manifest))
+}
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write as _};
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    fn archive(entries: &[(&str, &[u8])], manifest: Option<&BackupManifest>) -> Result<ZipArchive<Cursor<Vec<u8>>>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for (name, content) in entries {
+            zip.start_file(*name, options)?;
+            zip.write_all(content)?;
+        }
+        if let Some(manifest) = manifest {
+            zip.start_file(MANIFEST_FILE, options)?;
+            zip.write_all(&serde_json::to_vec(manifest)?)?;
+        }
+        Ok(ZipArchive::new(zip.finish()?)?)
+    }
+
+    #[test]
+    fn verify_backup_manifest() -> Result<()> {
+        let mut manifest = BackupManifest::new();
+        manifest.add_entry("verge.yaml", b"theme: dark");
+        manifest.add_entry("profiles/R1.yaml", b"proxies: []");
+
+        let entries: &[(&str, &[u8])] = &[("verge.yaml", b"theme: dark"), ("profiles/R1.yaml", b"proxies: []")];
+        let check = verify_archive(&mut archive(entries, Some(&manifest))?).ok();
+        assert!(check.is_some_and(|check| check.manifest.is_some() && check.warnings.is_empty()));
+
+        let tampered: &[(&str, &[u8])] = &[("verge.yaml", b"theme: light"), ("profiles/R1.yaml", b"proxies: []")];
+        assert!(verify_archive(&mut archive(tampered, Some(&manifest))?).is_err());
+
+        let missing: &[(&str, &[u8])] = &[("verge.yaml", b"theme: dark")];
+        assert!(verify_archive(&mut archive(missing, Some(&manifest))?).is_err());
+
+        let extra: &[(&str, &[u8])] = &[
+            ("verge.yaml", b"theme: dark"),
+            ("profiles/R1.yaml", b"proxies: []"),
+            ("profiles/R2.yaml", b"proxies: []"),
+        ];
+        assert!(verify_archive(&mut archive(extra, Some(&manifest))?).is_err());
+
+        let legacy = verify_archive(&mut archive(entries, None)?).ok();
+        assert!(legacy.is_some_and(|check| check.manifest.is_none() && check.warnings.len() == 1));
+
+        manifest.os = "plan9".into();
+        manifest.app_version = "0.0.1".into();
+        let foreign = verify_archive(&mut archive(entries, Some(&manifest))?).ok();
+        assert!(foreign.is_some_and(|check| check.warnings.len() == 2));
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use smartstring::alias::String;
+use std::path::PathBuf;
+
+/// 远程存储中的备份文件
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteBackupFile {
+    pub filename: String,
+    /// RFC 3339
+    pub last_modified: String,
+    pub content_length: u64,
+}
+
+/// A remote location backups can be uploaded to and restored from
+#[async_trait]
+pub trait BackupStorage: Send + Sync {
+    /// 用于日志中区分存储后端
+    fn name(&self) -> &'static str;
+
+    async fn upload(&self, file_path: PathBuf, file_name: String) -> Result<()>;
+
+    async fn download(&self, file_name: String, storage_path: PathBuf) -> Result<()>;
+
+    async fn list(&self) -> Result<Vec<RemoteBackupFile>>;
+
+    async fn delete(&self, file_name: String) -> Result<()>;
+
+    /// 配置变更后丢弃缓存的连接与配置
+    fn reset(&self);
+}
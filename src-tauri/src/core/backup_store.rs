@@ -0,0 +1,355 @@
+use crate::{
+    core::{
+        backup::{BackupEntry, build_archive},
+        backup_manifest::{BackupManifest, ManifestEntry, sha256_hex},
+    },
+    utils::dirs::local_backup_dir,
+};
+use anyhow::{Context as _, Result, bail};
+use clash_verge_logging::{Type, logging};
+use once_cell::sync::OnceCell;
+use smartstring::alias::String;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio::{fs, sync::Mutex};
+
+/// 增量备份仓库，位于本地备份目录下
+pub const STORE_DIR: &str = "store";
+const OBJECTS_DIR: &str = "objects";
+const SNAPSHOTS_DIR: &str = "snapshots";
+const SNAPSHOT_EXT: &str = "json";
+
+/// 仓库中的一次备份
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreSnapshot {
+    pub name: String,
+    pub path: PathBuf,
+    pub created_at: i64,
+    /// 备份中所有文件的大小之和，不是实际占用的空间
+    pub size: u64,
+}
+
+/// 垃圾回收删除的文件
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StoreGcReport {
+    pub objects: usize,
+    pub bytes: u64,
+}
+
+/// Deduplicated local backups. Every file is stored once under its SHA-256,
+/// a snapshot is a backup manifest listing the hashes of its files.
+/// Files are stored unencrypted, so nothing is written while a backup passphrase is set.
+#[derive(Default)]
+pub struct BackupStore {
+    /// 写入与垃圾回收互斥，避免回收刚写入但快照尚未保存的文件
+    lock: Mutex<()>,
+}
+
+impl BackupStore {
+    pub fn global() -> &'static Self {
+        static BACKUP_STORE: OnceCell<BackupStore> = OnceCell::new();
+        BACKUP_STORE.get_or_init(Self::default)
+    }
+
+    fn root() -> Result<PathBuf> {
+        Ok(local_backup_dir()?.join(STORE_DIR))
+    }
+
+    /// Save a snapshot, returning the number of bytes that were not stored yet
+    pub async fn save(&self, name: &str, entries: &[BackupEntry], passphrase: Option<&str>) -> Result<u64> {
+        let _guard = self.lock.lock().await;
+        save_snapshot(&Self::root()?, name, entries, passphrase).await
+    }
+
+    /// 按创建时间倒序列出快照
+    pub async fn list(&self) -> Result<Vec<StoreSnapshot>> {
+        list_snapshots(&Self::root()?).await
+    }
+
+    /// Rebuild a snapshot as a standalone backup archive
+    pub async fn export(&self, name: &str) -> Result<Vec<u8>> {
+        export_snapshot(&Self::root()?, name).await
+    }
+
+    /// Remove the snapshots, then every file no remaining snapshot refers to
+    pub async fn delete(&self, names: &[String]) -> Result<StoreGcReport> {
+        let _guard = self.lock.lock().await;
+        let root = Self::root()?;
+        for name in names {
+            fs::remove_file(snapshot_path(&root, name)?)
+                .await
+                .with_context(|| format!("Failed to remove incremental backup {name}"))?;
+        }
+        collect_garbage(&root).await
+    }
+}
+
+async fn save_snapshot(root: &Path, name: &str, entries: &[BackupEntry], passphrase: Option<&str>) -> Result<u64> {
+    if passphrase.is_some_and(|passphrase| !passphrase.is_empty()) {
+        bail!("Incremental backups are not encrypted, clear the backup passphrase or use full backups");
+    }
+    let path = snapshot_path(root, name)?;
+    if path.exists() {
+        bail!("Incremental backup {name} already exists");
+    }
+
+    let mut manifest = BackupManifest::new();
+    let mut written = 0;
+    for entry in entries {
+        let hash = sha256_hex(&entry.content);
+        let size = entry.content.len() as u64;
+        let object = object_path(root, &hash)?;
+        // 大小不一致说明之前的写入不完整，重新写入
+        let stored = fs::metadata(&object).await.is_ok_and(|metadata| metadata.len() == size);
+        if !stored {
+            write_atomic(&object, &entry.content).await?;
+            written += size;
+        }
+        manifest.entries.push(ManifestEntry {
+            name: entry.name.clone(),
+            size,
+            sha256: hash,
+        });
+    }
+
+    // 快照最后写入，中途失败只会留下未被引用的文件，由垃圾回收清理
+    write_atomic(&path, &serde_json::to_vec_pretty(&manifest)?).await?;
+    Ok(written)
+}
+
+async fn list_snapshots(root: &Path) -> Result<Vec<StoreSnapshot>> {
+    let dir = root.join(SNAPSHOTS_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    let mut entries = fs::read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let Some(name) = snapshot_name(&path).map(String::from) else {
+            continue;
+        };
+        match read_manifest(&path).await {
+            Ok(manifest) => snapshots.push(StoreSnapshot {
+                name,
+                path,
+                created_at: manifest.created_at,
+                size: manifest.entries.iter().map(|entry| entry.size).sum(),
+            }),
+            Err(err) => logging!(warn, Type::Backup, "Skip invalid incremental backup {name}: {err:#?}"),
+        }
+    }
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.name.cmp(&a.name)));
+    Ok(snapshots)
+}
+
+async fn export_snapshot(root: &Path, name: &str) -> Result<Vec<u8>> {
+    let manifest = read_manifest(&snapshot_path(root, name)?).await?;
+    let mut entries = Vec::with_capacity(manifest.entries.len());
+    for entry in &manifest.entries {
+        let content = fs::read(object_path(root, &entry.sha256)?)
+            .await
+            .with_context(|| format!("Incremental backup is corrupted: \"{}\" is missing", entry.name))?;
+        if content.len() as u64 != entry.size || sha256_hex(&content) != entry.sha256 {
+            bail!(
+                "Incremental backup is corrupted: checksum mismatch for \"{}\"",
+                entry.name
+            );
+        }
+        entries.push(BackupEntry {
+            name: entry.name.clone(),
+            content,
+        });
+    }
+    build_archive(manifest, &entries)
+}
+
+/// 删除没有任何快照引用的文件，快照无法读取时不做任何删除
+async fn collect_garbage(root: &Path) -> Result<StoreGcReport> {
+    let mut referenced = HashSet::new();
+    let snapshots_dir = root.join(SNAPSHOTS_DIR);
+    if snapshots_dir.exists() {
+        let mut entries = fs::read_dir(&snapshots_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if snapshot_name(&path).is_none() {
+                continue;
+            }
+            let manifest = read_manifest(&path)
+                .await
+                .with_context(|| format!("Failed to read {}, skip garbage collection", path.display()))?;
+            referenced.extend(manifest.entries.into_iter().map(|entry| entry.sha256));
+        }
+    }
+
+    let mut report = StoreGcReport::default();
+    let objects_dir = root.join(OBJECTS_DIR);
+    if !objects_dir.exists() {
+        return Ok(report);
+    }
+    let mut shards = fs::read_dir(&objects_dir).await?;
+    while let Some(shard) = shards.next_entry().await? {
+        if !shard.metadata().await?.is_dir() {
+            continue;
+        }
+        let mut objects = fs::read_dir(shard.path()).await?;
+        while let Some(object) = objects.next_entry().await? {
+            let name = object.file_name();
+            if name.to_str().is_some_and(|hash| referenced.contains(hash)) {
+                continue;
+            }
+            let size = object.metadata().await?.len();
+            fs::remove_file(object.path()).await?;
+            report.objects += 1;
+            report.bytes += size;
+        }
+        // 目录不为空时删除失败，忽略即可
+        let _ = fs::remove_dir(shard.path()).await;
+    }
+    Ok(report)
+}
+
+async fn read_manifest(path: &Path) -> Result<BackupManifest> {
+    let content = fs::read(path).await?;
+    let manifest: BackupManifest = serde_json::from_slice(&content)?;
+    if let Some(entry) = manifest.entries.iter().find(|entry| !is_hash(&entry.sha256)) {
+        bail!("Invalid checksum for \"{}\"", entry.name);
+    }
+    Ok(manifest)
+}
+
+fn snapshot_name(path: &Path) -> Option<&str> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some(SNAPSHOT_EXT) {
+        return None;
+    }
+    path.file_stem()?.to_str().filter(|name| !name.starts_with('.'))
+}
+
+/// 只允许目录中的文件名，防止路径穿越
+fn snapshot_path(root: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        bail!("Invalid incremental backup name: {name}");
+    }
+    Ok(root.join(SNAPSHOTS_DIR).join(format!("{name}.{SNAPSHOT_EXT}")))
+}
+
+/// 按哈希前两位分目录存放，避免单个目录中文件过多
+fn object_path(root: &Path, hash: &str) -> Result<PathBuf> {
+    let Some(shard) = hash.get(..2).filter(|_| is_hash(hash)) else {
+        bail!("Invalid checksum: {hash}");
+    };
+    Ok(root.join(OBJECTS_DIR).join(shard).join(hash))
+}
+
+fn is_hash(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+async fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let Some((dir, file_name)) = path.parent().zip(path.file_name().and_then(|name| name.to_str())) else {
+        bail!("Invalid path: {}", path.display());
+    };
+    fs::create_dir_all(dir).await?;
+    let temp = dir.join(format!(".{file_name}.tmp"));
+    fs::write(&temp, content).await?;
+    if let Err(err) = fs::rename(&temp, path).await {
+        let _ = fs::remove_file(&temp).await;
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::backup_manifest::verify_archive;
+    use std::io::{Cursor, Read as _};
+
+    fn entry(name: &str, content: &str) -> BackupEntry {
+        BackupEntry {
+            name: name.into(),
+            content: content.as_bytes().to_vec(),
+        }
+    }
+
+    async fn object_count(root: &Path) -> Result<usize> {
+        let mut count = 0;
+        let mut shards = fs::read_dir(root.join(OBJECTS_DIR)).await?;
+        while let Some(shard) = shards.next_entry().await? {
+            let mut objects = fs::read_dir(shard.path()).await?;
+            while objects.next_entry().await?.is_some() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    #[tokio::test]
+    async fn deduplicate_export_and_collect_garbage() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("backup-store-{}", nanoid::nanoid!()));
+        let first = [
+            entry("profiles/R1.yaml", "proxies: [a]"),
+            entry("verge.yaml", "theme: dark"),
+        ];
+        let second = [
+            entry("profiles/R1.yaml", "proxies: [a]"),
+            entry("verge.yaml", "theme: light"),
+        ];
+
+        assert_eq!(save_snapshot(&root, "first", &first, None).await?, 23);
+        // 只写入变化的文件
+        assert_eq!(save_snapshot(&root, "second", &second, None).await?, 12);
+        assert_eq!(object_count(&root).await?, 3);
+        assert!(save_snapshot(&root, "second", &second, None).await.is_err());
+        assert!(snapshot_path(&root, "../verge").is_err());
+
+        let names: Vec<String> = list_snapshots(&root)
+            .await?
+            .into_iter()
+            .map(|snapshot| snapshot.name)
+            .collect();
+        assert_eq!(names, ["second", "first"]);
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(export_snapshot(&root, "first").await?))?;
+        assert!(verify_archive(&mut zip)?.manifest.is_some());
+        let mut verge = std::string::String::new();
+        zip.by_name("verge.yaml")?.read_to_string(&mut verge)?;
+        assert_eq!(verge, "theme: dark");
+
+        fs::remove_file(snapshot_path(&root, "first")?).await?;
+        let report = collect_garbage(&root).await?;
+        assert_eq!(report, StoreGcReport { objects: 1, bytes: 11 });
+        assert_eq!(object_count(&root).await?, 2);
+        assert!(export_snapshot(&root, "second").await.is_ok());
+
+        // 文件被篡改时导出失败
+        let hash = sha256_hex(b"theme: light");
+        fs::write(object_path(&root, &hash)?, b"theme: lighT").await?;
+        assert!(export_snapshot(&root, "second").await.is_err());
+
+        fs::remove_dir_all(&root).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refuse_plaintext_objects_with_passphrase() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("backup-store-{}", nanoid::nanoid!()));
+        let entries = [entry("verge.yaml", "webdav_password: secret")];
+
+        assert!(
+            save_snapshot(&root, "locked", &entries, Some("passphrase"))
+                .await
+                .is_err()
+        );
+        assert!(!root.exists());
+        assert!(list_snapshots(&root).await?.is_empty());
+
+        // 空口令视为未设置
+        assert!(save_snapshot(&root, "open", &entries, Some("")).await.is_ok());
+        fs::remove_dir_all(&root).await?;
+        Ok(())
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{APP_HANDLE, singleton};
+use crate::{
+    APP_HANDLE,
+    config::{PrfChangeReport, ProfileUpdateReport},
+    singleton,
+};
 use smartstring::alias::String;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::AppHandle;
@@ -62,6 +66,14 @@ impl Handle {
         Self::send_event(FrontendEvent::ProfileUpdateCompleted { uid });
     }
 
+    pub fn notify_profile_change_report(uid: &String, report: &PrfChangeReport) {
+        Self::send_event(FrontendEvent::ProfileChangeReport { uid, report });
+    }
+
+    pub fn notify_profile_batch_progress(done: usize, total: usize, report: &ProfileUpdateReport) {
+        Self::send_event(FrontendEvent::ProfileBatchProgress(done, total, report));
+    }
+
     pub fn notice_message<S: AsRef<str>, M: Into<String>>(status: S, msg: M) {
         let status_str = status.as_ref();
         let msg_str = msg.into();
@@ -1,5 +1,11 @@
 pub mod autostart;
 pub mod backup;
+pub mod backup_crypto;
+pub mod backup_directory;
+pub mod backup_manifest;
+pub mod backup_s3;
+pub mod backup_storage;
+pub mod backup_store;
 pub mod handle;
 pub mod hotkey;
 pub mod logger;
@@ -1,4 +1,7 @@
-use crate::utils::window_manager::WindowManager;
+use crate::{
+    config::{PrfChangeReport, ProfileUpdateReport},
+    utils::window_manager::WindowManager,
+};
 use clash_verge_logging::{Type, logging};
 use serde_json::json;
 use smartstring::alias::String;
@@ -14,6 +17,8 @@ pub enum FrontendEvent<'a> {
     TimerUpdated { profile_index: &'a String },
     ProfileUpdateStarted { uid: &'a String },
     ProfileUpdateCompleted { uid: &'a String },
+    ProfileChangeReport { uid: &'a str, report: &'a PrfChangeReport },
+    ProfileBatchProgress(usize, usize, &'a ProfileUpdateReport),
 }
 
 #[derive(Debug)]
@@ -41,6 +46,14 @@ impl NotificationSystem {
             FrontendEvent::TimerUpdated { profile_index } => ("verge://timer-updated", Ok(json!(profile_index))),
             FrontendEvent::ProfileUpdateStarted { uid } => ("profile-update-started", Ok(json!({ "uid": uid }))),
             FrontendEvent::ProfileUpdateCompleted { uid } => ("profile-update-completed", Ok(json!({ "uid": uid }))),
+            FrontendEvent::ProfileChangeReport { uid, report } => (
+                "profile-change-report",
+                serde_json::to_value(report).map(|report| json!({ "uid": uid, "report": report })),
+            ),
+            FrontendEvent::ProfileBatchProgress(done, total, report) => (
+                "profile-batch-progress",
+                serde_json::to_value(report).map(|report| json!({ "done": done, "total": total, "report": report })),
+            ),
         }
     }
 
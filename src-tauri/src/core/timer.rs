@@ -1,11 +1,20 @@
-use crate::{config::Config, feat, singleton, utils::resolve::is_resolve_done};
+use crate::{
+    config::{
+        Config, PrfOption, ProfileUpdateReport, ProfileUpdateStatus,
+        profiles::profiles_draft_record_update_failure_safe,
+    },
+    feat, singleton,
+    utils::{help, resolve::is_resolve_done, schedule},
+};
 use anyhow::{Context as _, Result};
 use clash_verge_logging::{Type, logging, logging_error};
-use delay_timer::prelude::{DelayTimer, DelayTimerBuilder, TaskBuilder};
+use delay_timer::prelude::{DelayTimer, DelayTimerBuilder, ScheduleIteratorTimeZone, TaskBuilder};
 use parking_lot::RwLock;
+use serde::Serialize;
 use smartstring::alias::String;
 use std::{
     collections::HashMap,
+    fmt,
     pin::Pin,
     sync::{
         Arc,
@@ -17,12 +26,101 @@ use tokio::time::{sleep, timeout};
 
 type TaskID = u64;
 
+/// timer_map 中 hosts 列表刷新任务的键前缀，与订阅 uid 区分
+const HOSTS_TASK_PREFIX: &str = "hosts::";
+
+/// 每日检查订阅流量及到期时间的任务
+const QUOTA_TASK_KEY: &str = "quota::daily";
+const QUOTA_TASK_INTERVAL_MINUTES: u64 = 24 * 60;
+
+/// 订阅定时更新失败后依次等待的重试间隔（分钟），之后的重试均使用最后一个间隔，
+/// 每次定时更新都会重新开始计算
+const RETRY_BACKOFF_MINUTES: [u64; 3] = [1, 5, 30];
+
 #[derive(Debug, Clone)]
 pub struct TimerTask {
     pub task_id: TaskID,
-    pub interval_minutes: u64,
+    pub schedule: TimerSchedule,
     #[allow(unused)]
     pub last_run: i64, // Timestamp of last execution
+    /// pending retry after a failed update: (task id, run time in seconds)
+    pub retry: Option<(TaskID, i64)>,
+}
+
+/// Failure state of a profile's scheduled updates
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileUpdateFailure {
+    pub uid: String,
+    /// consecutive failed updates
+    pub failures: u32,
+    pub last_error: Option<String>,
+    /// run time of the pending retry in seconds
+    pub next_retry: Option<i64>,
+}
+
+/// 定时任务的触发方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimerSchedule {
+    /// every n minutes
+    Interval(u64),
+    /// normalized cron expression in local time, each run starts after a random delay of up to `jitter_secs`
+    Cron { expr: String, jitter_secs: u64 },
+}
+
+impl TimerSchedule {
+    /// 订阅的定时更新方式，cron 表达式优先于更新间隔
+    fn from_profile_option(uid: &str, option: &PrfOption) -> Option<Self> {
+        if !option.allow_auto_update.unwrap_or_default() {
+            return None;
+        }
+
+        if let Some(expr) = option
+            .update_cron
+            .as_deref()
+            .map(str::trim)
+            .filter(|expr| !expr.is_empty())
+        {
+            match schedule::normalize_cron(expr) {
+                Ok(expr) => {
+                    return Some(Self::Cron {
+                        expr,
+                        jitter_secs: option.update_jitter.unwrap_or(schedule::DEFAULT_CRON_JITTER_SECS),
+                    });
+                }
+                Err(e) => {
+                    logging!(
+                        warn,
+                        Type::Timer,
+                        "订阅 {} 的 cron 表达式无效，改用更新间隔: {}",
+                        uid,
+                        e
+                    );
+                }
+            }
+        }
+
+        option
+            .update_interval
+            .filter(|&interval| interval > 0)
+            .map(Self::Interval)
+    }
+
+    /// 上次更新之后是否已经错过了一次定时更新
+    fn is_due(&self, updated: i64, now: i64) -> bool {
+        match self {
+            Self::Interval(minutes) => now - updated >= *minutes as i64 * 60,
+            Self::Cron { expr, .. } => schedule::next_run(expr, updated).is_some_and(|next| next <= now),
+        }
+    }
+}
+
+impl fmt::Display for TimerSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interval(minutes) => write!(f, "interval={minutes}min"),
+            Self::Cron { expr, jitter_secs } => write!(f, "cron=\"{expr}\", jitter={jitter_secs}s"),
+        }
+    }
 }
 
 pub struct Timer {
@@ -81,9 +179,9 @@ impl Timer {
                 logging!(
                     info,
                     Type::Timer,
-                    "注册了定时任务 - uid={}, interval={}min, task_id={}",
+                    "注册了定时任务 - uid={}, {}, task_id={}",
                     uid,
-                    task.interval_minutes,
+                    task.schedule,
                     task.task_id
                 );
             }
@@ -96,16 +194,11 @@ impl Timer {
             items
                 .iter()
                 .filter_map(|item| {
-                    let allow_auto_update = item.option.as_ref()?.allow_auto_update.unwrap_or_default();
-                    if !allow_auto_update {
-                        return None;
-                    }
-
-                    let interval = item.option.as_ref()?.update_interval? as i64;
-                    let updated = item.updated? as i64;
                     let uid = item.uid.as_ref()?;
+                    let schedule = TimerSchedule::from_profile_option(uid, item.option.as_ref()?)?;
+                    let updated = item.updated? as i64;
 
-                    if interval > 0 && cur_timestamp - updated >= interval * 60 {
+                    if schedule.is_due(updated, cur_timestamp) {
                         logging!(info, Type::Timer, "需要立即更新的配置: uid={}", uid);
                         Some(uid.clone())
                     } else {
@@ -138,6 +231,9 @@ impl Timer {
             }
         }
 
+        // 每日任务首次执行在一天之后，启动时先检查一次
+        feat::check_quota_alerts(None).await;
+
         logging!(info, Type::Timer, "Timer initialization completed");
         Ok(())
     }
@@ -155,7 +251,7 @@ impl Timer {
         logging!(info, Type::Timer, "Refreshing {} timer tasks", diff_map.len());
 
         // Apply changes - first collect operations to perform without holding locks
-        let mut operations_to_add: Vec<(String, TaskID, u64)> = Vec::new();
+        let mut operations_to_add: Vec<(String, TaskID, TimerSchedule)> = Vec::new();
         let _operations_to_remove: Vec<String> = Vec::new();
 
         // Perform sync operations while holding locks
@@ -163,7 +259,10 @@ impl Timer {
             for (uid, diff) in diff_map {
                 match diff {
                     DiffFlag::Del(tid) => {
-                        self.timer_map.write().remove(&uid);
+                        let removed = self.timer_map.write().remove(&uid);
+                        if let Some((retry_id, _)) = removed.and_then(|task| task.retry) {
+                            let _ = self.delay_timer.write().remove_task(retry_id);
+                        }
                         let value = self.delay_timer.write().remove_task(tid);
                         if let Err(e) = value {
                             logging!(
@@ -178,17 +277,18 @@ impl Timer {
                             logging!(debug, Type::Timer, "Removed task {} for uid {}", tid, uid);
                         }
                     }
-                    DiffFlag::Add(tid, interval) => {
+                    DiffFlag::Add(tid, schedule) => {
                         let task = TimerTask {
                             task_id: tid,
-                            interval_minutes: interval,
+                            schedule: schedule.clone(),
                             last_run: chrono::Local::now().timestamp(),
+                            retry: None,
                         };
 
                         self.timer_map.write().insert(uid.clone(), task);
-                        operations_to_add.push((uid, tid, interval));
+                        operations_to_add.push((uid, tid, schedule));
                     }
-                    DiffFlag::Mod(tid, interval) => {
+                    DiffFlag::Mod(tid, schedule) => {
                         // Remove old task first
                         let value = self.delay_timer.write().remove_task(tid);
                         if let Err(e) = value {
@@ -202,15 +302,17 @@ impl Timer {
                             );
                         }
 
-                        // Then add the new one
+                        // Then add the new one, a pending retry is kept
+                        let retry = self.timer_map.read().get(&uid).and_then(|task| task.retry);
                         let task = TimerTask {
                             task_id: tid,
-                            interval_minutes: interval,
+                            schedule: schedule.clone(),
                             last_run: chrono::Local::now().timestamp(),
+                            retry,
                         };
 
                         self.timer_map.write().insert(uid.clone(), task);
-                        operations_to_add.push((uid, tid, interval));
+                        operations_to_add.push((uid, tid, schedule));
                     }
                 }
             }
@@ -218,8 +320,8 @@ impl Timer {
 
         // Now perform async operations without holding locks
         let delay_timer = self.delay_timer.write();
-        for (uid, tid, interval) in operations_to_add {
-            if let Err(e) = self.add_task(&delay_timer, uid.clone(), tid, interval) {
+        for (uid, tid, schedule) in operations_to_add {
+            if let Err(e) = self.add_task(&delay_timer, uid.clone(), tid, schedule) {
                 logging_error!(Type::Timer, "Failed to add task for uid {}: {}", uid, e);
                 // Rollback on failure - remove from timer_map
                 self.timer_map.write().remove(&uid);
@@ -231,30 +333,46 @@ impl Timer {
         Ok(())
     }
 
-    /// Generate map of profile UIDs to update intervals
-    async fn gen_map(&self) -> HashMap<String, u64> {
+    /// Generate map of profile UIDs to update schedules
+    async fn gen_map(&self) -> HashMap<String, TimerSchedule> {
         let mut new_map = HashMap::new();
 
         if let Some(items) = Config::profiles().await.latest_arc().get_items() {
             for item in items.iter() {
-                if let Some(option) = item.option.as_ref()
-                    && let Some(allow_auto_update) = option.allow_auto_update
-                    && let (Some(interval), Some(uid)) = (option.update_interval, &item.uid)
-                    && allow_auto_update
+                if let (Some(option), Some(uid)) = (item.option.as_ref(), &item.uid)
+                    && let Some(schedule) = TimerSchedule::from_profile_option(uid, option)
+                {
+                    logging!(debug, Type::Timer, "找到定时更新配置: uid={}, {}", uid, schedule);
+                    new_map.insert(uid.clone(), schedule);
+                }
+            }
+        }
+
+        if let Some(sources) = Config::verge().await.latest_arc().hosts_sources.as_ref() {
+            for source in sources {
+                if let (Some(uid), Some(interval)) = (&source.uid, source.update_interval)
                     && interval > 0
                 {
                     logging!(
                         debug,
                         Type::Timer,
-                        "找到定时更新配置: uid={}, interval={}min",
+                        "找到定时刷新 hosts 列表: uid={}, interval={}min",
                         uid,
                         interval
                     );
-                    new_map.insert(uid.clone(), interval);
+                    new_map.insert(
+                        format!("{HOSTS_TASK_PREFIX}{uid}").into(),
+                        TimerSchedule::Interval(interval),
+                    );
                 }
             }
         }
 
+        new_map.insert(
+            QUOTA_TASK_KEY.into(),
+            TimerSchedule::Interval(QUOTA_TASK_INTERVAL_MINUTES),
+        );
+
         logging!(debug, Type::Timer, "生成的定时更新配置数量: {}", new_map.len());
         new_map
     }
@@ -271,17 +389,17 @@ impl Timer {
         // Find tasks to modify or delete
         for (uid, task) in timer_map.iter() {
             match new_map.get(uid) {
-                Some(&interval) if interval != task.interval_minutes => {
-                    // Task exists but interval changed
+                Some(schedule) if *schedule != task.schedule => {
+                    // Task exists but schedule changed
                     logging!(
                         debug,
                         Type::Timer,
                         "定时任务间隔变更: uid={}, 旧={}, 新={}",
                         uid,
-                        task.interval_minutes,
-                        interval
+                        task.schedule,
+                        schedule
                     );
-                    diff_map.insert(uid.clone(), DiffFlag::Mod(task.task_id, interval));
+                    diff_map.insert(uid.clone(), DiffFlag::Mod(task.task_id, schedule.clone()));
                 }
                 None => {
                     // Task no longer needed
@@ -289,7 +407,7 @@ impl Timer {
                     diff_map.insert(uid.clone(), DiffFlag::Del(task.task_id));
                 }
                 _ => {
-                    // Task exists with same interval, no change needed
+                    // Task exists with same schedule, no change needed
                     logging!(debug, Type::Timer, "定时任务保持不变: uid={}", uid);
                 }
             }
@@ -299,16 +417,10 @@ impl Timer {
         let mut next_id = self.timer_count.load(Ordering::Relaxed);
         let original_id = next_id;
 
-        for (uid, &interval) in new_map.iter() {
+        for (uid, schedule) in new_map.iter() {
             if !timer_map.contains_key(uid) {
-                logging!(
-                    debug,
-                    Type::Timer,
-                    "新增定时任务: uid={}, interval={}min",
-                    uid,
-                    interval
-                );
-                diff_map.insert(uid.clone(), DiffFlag::Add(next_id, interval));
+                logging!(debug, Type::Timer, "新增定时任务: uid={}, {}", uid, schedule);
+                diff_map.insert(uid.clone(), DiffFlag::Add(next_id, schedule.clone()));
                 next_id += 1;
             }
         }
@@ -323,26 +435,43 @@ impl Timer {
     }
 
     /// Add a timer task with better error handling
-    fn add_task(&self, delay_timer: &DelayTimer, uid: String, tid: TaskID, minutes: u64) -> Result<()> {
-        logging!(
-            info,
-            Type::Timer,
-            "Adding task: uid={}, id={}, interval={}min",
-            uid,
-            tid,
-            minutes
-        );
+    fn add_task(&self, delay_timer: &DelayTimer, uid: String, tid: TaskID, schedule: TimerSchedule) -> Result<()> {
+        logging!(info, Type::Timer, "Adding task: uid={}, id={}, {}", uid, tid, schedule);
 
         // Create a task with reasonable retries and backoff
-        let task = TaskBuilder::default()
-            .set_task_id(tid)
-            .set_maximum_parallel_runnable_num(1)
-            .set_frequency_repeated_by_minutes(minutes)
+        let mut builder = TaskBuilder::default();
+        builder.set_task_id(tid).set_maximum_parallel_runnable_num(1);
+        let jitter_secs = match &schedule {
+            TimerSchedule::Interval(minutes) => {
+                builder.set_frequency_repeated_by_minutes(*minutes);
+                0
+            }
+            TimerSchedule::Cron { expr, jitter_secs } => {
+                builder
+                    .set_frequency_repeated_by_cron_str(expr)
+                    .set_schedule_iterator_time_zone(ScheduleIteratorTimeZone::Local);
+                *jitter_secs
+            }
+        };
+
+        let task = builder
             .spawn_async_routine(move || {
                 let uid = uid.clone();
                 Box::pin(async move {
+                    // 随机延迟，避免大量客户端在同一时刻请求订阅
+                    if jitter_secs > 0 {
+                        let delay = getrandom::u64().unwrap_or_default() % (jitter_secs + 1);
+                        sleep(Duration::from_secs(delay)).await;
+                    }
                     Self::wait_until_resolve_done(Duration::from_millis(5000)).await;
-                    Self::async_task(&uid).await;
+                    if uid == QUOTA_TASK_KEY {
+                        feat::check_quota_alerts(None).await;
+                        return;
+                    }
+                    match uid.strip_prefix(HOSTS_TASK_PREFIX) {
+                        Some(source_uid) => Self::hosts_task(source_uid).await,
+                        None => Self::async_task(&uid, 0).await,
+                    }
                 }) as Pin<Box<dyn std::future::Future<Output = ()> + Send>>
             })
             .context("failed to create timer task")?;
@@ -360,7 +489,15 @@ impl Timer {
         let task_interval = {
             let timer_map = self.timer_map.read();
             match timer_map.get(uid) {
-                Some(t) => t.interval_minutes,
+                Some(t) => match &t.schedule {
+                    TimerSchedule::Interval(minutes) => *minutes,
+                    // cron 任务按本地时间触发，与上次更新时间无关（不含随机延迟）
+                    TimerSchedule::Cron { expr, .. } => {
+                        let next_time = schedule::next_run(expr, chrono::Local::now().timestamp());
+                        logging!(info, Type::Timer, "计算得到下次更新时间: {:?}, uid={}", next_time, uid);
+                        return next_time;
+                    }
+                },
                 None => {
                     logging!(warn, Type::Timer, "找不到对应的定时任务，uid={}", uid);
                     return None;
@@ -420,11 +557,12 @@ impl Timer {
     }
 
     /// Async task with better error handling and logging
-    async fn async_task(uid: &String) {
+    /// `attempt` 为本轮定时更新中的重试次数，定时触发时为 0
+    async fn async_task(uid: &String, attempt: usize) {
         let task_start = std::time::Instant::now();
         logging!(info, Type::Timer, "Running timer task for profile: {}", uid);
 
-        match tokio::time::timeout(std::time::Duration::from_secs(40), async {
+        let error = match tokio::time::timeout(std::time::Duration::from_secs(40), async {
             Self::emit_update_event(uid, true);
 
             let is_current = Config::profiles().await.latest_arc().current.as_ref() == Some(uid);
@@ -434,23 +572,28 @@ impl Timer {
         })
         .await
         {
-            Ok(result) => match result {
-                Ok(_) => {
-                    let duration = task_start.elapsed().as_millis();
-                    logging!(
-                        info,
-                        Type::Timer,
-                        "Timer task completed successfully for uid: {} (took {}ms)",
-                        uid,
-                        duration
-                    );
-                }
-                Err(e) => {
-                    logging_error!(Type::Timer, "Failed to update profile uid {}: {}", uid, e);
-                }
-            },
+            Ok(result) => update_error(result),
             Err(_) => {
                 logging_error!(Type::Timer, "Timer task timed out for uid: {}", uid);
+                Some("update timed out".into())
+            }
+        };
+
+        match error {
+            Some(error) => {
+                logging_error!(Type::Timer, "Failed to update profile uid {}: {}", uid, error);
+                Self::retry_after_failure(uid, error, attempt).await;
+            }
+            None => {
+                let duration = task_start.elapsed().as_millis();
+                logging!(
+                    info,
+                    Type::Timer,
+                    "Timer task completed successfully for uid: {} (took {}ms)",
+                    uid,
+                    duration
+                );
+                Self::global().cancel_retry(uid);
             }
         }
 
@@ -458,6 +601,146 @@ impl Timer {
         Self::emit_update_event(uid, false);
     }
 
+    /// 记录失败次数，按退避间隔安排下一次重试
+    async fn retry_after_failure(uid: &String, error: String, attempt: usize) {
+        if let Err(e) = profiles_draft_record_update_failure_safe(uid, error).await {
+            logging_error!(Type::Timer, "Failed to record update failure for uid {}: {}", uid, e);
+            return;
+        }
+
+        let allow_auto_update = Config::profiles()
+            .await
+            .latest_arc()
+            .get_item(uid)
+            .ok()
+            .and_then(|item| item.option.as_ref()?.allow_auto_update)
+            .unwrap_or_default();
+        if !allow_auto_update {
+            return;
+        }
+
+        if let Err(e) = Self::global().schedule_retry(uid, attempt) {
+            logging_error!(Type::Timer, "Failed to schedule retry for uid {}: {}", uid, e);
+        }
+    }
+
+    fn schedule_retry(&self, uid: &String, attempt: usize) -> Result<()> {
+        // 定时任务已被删除时不再重试
+        if !self.timer_map.read().contains_key(uid) {
+            return Ok(());
+        }
+
+        let minutes = retry_backoff_minutes(attempt);
+
+        let retry_id = self.timer_count.fetch_add(1, Ordering::Relaxed);
+        let task_uid = uid.clone();
+        let task = TaskBuilder::default()
+            .set_task_id(retry_id)
+            .set_maximum_parallel_runnable_num(1)
+            .set_frequency_once_by_minutes(minutes)
+            .spawn_async_routine(move || {
+                let uid = task_uid.clone();
+                Box::pin(async move {
+                    Self::retry_task(&uid, attempt + 1).await;
+                }) as Pin<Box<dyn std::future::Future<Output = ()> + Send>>
+            })
+            .context("failed to create retry task")?;
+        self.delay_timer
+            .write()
+            .add_task(task)
+            .context("failed to add retry task")?;
+
+        let run_at = chrono::Local::now().timestamp() + minutes as i64 * 60;
+        let previous = self
+            .timer_map
+            .write()
+            .get_mut(uid)
+            .and_then(|task| task.retry.replace((retry_id, run_at)));
+        if let Some((previous_id, _)) = previous {
+            let _ = self.delay_timer.write().remove_task(previous_id);
+        }
+        logging!(info, Type::Timer, "订阅 {} 将在 {} 分钟后重试更新", uid, minutes);
+        Ok(())
+    }
+
+    fn cancel_retry(&self, uid: &String) {
+        let retry = self.timer_map.write().get_mut(uid).and_then(|task| task.retry.take());
+        if let Some((retry_id, _)) = retry {
+            let _ = self.delay_timer.write().remove_task(retry_id);
+        }
+    }
+
+    async fn retry_task(uid: &String, attempt: usize) {
+        let scheduled = Self::global()
+            .timer_map
+            .write()
+            .get_mut(uid)
+            .and_then(|task| task.retry.take())
+            .is_some();
+        if !scheduled {
+            return;
+        }
+
+        // 等待期间已关闭自动更新或已更新成功时跳过
+        let pending = Config::profiles().await.latest_arc().get_item(uid).is_ok_and(|item| {
+            item.update_failures.unwrap_or(0) > 0
+                && item
+                    .option
+                    .as_ref()
+                    .and_then(|option| option.allow_auto_update)
+                    .unwrap_or_default()
+        });
+        if !pending {
+            return;
+        }
+
+        logging!(info, Type::Timer, "Retrying profile update: {}", uid);
+        Self::wait_until_resolve_done(Duration::from_millis(5000)).await;
+        Self::async_task(uid, attempt).await;
+    }
+
+    /// 定时更新失败的订阅及其重试状态
+    pub async fn update_failures(&self) -> Vec<ProfileUpdateFailure> {
+        let items = Config::profiles()
+            .await
+            .latest_arc()
+            .get_items()
+            .cloned()
+            .unwrap_or_default();
+        let timer_map = self.timer_map.read();
+        items
+            .into_iter()
+            .filter_map(|item| {
+                let failures = item.update_failures.filter(|&failures| failures > 0)?;
+                let uid = item.uid?;
+                let next_retry = timer_map
+                    .get(&uid)
+                    .and_then(|task| task.retry)
+                    .map(|(_, run_at)| run_at);
+                Some(ProfileUpdateFailure {
+                    uid,
+                    failures,
+                    last_error: item.update_error,
+                    next_retry,
+                })
+            })
+            .collect()
+    }
+
+    /// Refresh an imported hosts list
+    async fn hosts_task(source_uid: &str) {
+        logging!(info, Type::Timer, "Running hosts refresh task: {}", source_uid);
+        match tokio::time::timeout(Duration::from_secs(60), feat::refresh_hosts_source(source_uid)).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                logging_error!(Type::Timer, "Failed to refresh hosts source {}: {}", source_uid, e);
+            }
+            Err(_) => {
+                logging_error!(Type::Timer, "Hosts refresh task timed out: {}", source_uid);
+            }
+        }
+    }
+
     async fn wait_until_resolve_done(max_wait: Duration) {
         let _ = timeout(max_wait, async {
             while !is_resolve_done() {
@@ -472,6 +755,69 @@ impl Timer {
 #[derive(Debug)]
 enum DiffFlag {
     Del(TaskID),
-    Add(TaskID, u64),
-    Mod(TaskID, u64),
+    Add(TaskID, TimerSchedule),
+    Mod(TaskID, TimerSchedule),
+}
+
+/// 更新结果中的错误信息，状态为 `Failed` 的报告同样视为失败
+fn update_error(result: Result<ProfileUpdateReport>) -> Option<String> {
+    match result {
+        Ok(report) if report.status == ProfileUpdateStatus::Failed => {
+            Some(report.error.unwrap_or_else(|| "update failed".into()))
+        }
+        Ok(_) => None,
+        Err(e) => Some(help::mask_err(&e.to_string()).into()),
+    }
+}
+
+/// 第 `attempt` 次重试前的等待时间，超出退避序列后保持最后一个间隔
+fn retry_backoff_minutes(attempt: usize) -> u64 {
+    RETRY_BACKOFF_MINUTES
+        .get(attempt)
+        .or(RETRY_BACKOFF_MINUTES.last())
+        .copied()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_report_is_an_update_error() {
+        let failed = ProfileUpdateReport {
+            status: ProfileUpdateStatus::Failed,
+            error: Some("all urls failed".into()),
+            ..Default::default()
+        };
+        assert_eq!(update_error(Ok(failed)), Some("all urls failed".into()));
+
+        let failed = ProfileUpdateReport {
+            status: ProfileUpdateStatus::Failed,
+            ..Default::default()
+        };
+        assert_eq!(update_error(Ok(failed)), Some("update failed".into()));
+
+        for status in [
+            ProfileUpdateStatus::Updated,
+            ProfileUpdateStatus::NotModified,
+            ProfileUpdateStatus::Skipped,
+        ] {
+            let report = ProfileUpdateReport {
+                status,
+                ..Default::default()
+            };
+            assert_eq!(update_error(Ok(report)), None);
+        }
+        assert!(update_error(Err(anyhow::anyhow!("timeout"))).is_some());
+    }
+
+    #[test]
+    fn retry_backoff_is_capped() {
+        assert_eq!(retry_backoff_minutes(0), 1);
+        assert_eq!(retry_backoff_minutes(1), 5);
+        assert_eq!(retry_backoff_minutes(2), 30);
+        assert_eq!(retry_backoff_minutes(3), 30);
+        assert_eq!(retry_backoff_minutes(100), 30);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::config::{IProfilePreview, IVerge};
+use crate::config::{FETCH_NODE_PREFIX, IProfilePreview, IVerge};
 use crate::core::service;
 use crate::core::tray::menu_def::TrayAction;
 use crate::module::lightweight;
@@ -423,24 +423,100 @@ fn create_hotkeys(hotkeys: &Option<Vec<String>>) -> HashMap<String, String> {
         .unwrap_or_default()
 }
 
+/// 订阅菜单中的一项，`a/b` 形式的文件夹生成嵌套的子菜单
+enum ProfileMenuEntry<'p> {
+    Profile(&'p IProfilePreview<'p>),
+    Folder {
+        name: &'p str,
+        /// 从根开始的完整路径，用作菜单 id
+        path: &'p str,
+        entries: Vec<Self>,
+    },
+}
+
+/// 按订阅的原有顺序构建文件夹树，文件夹位于其中第一个订阅所在的位置
+fn profile_menu_tree<'p>(profiles: &'p [IProfilePreview<'p>]) -> Vec<ProfileMenuEntry<'p>> {
+    let mut root = Vec::new();
+    for profile in profiles {
+        match profile.folder {
+            Some(folder) => insert_into_folder(&mut root, folder, 0, profile),
+            None => root.push(ProfileMenuEntry::Profile(profile)),
+        }
+    }
+    root
+}
+
+/// 将订阅放入 `folder` 中从 `offset` 开始的下一级文件夹
+fn insert_into_folder<'p>(
+    entries: &mut Vec<ProfileMenuEntry<'p>>,
+    folder: &'p str,
+    offset: usize,
+    profile: &'p IProfilePreview<'p>,
+) {
+    let Some(rest) = folder.get(offset..) else {
+        entries.push(ProfileMenuEntry::Profile(profile));
+        return;
+    };
+    let name = rest.split('/').next().unwrap_or(rest);
+    let path = &folder[..offset + name.len()];
+
+    let index = entries
+        .iter()
+        .position(|entry| matches!(entry, ProfileMenuEntry::Folder { path: own, .. } if *own == path))
+        .unwrap_or_else(|| {
+            entries.push(ProfileMenuEntry::Folder {
+                name,
+                path,
+                entries: Vec::new(),
+            });
+            entries.len() - 1
+        });
+    if let ProfileMenuEntry::Folder { entries: children, .. } = &mut entries[index] {
+        insert_into_folder(children, folder, path.len() + 1, profile);
+    }
+}
+
 fn create_profile_menu_item(
     app_handle: &AppHandle,
     profiles_preview: Vec<IProfilePreview<'_>>,
-) -> Result<Vec<CheckMenuItem<Wry>>> {
-    profiles_preview
-        .into_iter()
-        .map(|profile| {
-            CheckMenuItem::with_id(
-                app_handle,
-                format!("profiles_{}", profile.uid),
-                profile.name,
-                true,
-                profile.is_current,
-                None::<&str>,
-            )
-            .map_err(|e| e.into())
-        })
-        .collect()
+) -> Result<Vec<Box<dyn IsMenuItem<Wry>>>> {
+    create_profile_menu_entries(app_handle, &profile_menu_tree(&profiles_preview))
+}
+
+fn create_profile_menu_entries(
+    app_handle: &AppHandle,
+    entries: &[ProfileMenuEntry<'_>],
+) -> Result<Vec<Box<dyn IsMenuItem<Wry>>>> {
+    let mut menu_items: Vec<Box<dyn IsMenuItem<Wry>>> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match entry {
+            ProfileMenuEntry::Profile(profile) => {
+                let item = CheckMenuItem::with_id(
+                    app_handle,
+                    format!("profiles_{}", profile.uid),
+                    profile.name,
+                    true,
+                    profile.is_current,
+                    None::<&str>,
+                )?;
+                menu_items.push(Box::new(item));
+            }
+            ProfileMenuEntry::Folder { name, path, entries } => {
+                let children = create_profile_menu_entries(app_handle, entries)?;
+                let children_refs: Vec<&dyn IsMenuItem<Wry>> = children.iter().map(Box::as_ref).collect();
+                let submenu = Submenu::with_id_and_items(
+                    app_handle,
+                    format!("profile_folder_{path}"),
+                    name,
+                    true,
+                    &children_refs,
+                )?;
+                menu_items.push(Box::new(submenu));
+            }
+        }
+    }
+
+    Ok(menu_items)
 }
 
 fn create_subcreate_proxy_menu_item(
@@ -474,6 +550,8 @@ fn create_subcreate_proxy_menu_item(
                 // Create proxy items
                 let group_items: Vec<CheckMenuItem<Wry>> = all_proxies
                     .iter()
+                    // 拉取订阅用的内部节点不展示
+                    .filter(|proxy_str| !proxy_str.starts_with(FETCH_NODE_PREFIX))
                     .filter_map(|proxy_str| {
                         let is_selected = *proxy_str == now_proxy;
                         let item_id = format!("proxy_{}_{}", group_name, proxy_str);
@@ -640,15 +718,12 @@ async fn create_tray_menu(
 
     let hotkeys = create_hotkeys(&verge_settings.hotkeys);
 
-    let profile_menu_items: Vec<CheckMenuItem<Wry>> = create_profile_menu_item(app_handle, profiles_preview)?;
+    let profile_menu_items = create_profile_menu_item(app_handle, profiles_preview)?;
 
     // Pre-fetch all localized strings
     let texts = MenuTexts::new();
     // Convert to references only when needed
-    let profile_menu_items_refs: Vec<&dyn IsMenuItem<Wry>> = profile_menu_items
-        .iter()
-        .map(|item| item as &dyn IsMenuItem<Wry>)
-        .collect();
+    let profile_menu_items_refs: Vec<&dyn IsMenuItem<Wry>> = profile_menu_items.iter().map(Box::as_ref).collect();
 
     let open_window = &MenuItem::with_id(
         app_handle,
@@ -998,3 +1073,49 @@ fn on_menu_event(_: &AppHandle, event: MenuEvent) {
         // as the inner handle function (SHOULD) already takes care of it
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn describe(entries: &[ProfileMenuEntry<'_>]) -> std::string::String {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                ProfileMenuEntry::Profile(profile) => profile.name.to_string(),
+                ProfileMenuEntry::Folder { name, path, entries } => {
+                    format!("{name}({path})[{}]", describe(entries))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    #[test]
+    fn nested_profile_folders_keep_user_order() {
+        let names: Vec<String> = ["p1", "p2", "p3", "p4", "p5"].into_iter().map(Into::into).collect();
+        let folders: Vec<String> = ["a/b", "a", "c"].into_iter().map(Into::into).collect();
+        let folder_of = [
+            Some(&folders[0]),
+            None,
+            Some(&folders[1]),
+            Some(&folders[0]),
+            Some(&folders[2]),
+        ];
+        let profiles: Vec<IProfilePreview<'_>> = names
+            .iter()
+            .zip(folder_of)
+            .map(|(name, folder)| IProfilePreview {
+                uid: name,
+                name,
+                folder,
+                is_current: false,
+            })
+            .collect();
+
+        assert_eq!(
+            describe(&profile_menu_tree(&profiles)),
+            "a(a)[b(a/b)[p1,p4],p3],p2,c(c)[p5]"
+        );
+    }
+}
@@ -132,8 +132,13 @@ fn version_lte(a: &str, b: &str) -> bool {
 impl SilentUpdater {
     /// Called at app startup. If a cached update exists and is newer than the current version,
     /// attempt to install it immediately (before the main app initializes).
+    /// `before_install` runs right before installing, only when the cached update is still valid.
     /// Returns true if install was triggered (app should relaunch), false otherwise.
-    pub async fn try_install_on_startup(&self, app_handle: &tauri::AppHandle) -> bool {
+    pub async fn try_install_on_startup(
+        &self,
+        app_handle: &tauri::AppHandle,
+        before_install: impl Future<Output = ()>,
+    ) -> bool {
         let current_version = env!("CARGO_PKG_VERSION");
 
         let meta = match Self::read_cache_meta() {
@@ -237,6 +242,8 @@ impl SilentUpdater {
         // Show splash window so user knows the app is updating, not frozen
         Self::show_update_splash(app_handle, &version);
 
+        before_install.await;
+
         // install() is sync and may hang (known bug #2558), so run with a timeout.
         // On Windows, NSIS takes over the process so install() may never return — that's OK.
         let install_result = tokio::task::spawn_blocking({
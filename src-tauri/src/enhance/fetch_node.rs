@@ -0,0 +1,175 @@
+use crate::config::{Config, FETCH_NODE_PREFIX, PrfFetchNode, PrfItem};
+use clash_verge_logging::{Type, logging};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use std::{collections::HashMap, time::Duration};
+use tokio::net::TcpStream;
+
+/// 等待监听器就绪的重试次数及间隔
+const READY_RETRIES: u32 = 10;
+const READY_INTERVAL: Duration = Duration::from_millis(300);
+
+/// 节点 -> 本地监听端口，配置重新生成时尽量保持端口不变
+static FETCH_NODE_PORTS: Lazy<RwLock<HashMap<PrfFetchNode, u16>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Local proxy url that exits through the node
+/// 仅在内核的监听器确实可连接后返回；端口始终无法连接时（例如被其他进程抢占）丢弃该端口，
+/// 下次生成运行配置时重新分配
+pub async fn fetch_node_proxy_url(node: &PrfFetchNode) -> Option<String> {
+    for _ in 0..READY_RETRIES {
+        let port = FETCH_NODE_PORTS.read().get(node).copied()?;
+        if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            return Some(format!("http://127.0.0.1:{port}").into());
+        }
+        tokio::time::sleep(READY_INTERVAL).await;
+    }
+
+    if let Some(port) = FETCH_NODE_PORTS.write().remove(node) {
+        logging!(
+            warn,
+            Type::Config,
+            "Warning: 节点 {} 的本地监听端口 {} 不可用",
+            node.name,
+            port
+        );
+    }
+    None
+}
+
+/// 为订阅设置的 `fetch_node` 生成 mihomo 入站监听器
+/// 节点从其所在订阅复制到运行配置中，监听器的流量直接交由该节点处理，不经过规则匹配
+pub async fn use_fetch_listeners(mut config: Mapping) -> Mapping {
+    let items = Config::profiles()
+        .await
+        .latest_arc()
+        .get_items()
+        .cloned()
+        .unwrap_or_default();
+    let mut nodes: Vec<&PrfFetchNode> = items
+        .iter()
+        .filter_map(|item| item.option.as_ref()?.fetch_node.as_ref())
+        .collect();
+    nodes.sort_by(|a, b| (&a.profile, &a.name).cmp(&(&b.profile, &b.name)));
+    nodes.dedup();
+
+    let previous_ports = FETCH_NODE_PORTS.read().clone();
+    let mut ports = HashMap::new();
+    let mut proxies = Vec::new();
+    let mut listeners = Vec::new();
+    for node in nodes {
+        let Some(mut proxy) = find_node(&items, node).await else {
+            logging!(
+                warn,
+                Type::Config,
+                "Warning: 用于拉取订阅的节点不存在: {}/{}",
+                node.profile,
+                node.name
+            );
+            continue;
+        };
+        let Some(port) = previous_ports.get(node).copied().or_else(unused_port) else {
+            logging!(warn, Type::Config, "Warning: 无法为节点 {} 分配本地端口", node.name);
+            continue;
+        };
+
+        let name = format!("{FETCH_NODE_PREFIX}{port}");
+        proxy.insert("name".into(), name.as_str().into());
+        proxies.push(Value::Mapping(proxy));
+
+        let mut listener = Mapping::new();
+        listener.insert("name".into(), name.as_str().into());
+        listener.insert("type".into(), "mixed".into());
+        listener.insert("listen".into(), "127.0.0.1".into());
+        listener.insert("port".into(), port.into());
+        listener.insert("proxy".into(), name.as_str().into());
+        listeners.push(Value::Mapping(listener));
+
+        ports.insert(node.clone(), port);
+    }
+
+    if !proxies.is_empty() {
+        append_sequence(&mut config, "proxies", proxies);
+        append_sequence(&mut config, "listeners", listeners);
+        exclude_from_groups(&mut config);
+    }
+    *FETCH_NODE_PORTS.write() = ports;
+    config
+}
+
+async fn find_node(items: &[PrfItem], node: &PrfFetchNode) -> Option<Mapping> {
+    let item = items.iter().find(|item| item.uid.as_ref() == Some(&node.profile))?;
+    let content = item.read_file().await.ok()?;
+    let profile = serde_yaml_ng::from_str::<Mapping>(&content).ok()?;
+    profile
+        .get("proxies")?
+        .as_sequence()?
+        .iter()
+        .filter_map(Value::as_mapping)
+        .find(|proxy| proxy.get("name").and_then(Value::as_str) == Some(node.name.as_str()))
+        .cloned()
+}
+
+fn unused_port() -> Option<u16> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").ok()?;
+    listener.local_addr().ok().map(|addr| addr.port())
+}
+
+/// 使用 `include-all` 的代理组会自动收录所有节点，通过 `exclude-filter` 将注入的节点排除
+fn exclude_from_groups(config: &mut Mapping) {
+    let Some(groups) = config.get_mut("proxy-groups").and_then(Value::as_sequence_mut) else {
+        return;
+    };
+    let pattern = format!("^{FETCH_NODE_PREFIX}");
+    for group in groups.iter_mut().filter_map(Value::as_mapping_mut) {
+        let include_all = ["include-all", "include-all-proxies"]
+            .iter()
+            .any(|key| group.get(*key).and_then(Value::as_bool) == Some(true));
+        if !include_all {
+            continue;
+        }
+        // mihomo 使用反引号分隔多个过滤表达式
+        let filter = match group.get("exclude-filter").and_then(Value::as_str) {
+            Some(filter) if !filter.is_empty() => format!("{filter}`{pattern}"),
+            _ => pattern.clone(),
+        };
+        group.insert("exclude-filter".into(), filter.into());
+    }
+}
+
+fn append_sequence(config: &mut Mapping, key: &str, values: Vec<Value>) {
+    let mut seq = config
+        .get(key)
+        .and_then(Value::as_sequence)
+        .cloned()
+        .unwrap_or_default();
+    seq.extend(values);
+    config.insert(key.into(), Value::Sequence(seq));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn exclude_injected_nodes_from_include_all_groups() {
+        let mut config: Mapping = serde_yaml_ng::from_str(
+            r#"
+proxy-groups:
+  - { name: auto, type: url-test, include-all: true }
+  - { name: hk, type: select, include-all-proxies: true, exclude-filter: "US" }
+  - { name: manual, type: select, proxies: [a, b] }
+"#,
+        )
+        .unwrap();
+        exclude_from_groups(&mut config);
+
+        let groups = config.get("proxy-groups").and_then(Value::as_sequence).unwrap();
+        let filter = |index: usize| groups[index].get("exclude-filter").and_then(Value::as_str);
+        assert_eq!(filter(0), Some("^verge-fetch-"));
+        assert_eq!(filter(1), Some("US`^verge-fetch-"));
+        assert_eq!(filter(2), None);
+    }
+}
@@ -1,4 +1,5 @@
 mod chain;
+pub mod fetch_node;
 pub mod field;
 mod merge;
 mod script;
@@ -11,16 +12,22 @@ use self::{
     merge::use_merge,
     script::use_script,
     seq::{SeqMap, use_seq},
-    tun::use_tun,
+    tun::{TunRouteExclude, use_tun, use_tun_route_exclude},
 };
 use crate::utils::dirs;
-use crate::{config::Config, utils::tmpl};
 use crate::{config::IVerge, constants};
+use crate::{
+    config::{Config, IProfiles},
+    utils::tmpl,
+};
 use anyhow::{Context as _, Result};
 use clash_verge_logging::{Type, logging};
 use serde_yaml_ng::{Mapping, Value};
 use smartstring::alias::String;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 use tokio::fs;
 
 type ResultLog = Vec<(String, String)>;
@@ -29,10 +36,12 @@ struct ConfigValues {
     clash_config: Mapping,
     clash_core: Option<String>,
     enable_tun: bool,
+    tun_route_exclude: TunRouteExclude,
     enable_builtin: bool,
     socks_enabled: bool,
     http_enabled: bool,
     enable_dns_settings: bool,
+    enable_external_controller: bool,
     #[cfg(not(target_os = "windows"))]
     redir_enabled: bool,
     #[cfg(target_os = "linux")]
@@ -89,16 +98,7 @@ impl Default for ProfileItems {
     }
 }
 
-async fn get_config_values() -> ConfigValues {
-    let clash = Config::clash().await;
-    let clash_arc = clash.latest_arc();
-    let clash_config = clash_arc.0.clone();
-    drop(clash_arc);
-    drop(clash);
-
-    let verge = Config::verge().await;
-
-    let verge_arc = verge.latest_arc();
+fn get_config_values(clash_config: Mapping, verge: &IVerge) -> ConfigValues {
     let IVerge {
         ref enable_tun_mode,
         ref enable_builtin_enhanced,
@@ -106,10 +106,10 @@ async fn get_config_values() -> ConfigValues {
         ref verge_http_enabled,
         ref enable_dns_settings,
         ..
-    } = *verge_arc;
+    } = *verge;
 
     let (clash_core, enable_tun, enable_builtin, socks_enabled, http_enabled, enable_dns_settings) = (
-        Some(verge_arc.get_valid_clash_core()),
+        Some(verge.get_valid_clash_core()),
         enable_tun_mode.unwrap_or(false),
         enable_builtin_enhanced.unwrap_or(true),
         verge_socks_enabled.unwrap_or(false),
@@ -118,22 +118,27 @@ async fn get_config_values() -> ConfigValues {
     );
 
     #[cfg(not(target_os = "windows"))]
-    let redir_enabled = verge_arc.verge_redir_enabled.unwrap_or(false);
+    let redir_enabled = verge.verge_redir_enabled.unwrap_or(false);
 
     #[cfg(target_os = "linux")]
-    let tproxy_enabled = verge_arc.verge_tproxy_enabled.unwrap_or(false);
+    let tproxy_enabled = verge.verge_tproxy_enabled.unwrap_or(false);
 
-    drop(verge_arc);
-    drop(verge);
+    let tun_route_exclude = TunRouteExclude {
+        auto: verge.tun_auto_route_exclude.unwrap_or(false),
+        extra: verge.tun_route_exclude_extra.clone().unwrap_or_default(),
+        interface_filter: verge.tun_interface_filter.clone(),
+    };
 
     ConfigValues {
         clash_config,
         clash_core,
         enable_tun,
+        tun_route_exclude,
         enable_builtin,
         socks_enabled,
         http_enabled,
         enable_dns_settings,
+        enable_external_controller: verge.enable_external_controller.unwrap_or(false),
         #[cfg(not(target_os = "windows"))]
         redir_enabled,
         #[cfg(target_os = "linux")]
@@ -142,25 +147,18 @@ async fn get_config_values() -> ConfigValues {
 }
 
 #[allow(clippy::cognitive_complexity)]
-async fn collect_profile_items() -> Result<ProfileItems> {
-    let profiles = Config::profiles().await;
-    let profiles_arc = profiles.latest_arc();
-    drop(profiles);
-
-    let current_profile_uid = match profiles_arc.get_current().cloned() {
+async fn collect_profile_items(profiles: &IProfiles) -> Result<ProfileItems> {
+    let current_profile_uid = match profiles.get_current().cloned() {
         Some(uid) => uid,
-        None => {
-            drop(profiles_arc);
-            return Ok(ProfileItems::default());
-        }
+        None => return Ok(ProfileItems::default()),
     };
 
-    let current = profiles_arc
+    let current = profiles
         .current_mapping()
         .await
         .with_context(|| format!("failed to read current profile \"{current_profile_uid}\""))?;
 
-    let current_item = match profiles_arc.get_item(&current_profile_uid) {
+    let current_item = match profiles.get_item(&current_profile_uid) {
         Ok(item) => item,
         Err(err) => {
             return Err(err).with_context(|| format!("failed to get current profile \"{current_profile_uid}\""));
@@ -185,7 +183,7 @@ async fn collect_profile_items() -> Result<ProfileItems> {
     let name = current_item.name.clone().unwrap_or_default();
 
     let merge_item = {
-        let item = profiles_arc.get_item(&merge_uid).ok().cloned();
+        let item = profiles.get_item(&merge_uid).ok().cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
@@ -198,7 +196,7 @@ async fn collect_profile_items() -> Result<ProfileItems> {
     });
 
     let script_item = {
-        let item = profiles_arc.get_item(&script_uid).ok().cloned();
+        let item = profiles.get_item(&script_uid).ok().cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
@@ -211,7 +209,7 @@ async fn collect_profile_items() -> Result<ProfileItems> {
     });
 
     let rules_item = {
-        let item = profiles_arc.get_item(&rules_uid).ok().cloned();
+        let item = profiles.get_item(&rules_uid).ok().cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
@@ -224,7 +222,7 @@ async fn collect_profile_items() -> Result<ProfileItems> {
     });
 
     let proxies_item = {
-        let item = profiles_arc.get_item(&proxies_uid).ok().cloned();
+        let item = profiles.get_item(&proxies_uid).ok().cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
@@ -237,7 +235,7 @@ async fn collect_profile_items() -> Result<ProfileItems> {
     });
 
     let groups_item = {
-        let item = profiles_arc.get_item(&groups_uid).ok().cloned();
+        let item = profiles.get_item(&groups_uid).ok().cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
@@ -250,7 +248,7 @@ async fn collect_profile_items() -> Result<ProfileItems> {
     });
 
     let global_merge = {
-        let item = profiles_arc.get_item("Merge").ok().cloned();
+        let item = profiles.get_item("Merge").ok().cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
@@ -263,7 +261,7 @@ async fn collect_profile_items() -> Result<ProfileItems> {
     });
 
     let global_script = {
-        let item = profiles_arc.get_item("Script").ok().cloned();
+        let item = profiles.get_item("Script").ok().cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
@@ -275,8 +273,6 @@ async fn collect_profile_items() -> Result<ProfileItems> {
         data: ChainType::Script(tmpl::ITEM_SCRIPT.into()),
     });
 
-    drop(profiles_arc);
-
     Ok(ProfileItems {
         config: current,
         merge_item,
@@ -370,6 +366,7 @@ async fn merge_default_config(
     clash_config: Mapping,
     socks_enabled: bool,
     http_enabled: bool,
+    enable_external_controller: bool,
     #[cfg(not(target_os = "windows"))] redir_enabled: bool,
     #[cfg(target_os = "linux")] tproxy_enabled: bool,
 ) -> Mapping {
@@ -421,12 +418,6 @@ async fn merge_default_config(
             }
             // 处理 external-controller 键的开关逻辑
             if key.as_str() == Some("external-controller") {
-                let enable_external_controller = Config::verge()
-                    .await
-                    .latest_arc()
-                    .enable_external_controller
-                    .unwrap_or(false);
-
                 if enable_external_controller {
                     config.insert(key, value);
                 } else {
@@ -547,12 +538,10 @@ fn cleanup_proxy_groups(mut config: Mapping) -> Mapping {
     config
 }
 
-async fn apply_dns_settings(mut config: Mapping, enable_dns_settings: bool) -> Mapping {
-    if enable_dns_settings && let Ok(app_dir) = dirs::app_home_dir() {
-        let dns_path = app_dir.join(constants::files::DNS_CONFIG);
-
+async fn apply_dns_settings(mut config: Mapping, enable_dns_settings: bool, dns_path: Option<&Path>) -> Mapping {
+    if enable_dns_settings && let Some(dns_path) = dns_path {
         if dns_path.exists()
-            && let Ok(dns_yaml) = fs::read_to_string(&dns_path).await
+            && let Ok(dns_yaml) = fs::read_to_string(dns_path).await
             && let Ok(dns_config) = serde_yaml_ng::from_str::<serde_yaml_ng::Mapping>(&dns_yaml)
         {
             if let Some(hosts_value) = dns_config.get("hosts")
@@ -567,7 +556,8 @@ async fn apply_dns_settings(mut config: Mapping, enable_dns_settings: bool) -> M
                     config.insert("dns".into(), dns_mapping.clone().into());
                     logging!(info, Type::Core, "apply dns_config.yaml (dns section)");
                 }
-            } else {
+            } else if !dns_config.contains_key("hosts") {
+                // 旧格式：整个文件即为 dns 段；仅含 hosts 的文件（如导入 hosts 生成）不视为 dns 配置
                 config.insert("dns".into(), dns_config.into());
                 logging!(info, Type::Core, "apply dns_config.yaml");
             }
@@ -580,16 +570,41 @@ async fn apply_dns_settings(mut config: Mapping, enable_dns_settings: bool) -> M
 /// Enhance mode
 /// 返回最终订阅、该订阅包含的键、和script执行的结果
 pub async fn enhance() -> Result<(Mapping, HashSet<String>, HashMap<String, ResultLog>)> {
+    let clash = Config::clash().await.latest_arc();
+    let verge = Config::verge().await.latest_arc();
+    let profiles = Config::profiles().await.latest_arc();
+    let dns_path = dirs::app_home_dir()
+        .ok()
+        .map(|dir| dir.join(constants::files::DNS_CONFIG));
+    let (config, exists_keys, result_map) =
+        enhance_with(clash.0.clone(), &verge, &profiles, dns_path.as_deref()).await?;
+
+    // listeners for fetching subscriptions through a specific node
+    let config = fetch_node::use_fetch_listeners(config).await;
+
+    Ok((config, exists_keys, result_map))
+}
+
+/// 由给定的 clash/verge/订阅配置生成运行配置，不读取也不修改全局配置
+/// 拉取订阅用的监听器依赖运行状态，只在 [`enhance`] 中添加
+pub async fn enhance_with(
+    clash_config: Mapping,
+    verge: &IVerge,
+    profiles: &IProfiles,
+    dns_path: Option<&Path>,
+) -> Result<(Mapping, HashSet<String>, HashMap<String, ResultLog>)> {
     // gather config values
-    let cfg_vals = get_config_values().await;
+    let cfg_vals = get_config_values(clash_config, verge);
     let ConfigValues {
         clash_config,
         clash_core,
         enable_tun,
+        tun_route_exclude,
         enable_builtin,
         socks_enabled,
         http_enabled,
         enable_dns_settings,
+        enable_external_controller,
         #[cfg(not(target_os = "windows"))]
         redir_enabled,
         #[cfg(target_os = "linux")]
@@ -597,7 +612,7 @@ pub async fn enhance() -> Result<(Mapping, HashSet<String>, HashMap<String, Resu
     } = cfg_vals;
 
     // collect profile items
-    let profile = collect_profile_items().await?;
+    let profile = collect_profile_items(profiles).await?;
     let config = profile.config;
     let merge_item = profile.merge_item;
     let script_item = profile.script_item;
@@ -632,6 +647,7 @@ pub async fn enhance() -> Result<(Mapping, HashSet<String>, HashMap<String, Resu
         clash_config,
         socks_enabled,
         http_enabled,
+        enable_external_controller,
         #[cfg(not(target_os = "windows"))]
         redir_enabled,
         #[cfg(target_os = "linux")]
@@ -648,7 +664,12 @@ pub async fn enhance() -> Result<(Mapping, HashSet<String>, HashMap<String, Resu
     config = use_sort(config);
 
     // dns settings
-    config = apply_dns_settings(config, enable_dns_settings).await;
+    config = apply_dns_settings(config, enable_dns_settings, dns_path).await;
+
+    // tun route exclusions, computed after dns settings so the final fake-ip-range is known
+    if enable_tun {
+        config = use_tun_route_exclude(config, &tun_route_exclude);
+    }
 
     let mut exists_keys_set = HashSet::new();
     exists_keys_set.extend(exists_keys);
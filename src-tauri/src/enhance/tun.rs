@@ -1,4 +1,9 @@
+use crate::utils::network;
+use clash_verge_logging::{Type, logging};
+use network_interface::NetworkInterface;
 use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 #[cfg(target_os = "macos")]
 use crate::process::AsyncHandler;
@@ -82,3 +87,297 @@ pub fn use_tun(mut config: Mapping, enable: bool) -> Mapping {
 
     config
 }
+
+/// TUN 路由排除相关设置，来自 verge 配置
+#[derive(Debug, Default)]
+pub struct TunRouteExclude {
+    /// 根据本机网卡自动生成 route-exclude-address
+    pub auto: bool,
+    /// 用户额外指定的 CIDR
+    pub extra: Vec<String>,
+    /// `include` / `exclude`，仅 Linux 生效
+    pub interface_filter: Option<String>,
+}
+
+/// 未指定 tun.device 时前端使用的默认网卡名
+#[cfg(target_os = "macos")]
+const DEFAULT_TUN_DEVICE: &str = "utun1024";
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_TUN_DEVICE: &str = "Mihomo";
+
+/// 常见的容器、虚拟机网桥及其他 VPN 网卡前缀
+const VIRTUAL_INTERFACE_PREFIXES: &[&str] = &[
+    "docker",
+    "br-",
+    "veth",
+    "virbr",
+    "vnet",
+    "vmnet",
+    "vboxnet",
+    "cni",
+    "flannel",
+    "podman",
+    "lxc",
+    "lxd",
+    "tun",
+    "tap",
+    "utun",
+    "wg",
+    "tailscale",
+    "zt",
+    "ppp",
+];
+
+/// 每次 enhance 时根据当前网卡重新计算，新建的 docker 网络、VPN 网段会被自动排除
+pub fn use_tun_route_exclude(mut config: Mapping, settings: &TunRouteExclude) -> Mapping {
+    if !settings.auto && settings.extra.is_empty() && settings.interface_filter.is_none() {
+        return config;
+    }
+
+    let interfaces = if settings.auto || settings.interface_filter.is_some() {
+        network::network_interfaces().unwrap_or_else(|err| {
+            logging!(warn, Type::Network, "Failed to list network interfaces: {}", err);
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+
+    let mut tun_val = config
+        .get("tun")
+        .and_then(Value::as_mapping)
+        .cloned()
+        .unwrap_or_default();
+    let tun_device = String::from(
+        tun_val
+            .get("device")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_TUN_DEVICE),
+    );
+    let fake_ip_range = config
+        .get("dns")
+        .and_then(|dns| dns.get("fake-ip-range"))
+        .and_then(Value::as_str)
+        .and_then(parse_cidr);
+
+    let mut excludes: Vec<String> = tun_val
+        .get("route-exclude-address")
+        .and_then(Value::as_sequence)
+        .map(|seq| seq.iter().filter_map(Value::as_str).map(Into::into).collect())
+        .unwrap_or_default();
+
+    if settings.auto {
+        for subnet in interface_subnets(&interfaces, &tun_device, fake_ip_range) {
+            push_unique(&mut excludes, subnet);
+        }
+    }
+    for cidr in &settings.extra {
+        match parse_cidr(cidr) {
+            Some((ip, prefix)) => push_unique(&mut excludes, format!("{ip}/{prefix}").into()),
+            None => logging!(warn, Type::Config, "Ignore invalid route exclude address: {}", cidr),
+        }
+    }
+    if !excludes.is_empty() {
+        revise!(tun_val, "route-exclude-address", to_sequence(&excludes));
+    }
+
+    if let Some((key, names)) = interface_filter(&interfaces, settings.interface_filter.as_deref(), &tun_device)
+        && !names.is_empty()
+    {
+        revise!(tun_val, key, to_sequence(&names));
+    }
+
+    revise!(config, "tun", tun_val);
+    config
+}
+
+fn push_unique(list: &mut Vec<String>, item: String) {
+    if !list.contains(&item) {
+        list.push(item);
+    }
+}
+
+fn to_sequence(list: &[String]) -> Value {
+    Value::Sequence(list.iter().map(|item| Value::from(item.as_str())).collect())
+}
+
+fn is_virtual_interface(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    VIRTUAL_INTERFACE_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// 生成 include-interface 或 exclude-interface 列表
+/// - `exclude`: 排除容器/虚拟机网桥及其他 VPN 网卡
+/// - `include`: 仅接管物理网卡
+fn interface_filter(
+    interfaces: &[NetworkInterface],
+    mode: Option<&str>,
+    tun_device: &str,
+) -> Option<(&'static str, Vec<String>)> {
+    // include-interface / exclude-interface 仅 Linux 下的 auto-route 支持
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let (key, want_virtual) = match mode? {
+        "exclude" => ("exclude-interface", true),
+        "include" => ("include-interface", false),
+        _ => return None,
+    };
+
+    let mut names = Vec::new();
+    for iface in interfaces {
+        if iface.internal || iface.name == tun_device || is_virtual_interface(&iface.name) != want_virtual {
+            continue;
+        }
+        push_unique(&mut names, iface.name.as_str().into());
+    }
+    Some((key, names))
+}
+
+/// 提取网卡所在网段，忽略回环、链路本地地址、mihomo 自身的 TUN 网卡以及与 fake-ip 段重叠的网段
+fn interface_subnets(
+    interfaces: &[NetworkInterface],
+    tun_device: &str,
+    fake_ip_range: Option<(IpAddr, u8)>,
+) -> Vec<String> {
+    let mut subnets = Vec::new();
+    for iface in interfaces {
+        if iface.internal || iface.name == tun_device {
+            continue;
+        }
+        for addr in &iface.addr {
+            let Some((network, prefix)) = subnet_of(addr.ip(), addr.netmask()) else {
+                continue;
+            };
+            if fake_ip_range.is_some_and(|(range, len)| overlaps((network, prefix), (range, len))) {
+                continue;
+            }
+            push_unique(&mut subnets, format!("{network}/{prefix}").into());
+        }
+    }
+    subnets
+}
+
+fn subnet_of(ip: IpAddr, netmask: Option<IpAddr>) -> Option<(IpAddr, u8)> {
+    let skip = match ip {
+        IpAddr::V4(v4) => v4.is_loopback() || v4.is_link_local() || v4.is_unspecified() || v4.is_multicast(),
+        IpAddr::V6(v6) => {
+            v6.is_loopback() || v6.is_unspecified() || v6.is_multicast() || (v6.segments()[0] & 0xffc0) == 0xfe80
+        }
+    };
+    if skip {
+        return None;
+    }
+
+    let prefix = match netmask? {
+        IpAddr::V4(mask) => u32::from(mask).leading_ones() as u8,
+        IpAddr::V6(mask) => u128::from(mask).leading_ones() as u8,
+    };
+    // 过宽的网段排除后会绕过大量流量，视为异常数据
+    if prefix == 0 {
+        return None;
+    }
+    Some((network_of(ip, prefix), prefix))
+}
+
+fn network_of(ip: IpAddr, prefix: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+        }
+        IpAddr::V6(v6) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+        }
+    }
+}
+
+/// 解析 `ip/prefix`，不带前缀时视为单个地址
+fn parse_cidr(raw: &str) -> Option<(IpAddr, u8)> {
+    let raw = raw.trim();
+    let (ip, prefix) = match raw.split_once('/') {
+        Some((ip, prefix)) => (ip.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+        None => (raw.parse::<IpAddr>().ok()?, None),
+    };
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    if prefix > max {
+        return None;
+    }
+    Some((network_of(ip, prefix), prefix))
+}
+
+fn overlaps(a: (IpAddr, u8), b: (IpAddr, u8)) -> bool {
+    if a.0.is_ipv4() != b.0.is_ipv4() {
+        return false;
+    }
+    let prefix = a.1.min(b.1);
+    network_of(a.0, prefix) == network_of(b.0, prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v4(name: &str, ip: [u8; 4], mask: [u8; 4]) -> NetworkInterface {
+        NetworkInterface::new_afinet(name, ip.into(), Some(mask.into()), None, 0, false)
+    }
+
+    #[test]
+    fn parse_and_normalize_cidr() {
+        assert_eq!(
+            parse_cidr("192.168.1.20/24"),
+            Some((IpAddr::V4(Ipv4Addr::new(192, 168, 1, 0)), 24))
+        );
+        assert_eq!(parse_cidr("10.0.0.1").map(|(_, prefix)| prefix), Some(32));
+        assert_eq!(
+            parse_cidr("fd00::1/64").map(|(ip, _)| ip.to_string()),
+            Some("fd00::".into())
+        );
+        assert!(parse_cidr("10.0.0.0/33").is_none());
+        assert!(parse_cidr("not-an-ip").is_none());
+    }
+
+    #[test]
+    fn subnets_skip_tun_device_and_fake_ip_range() {
+        let interfaces = vec![
+            v4("eth0", [192, 168, 1, 20], [255, 255, 255, 0]),
+            v4("docker0", [172, 17, 0, 1], [255, 255, 0, 0]),
+            v4("Mihomo", [198, 18, 0, 1], [255, 255, 255, 252]),
+            v4("tailscale0", [100, 100, 1, 2], [255, 255, 255, 255]),
+            v4("eth1", [169, 254, 3, 4], [255, 255, 0, 0]),
+            NetworkInterface::new_afinet("lo", Ipv4Addr::LOCALHOST, Some([255, 0, 0, 0].into()), None, 1, true),
+        ];
+
+        assert_eq!(
+            interface_subnets(&interfaces, "Mihomo", None),
+            vec!["192.168.1.0/24", "172.17.0.0/16", "100.100.1.2/32"]
+        );
+        assert_eq!(
+            interface_subnets(&interfaces, "utun1024", parse_cidr("198.18.0.1/16")),
+            vec!["192.168.1.0/24", "172.17.0.0/16", "100.100.1.2/32"]
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn interface_filter_modes() {
+        let interfaces = vec![
+            v4("eth0", [192, 168, 1, 20], [255, 255, 255, 0]),
+            v4("docker0", [172, 17, 0, 1], [255, 255, 0, 0]),
+            v4("wg0", [10, 8, 0, 2], [255, 255, 255, 0]),
+            v4("Mihomo", [198, 18, 0, 1], [255, 255, 255, 252]),
+        ];
+
+        assert_eq!(
+            interface_filter(&interfaces, Some("exclude"), "Mihomo"),
+            Some(("exclude-interface", vec!["docker0".into(), "wg0".into()]))
+        );
+        assert_eq!(
+            interface_filter(&interfaces, Some("include"), "Mihomo"),
+            Some(("include-interface", vec!["eth0".into()]))
+        );
+        assert_eq!(interface_filter(&interfaces, None, "Mihomo"), None);
+    }
+}
@@ -1,19 +1,32 @@
+use super::BackupSource;
 use crate::{
     config::{Config, IClashTemp, IProfiles, IVerge},
-    core::backup,
+    core::{
+        backup, backup_crypto,
+        backup_directory::DirectoryStorage,
+        backup_manifest::ManifestCheck,
+        backup_s3::S3Client,
+        backup_storage::{BackupStorage, RemoteBackupFile},
+        backup_store::BackupStore,
+        handle,
+        updater::SilentUpdater,
+    },
+    module::{
+        auto_backup::{AutoBackupManager, AutoBackupTrigger},
+        backup_retention::{local_policy, prune_local_backups, prune_remote_backups, prune_store_backups},
+    },
     process::AsyncHandler,
     utils::{
-        dirs::{PathBufExec as _, app_home_dir, local_backup_dir, verge_path},
+        dirs::{PathBufExec as _, local_backup_dir, verge_path},
         help,
     },
 };
 use anyhow::{Result, anyhow};
 use chrono::Utc;
 use clash_verge_logging::{Type, logging};
-use reqwest_dav::list_cmd::ListFile;
 use serde::Serialize;
 use smartstring::alias::String;
-use std::path::PathBuf;
+use std::{env::temp_dir, path::PathBuf};
 use tokio::fs;
 
 #[derive(Debug, Serialize)]
@@ -24,19 +37,68 @@ pub struct LocalBackupFile {
     pub content_length: u64,
 }
 
-/// Load restored verge.yaml from disk, merge back WebDAV creds, save, and sync memory.
-/// Also reload other restored configs so restarts won't overwrite them.
-async fn finalize_restored_verge_config(
+/// Backups removed (or to be removed in a dry run) by the retention policy
+#[derive(Debug, Default, Serialize)]
+pub struct BackupPruneReport {
+    pub local: Vec<String>,
+    pub webdav: Vec<String>,
+    pub s3: Vec<String>,
+    pub directory: Vec<String>,
+    pub incremental: Vec<String>,
+}
+
+/// 不写入备份的本机设置，恢复时保留当前的值
+#[derive(Debug, Clone)]
+pub(super) struct LocalOnlySettings {
     webdav_url: Option<String>,
     webdav_username: Option<String>,
     webdav_password: Option<String>,
-) -> Result<()> {
+    backup_passphrase: Option<String>,
+    s3_endpoint: Option<String>,
+    s3_bucket: Option<String>,
+    s3_prefix: Option<String>,
+    s3_region: Option<String>,
+    s3_access_key_id: Option<String>,
+    s3_secret_access_key: Option<String>,
+    backup_directory: Option<String>,
+}
+
+impl LocalOnlySettings {
+    pub(super) async fn current() -> Self {
+        let verge = Config::verge().await.latest_arc();
+        Self {
+            webdav_url: verge.webdav_url.clone(),
+            webdav_username: verge.webdav_username.clone(),
+            webdav_password: verge.webdav_password.clone(),
+            backup_passphrase: verge.backup_passphrase.clone(),
+            s3_endpoint: verge.s3_endpoint.clone(),
+            s3_bucket: verge.s3_bucket.clone(),
+            s3_prefix: verge.s3_prefix.clone(),
+            s3_region: verge.s3_region.clone(),
+            s3_access_key_id: verge.s3_access_key_id.clone(),
+            s3_secret_access_key: verge.s3_secret_access_key.clone(),
+            backup_directory: verge.backup_directory.clone(),
+        }
+    }
+}
+
+/// Load restored verge.yaml from disk, merge back WebDAV and S3 creds, save, and sync memory.
+/// Also reload other restored configs so restarts won't overwrite them.
+pub(super) async fn finalize_restored_verge_config(local: LocalOnlySettings) -> Result<()> {
     // Do NOT silently fallback to defaults; a broken/missing verge.yaml means restore failed.
     // Propagate the error so the UI/user can react accordingly.
     let mut restored = help::read_yaml::<IVerge>(&verge_path()?).await?;
-    restored.webdav_url = webdav_url;
-    restored.webdav_username = webdav_username;
-    restored.webdav_password = webdav_password;
+    restored.webdav_url = local.webdav_url;
+    restored.webdav_username = local.webdav_username;
+    restored.webdav_password = local.webdav_password;
+    restored.backup_passphrase = local.backup_passphrase;
+    restored.s3_endpoint = local.s3_endpoint;
+    restored.s3_bucket = local.s3_bucket;
+    restored.s3_prefix = local.s3_prefix;
+    restored.s3_region = local.s3_region;
+    restored.s3_access_key_id = local.s3_access_key_id;
+    restored.s3_secret_access_key = local.s3_secret_access_key;
+    restored.backup_directory = local.backup_directory;
     restored.save_file().await?;
 
     let restored_clash = IClashTemp::new().await;
@@ -67,20 +129,39 @@ async fn finalize_restored_verge_config(
     Ok(())
 }
 
+/// 记录备份校验时的警告并通知前端，这些问题不会阻止恢复
+pub(super) fn report_backup_warnings(check: &ManifestCheck) {
+    for warning in &check.warnings {
+        logging!(warn, Type::Backup, "Warning: {}", warning);
+        handle::Handle::notice_message("backup::restore_warning", warning.clone());
+    }
+}
+
 /// Create a backup and upload to WebDAV
 pub async fn create_backup_and_upload_webdav() -> Result<()> {
+    create_backup_and_upload(backup::WebDavClient::global()).await
+}
+
+/// Create a backup and upload to S3
+pub async fn create_backup_and_upload_s3() -> Result<()> {
+    create_backup_and_upload(S3Client::global()).await
+}
+
+/// Create a backup and write it to the backup directory
+pub async fn create_backup_and_upload_directory() -> Result<()> {
+    create_backup_and_upload(DirectoryStorage::global()).await
+}
+
+async fn create_backup_and_upload(storage: &dyn BackupStorage) -> Result<()> {
     let (file_name, temp_file_path) = backup::create_backup().await.map_err(|err| {
         logging!(error, Type::Backup, "Failed to create backup: {err:#?}");
         err
     })?;
 
-    if let Err(err) = backup::WebDavClient::global()
-        .upload(temp_file_path.clone(), file_name)
-        .await
-    {
-        logging!(error, Type::Backup, "Failed to upload to WebDAV: {err:#?}");
+    if let Err(err) = storage.upload(temp_file_path.clone(), file_name).await {
+        logging!(error, Type::Backup, "Failed to upload to {}: {err:#?}", storage.name());
         // 上传失败时重置客户端缓存
-        backup::WebDavClient::global().reset();
+        storage.reset();
         return Err(err);
     }
 
@@ -88,53 +169,140 @@ pub async fn create_backup_and_upload_webdav() -> Result<()> {
         logging!(warn, Type::Backup, "Failed to remove temp file: {err:#?}");
     }
 
+    prune_remote_after_upload(storage).await;
+    Ok(())
+}
+
+/// Upload an existing local backup to WebDAV
+pub async fn upload_local_backup_webdav(filename: &str) -> Result<()> {
+    let storage = backup::WebDavClient::global();
+    let file_path = local_backup_dir()?.join(filename);
+    if let Err(err) = storage.upload(file_path, filename.into()).await {
+        logging!(error, Type::Backup, "Failed to upload to WebDAV: {err:#?}");
+        storage.reset();
+        return Err(err);
+    }
+
+    prune_remote_after_upload(storage).await;
     Ok(())
 }
 
+/// 上传成功后按保留策略清理远程备份，失败只记录日志
+async fn prune_remote_after_upload(storage: &dyn BackupStorage) {
+    let policy = Config::verge().await.latest_arc().backup_retention;
+    if let Some(policy) = policy
+        && let Err(err) = prune_remote_backups(storage, &policy, false).await
+    {
+        logging!(
+            warn,
+            Type::Backup,
+            "Failed to prune {} backups: {err:#?}",
+            storage.name()
+        );
+    }
+}
+
+/// Apply the retention policy to local auto backups and the configured remote backups.
+/// With `dry_run` nothing is deleted, the report lists what would be pruned.
+pub async fn prune_backups(dry_run: bool) -> Result<BackupPruneReport> {
+    let (policy, webdav_configured, s3_configured, directory_configured) = {
+        let verge = Config::verge().await.latest_arc();
+        let webdav = verge.webdav_url.is_some() && verge.webdav_username.is_some() && verge.webdav_password.is_some();
+        let s3 = verge.s3_endpoint.is_some()
+            && verge.s3_bucket.is_some()
+            && verge.s3_access_key_id.is_some()
+            && verge.s3_secret_access_key.is_some();
+        let directory = verge.backup_directory.as_ref().is_some_and(|dir| !dir.is_empty());
+        (verge.backup_retention, webdav, s3, directory)
+    };
+
+    let auto_policy = local_policy(policy);
+    let mut report = BackupPruneReport {
+        local: prune_local_backups(&auto_policy, dry_run).await?,
+        incremental: prune_store_backups(&auto_policy, dry_run).await?,
+        ..Default::default()
+    };
+    if let Some(policy) = policy {
+        if webdav_configured {
+            report.webdav = prune_remote_backups(backup::WebDavClient::global(), &policy, dry_run).await?;
+        }
+        if s3_configured {
+            report.s3 = prune_remote_backups(S3Client::global(), &policy, dry_run).await?;
+        }
+        if directory_configured {
+            report.directory = prune_remote_backups(DirectoryStorage::global(), &policy, dry_run).await?;
+        }
+    }
+    Ok(report)
+}
+
 /// List WebDAV backups
-pub async fn list_wevdav_backup() -> Result<Vec<ListFile>> {
-    backup::WebDavClient::global().list().await.map_err(|err| {
-        logging!(error, Type::Backup, "Failed to list WebDAV backup files: {err:#?}");
+pub async fn list_wevdav_backup() -> Result<Vec<RemoteBackupFile>> {
+    list_remote_backup(backup::WebDavClient::global()).await
+}
+
+/// List S3 backups
+pub async fn list_s3_backup() -> Result<Vec<RemoteBackupFile>> {
+    list_remote_backup(S3Client::global()).await
+}
+
+/// List backups in the backup directory
+pub async fn list_directory_backup() -> Result<Vec<RemoteBackupFile>> {
+    list_remote_backup(DirectoryStorage::global()).await
+}
+
+async fn list_remote_backup(storage: &dyn BackupStorage) -> Result<Vec<RemoteBackupFile>> {
+    storage.list().await.map_err(|err| {
+        logging!(
+            error,
+            Type::Backup,
+            "Failed to list {} backup files: {err:#?}",
+            storage.name()
+        );
         err
     })
 }
 
 /// Delete WebDAV backup
 pub async fn delete_webdav_backup(filename: String) -> Result<()> {
-    backup::WebDavClient::global().delete(filename).await.map_err(|err| {
-        logging!(error, Type::Backup, "Failed to delete WebDAV backup file: {err:#?}");
+    delete_remote_backup(backup::WebDavClient::global(), filename).await
+}
+
+/// Delete S3 backup
+pub async fn delete_s3_backup(filename: String) -> Result<()> {
+    delete_remote_backup(S3Client::global(), filename).await
+}
+
+/// Delete backup from the backup directory
+pub async fn delete_directory_backup(filename: String) -> Result<()> {
+    delete_remote_backup(DirectoryStorage::global(), filename).await
+}
+
+async fn delete_remote_backup(storage: &dyn BackupStorage, filename: String) -> Result<()> {
+    storage.delete(filename).await.map_err(|err| {
+        logging!(
+            error,
+            Type::Backup,
+            "Failed to delete {} backup file: {err:#?}",
+            storage.name()
+        );
         err
     })
 }
 
 /// Restore WebDAV backup
-pub async fn restore_webdav_backup(filename: String) -> Result<()> {
-    let verge = Config::verge().await;
-    let verge_data = verge.latest_arc();
-    let webdav_url = verge_data.webdav_url.clone();
-    let webdav_username = verge_data.webdav_username.clone();
-    let webdav_password = verge_data.webdav_password.clone();
-
-    let backup_storage_path = app_home_dir()
-        .map_err(|e| anyhow::anyhow!("Failed to get app home dir: {e}"))?
-        .join(filename.as_str());
-    backup::WebDavClient::global()
-        .download(filename, backup_storage_path.clone())
-        .await
-        .map_err(|err| {
-            logging!(error, Type::Backup, "Failed to download WebDAV backup file: {err:#?}");
-            err
-        })?;
-
-    // extract zip file
-    let value = backup_storage_path.clone();
-    let file = AsyncHandler::spawn_blocking(move || std::fs::File::open(&value)).await??;
-    let mut zip = zip::ZipArchive::new(file)?;
-    zip.extract(app_home_dir()?)?;
-    let res = finalize_restored_verge_config(webdav_url, webdav_username, webdav_password).await;
-    // Finally remove the temp file (attempt cleanup even if finalize fails)
-    let _ = backup_storage_path.remove_if_exists().await;
-    res
+pub async fn restore_webdav_backup(filename: String, passphrase: Option<String>) -> Result<()> {
+    super::restore_backup(BackupSource::Webdav, filename, passphrase).await
+}
+
+/// Restore S3 backup
+pub async fn restore_s3_backup(filename: String, passphrase: Option<String>) -> Result<()> {
+    super::restore_backup(BackupSource::S3, filename, passphrase).await
+}
+
+/// Restore backup from the backup directory
+pub async fn restore_directory_backup(filename: String, passphrase: Option<String>) -> Result<()> {
+    super::restore_backup(BackupSource::Directory, filename, passphrase).await
 }
 
 /// Create a backup and save to local storage
@@ -173,6 +341,129 @@ where
     Ok(final_name)
 }
 
+/// Install the update cached by the silent updater, backing up the current configuration first
+/// so it can still be restored if the new version fails to migrate it
+pub async fn install_cached_update(app_handle: &tauri::AppHandle) -> bool {
+    SilentUpdater::global()
+        .try_install_on_startup(app_handle, AutoBackupManager::run_backup(AutoBackupTrigger::AppUpdate))
+        .await
+}
+
+/// Save the current configuration into the incremental backup store
+pub async fn create_incremental_backup_with_namer<F>(namer: F) -> Result<String>
+where
+    F: FnOnce(&str) -> String,
+{
+    let entries = backup::collect_backup_entries().await.map_err(|err| {
+        logging!(error, Type::Backup, "Failed to create incremental backup: {err:#?}");
+        err
+    })?;
+    let name = namer(&backup::backup_file_stem());
+    let passphrase = Config::verge().await.latest_arc().backup_passphrase.clone();
+    let written = BackupStore::global()
+        .save(&name, &entries, passphrase.as_deref())
+        .await?;
+    logging!(
+        info,
+        Type::Backup,
+        "Incremental backup {} saved, {} new bytes stored",
+        name,
+        written
+    );
+    Ok(name)
+}
+
+/// List incremental backups, `content_length` is the size of the standalone archive contents
+pub async fn list_incremental_backup() -> Result<Vec<LocalBackupFile>> {
+    let snapshots = BackupStore::global().list().await?;
+    Ok(snapshots
+        .into_iter()
+        .map(|snapshot| LocalBackupFile {
+            filename: snapshot.name,
+            path: snapshot.path.to_string_lossy().into(),
+            last_modified: chrono::DateTime::from_timestamp(snapshot.created_at, 0)
+                .unwrap_or_default()
+                .to_rfc3339()
+                .into(),
+            content_length: snapshot.size,
+        })
+        .collect())
+}
+
+/// Delete an incremental backup and the files only it referred to
+pub async fn delete_incremental_backup(filename: String) -> Result<()> {
+    let report = BackupStore::global().delete(&[filename]).await?;
+    logging!(
+        info,
+        Type::Backup,
+        "Removed {} unreferenced backup files ({} bytes)",
+        report.objects,
+        report.bytes
+    );
+    Ok(())
+}
+
+/// Restore incremental backup
+pub async fn restore_incremental_backup(filename: String, passphrase: Option<String>) -> Result<()> {
+    super::restore_backup(BackupSource::Incremental, filename, passphrase).await
+}
+
+/// Export an incremental backup as a standalone backup file
+pub async fn export_incremental_backup(filename: String, destination: String) -> Result<()> {
+    let (_, content) = export_incremental_archive(&filename).await?;
+    let dest_path = PathBuf::from(destination.as_str());
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(&dest_path, content)
+        .await
+        .map_err(|err| anyhow!("Failed to export backup file: {err:#?}"))?;
+    Ok(())
+}
+
+/// Upload an incremental backup to WebDAV as a standalone backup file
+pub async fn upload_incremental_backup_webdav(filename: &str) -> Result<()> {
+    let (file_name, content) = export_incremental_archive(filename).await?;
+    let temp_file_path = temp_dir().join(file_name.as_str());
+    fs::write(&temp_file_path, content).await?;
+
+    let storage = backup::WebDavClient::global();
+    let res = storage.upload(temp_file_path.clone(), file_name).await;
+    if let Err(err) = temp_file_path.remove_if_exists().await {
+        logging!(warn, Type::Backup, "Failed to remove temp file: {err:#?}");
+    }
+    if let Err(err) = res {
+        logging!(error, Type::Backup, "Failed to upload to WebDAV: {err:#?}");
+        storage.reset();
+        return Err(err);
+    }
+
+    prune_remote_after_upload(storage).await;
+    Ok(())
+}
+
+/// 重建独立的备份压缩包，设置了口令时与普通备份一样加密
+async fn export_incremental_archive(filename: &str) -> Result<(String, Vec<u8>)> {
+    let content = BackupStore::global().export(filename).await?;
+    let passphrase = Config::verge()
+        .await
+        .latest_arc()
+        .backup_passphrase
+        .clone()
+        .filter(|passphrase| !passphrase.is_empty());
+    match passphrase {
+        Some(passphrase) => {
+            let content =
+                AsyncHandler::spawn_blocking(move || backup_crypto::encrypt_backup(&content, &passphrase)).await??;
+            Ok((
+                format!("{filename}.{}", backup_crypto::ENCRYPTED_BACKUP_EXT).into(),
+                content,
+            ))
+        }
+        None => Ok((format!("{filename}.zip").into(), content)),
+    }
+}
+
 /// Import an existing backup file into the local backup directory
 pub async fn import_local_backup(source: String) -> Result<String> {
     let source_path = PathBuf::from(source.as_str());
@@ -183,20 +474,19 @@ pub async fn import_local_backup(source: String) -> Result<String> {
         return Err(anyhow!("Backup path is not a file: {source}"));
     }
 
-    let ext = source_path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext.to_ascii_lowercase())
-        .unwrap_or_default();
-    if ext != "zip" {
-        return Err(anyhow!("Only .zip backup files are supported"));
-    }
-
     let file_name = source_path
         .file_name()
         .and_then(|name| name.to_str())
         .ok_or_else(|| anyhow!("Invalid backup file name"))?;
 
+    let lower_name = file_name.to_ascii_lowercase();
+    if !lower_name.ends_with(".zip") && !lower_name.ends_with(backup_crypto::ENCRYPTED_BACKUP_EXT) {
+        return Err(anyhow!(
+            "Only .zip or .{} backup files are supported",
+            backup_crypto::ENCRYPTED_BACKUP_EXT
+        ));
+    }
+
     let backup_dir = local_backup_dir()?;
     let target_path = backup_dir.join(file_name);
 
@@ -294,28 +584,8 @@ pub async fn delete_local_backup(filename: String) -> Result<()> {
 }
 
 /// Restore local backup
-pub async fn restore_local_backup(filename: String) -> Result<()> {
-    let backup_dir = local_backup_dir()?;
-    let target_path = backup_dir.join(filename.as_str());
-    if !target_path.exists() {
-        return Err(anyhow!("Backup file not found: {}", filename));
-    }
-
-    let (webdav_url, webdav_username, webdav_password) = {
-        let verge = Config::verge().await;
-        let verge = verge.latest_arc();
-        (
-            verge.webdav_url.clone(),
-            verge.webdav_username.clone(),
-            verge.webdav_password.clone(),
-        )
-    };
-
-    let file = AsyncHandler::spawn_blocking(move || std::fs::File::open(&target_path)).await??;
-    let mut zip = zip::ZipArchive::new(file)?;
-    zip.extract(app_home_dir()?)?;
-    finalize_restored_verge_config(webdav_url, webdav_username, webdav_password).await?;
-    Ok(())
+pub async fn restore_local_backup(filename: String, passphrase: Option<String>) -> Result<()> {
+    super::restore_backup(BackupSource::Local, filename, passphrase).await
 }
 
 /// Export local backup file to user selected destination
@@ -0,0 +1,316 @@
+use super::{
+    BackupSource,
+    restore::{archived_profiles, load_backup_archive, profile_entry, read_entry},
+};
+use crate::{
+    config::{Config, IProfiles, PrfItem},
+    core::{
+        backup::{BackupArchive, LOCAL_ONLY_VERGE_KEYS},
+        backup_manifest::ManifestCheck,
+    },
+    utils::{dirs, help::mask_url},
+};
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use smartstring::alias::String;
+use std::collections::BTreeSet;
+use tokio::fs;
+
+/// 预览时隐藏的 clash 配置项
+const MASKED_CLASH_KEYS: [&str; 1] = ["secret"];
+const MASKED_VALUE: &str = "******";
+
+/// 备份中的订阅或扩展项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackupProfilePreview {
+    pub uid: String,
+    pub name: Option<String>,
+    pub itype: Option<String>,
+    /// 已隐藏链接中的凭据及 token
+    pub url: Option<String>,
+    pub current: bool,
+}
+
+/// 一个配置项在备份与当前配置中的值，`None` 表示未设置
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueChange {
+    /// 以 `.` 连接的键路径
+    pub key: String,
+    pub backup: Option<Value>,
+    pub current: Option<Value>,
+}
+
+/// 两边都存在但内容不同的订阅，`fields` 为不同的部分 (name/type/url/content)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProfileChange {
+    pub uid: String,
+    pub name: Option<String>,
+    pub fields: Vec<&'static str>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ProfilesDiff {
+    /// 仅存在于备份中
+    pub added: Vec<BackupProfilePreview>,
+    /// 仅存在于当前配置中
+    pub removed: Vec<BackupProfilePreview>,
+    pub changed: Vec<ProfileChange>,
+    /// 当前使用的订阅不同
+    pub current: Option<ValueChange>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct BackupDiff {
+    pub profiles: ProfilesDiff,
+    pub verge: Vec<ValueChange>,
+    pub clash: Vec<ValueChange>,
+}
+
+/// What a backup contains and how it differs from the live configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupPreview {
+    pub filename: String,
+    pub check: ManifestCheck,
+    pub profiles: Vec<BackupProfilePreview>,
+    /// 不含本机专属的设置 (WebDAV/S3 凭据等)
+    pub verge: Option<Value>,
+    pub clash: Option<Value>,
+    pub diff: BackupDiff,
+}
+
+/// Open a backup without restoring it and compare it with the live configuration
+pub async fn preview_backup(
+    source: BackupSource,
+    filename: String,
+    passphrase: Option<String>,
+) -> Result<BackupPreview> {
+    let (mut zip, check) = load_backup_archive(source, &filename, passphrase).await?;
+
+    let archived = archived_profiles(&mut zip)?;
+    let live = Config::profiles().await.latest_arc();
+    let profiles = profile_previews(&archived);
+    let profiles_diff = diff_profiles(&mut zip, &archived, &live).await?;
+
+    let verge = archived_yaml(&mut zip, dirs::VERGE_CONFIG)?.map(strip_local_settings);
+    let live_verge = strip_local_settings(serde_json::to_value(&*Config::verge().await.latest_arc())?);
+
+    let clash = archived_yaml(&mut zip, dirs::CLASH_CONFIG)?.map(mask_clash_secrets);
+    let live_clash = mask_clash_secrets(serde_json::to_value(&Config::clash().await.latest_arc().0)?);
+
+    // 备份中没有的文件不会被恢复，也就没有差异
+    let diff = BackupDiff {
+        profiles: profiles_diff,
+        verge: verge
+            .as_ref()
+            .map(|verge| diff_values(verge, &live_verge))
+            .unwrap_or_default(),
+        clash: clash
+            .as_ref()
+            .map(|clash| diff_values(clash, &live_clash))
+            .unwrap_or_default(),
+    };
+    Ok(BackupPreview {
+        filename,
+        check,
+        profiles,
+        verge,
+        clash,
+        diff,
+    })
+}
+
+fn profile_previews(profiles: &IProfiles) -> Vec<BackupProfilePreview> {
+    profiles
+        .items
+        .iter()
+        .flatten()
+        .filter_map(|item| profile_preview(profiles, item))
+        .collect()
+}
+
+fn profile_preview(profiles: &IProfiles, item: &PrfItem) -> Option<BackupProfilePreview> {
+    let uid = item.uid.clone()?;
+    Some(BackupProfilePreview {
+        current: profiles.is_current_profile_index(&uid),
+        uid,
+        name: item.name.clone(),
+        itype: item.itype.clone(),
+        url: item.url.as_deref().map(|url| mask_url(url).into()),
+    })
+}
+
+async fn diff_profiles(zip: &mut BackupArchive, archived: &IProfiles, live: &IProfiles) -> Result<ProfilesDiff> {
+    let mut diff = ProfilesDiff::default();
+    let profiles_dir = dirs::app_profiles_dir()?;
+
+    for item in archived.items.iter().flatten() {
+        let Some(preview) = profile_preview(archived, item) else {
+            continue;
+        };
+        let Ok(current) = live.get_item(&preview.uid) else {
+            diff.added.push(preview);
+            continue;
+        };
+
+        let mut fields = Vec::new();
+        if item.name != current.name {
+            fields.push("name");
+        }
+        if item.itype != current.itype {
+            fields.push("type");
+        }
+        if item.url != current.url {
+            fields.push("url");
+        }
+        let archived_content = item
+            .file
+            .as_ref()
+            .and_then(|file| read_entry(zip, &profile_entry(file)).ok());
+        let current_content = match current.file.as_ref() {
+            Some(file) => fs::read(profiles_dir.join(file.as_str())).await.ok(),
+            None => None,
+        };
+        if archived_content != current_content {
+            fields.push("content");
+        }
+        if !fields.is_empty() {
+            diff.changed.push(ProfileChange {
+                uid: preview.uid,
+                name: preview.name,
+                fields,
+            });
+        }
+    }
+
+    for item in live.items.iter().flatten() {
+        if let Some(preview) = profile_preview(live, item)
+            && archived.get_item(&preview.uid).is_err()
+        {
+            diff.removed.push(preview);
+        }
+    }
+
+    if archived.current != live.current {
+        diff.current = Some(ValueChange {
+            key: "current".into(),
+            backup: archived.current.as_deref().map(Value::from),
+            current: live.current.as_deref().map(Value::from),
+        });
+    }
+    Ok(diff)
+}
+
+/// 读取备份中的 yaml 文件，文件不存在时返回 `None`
+fn archived_yaml(zip: &mut BackupArchive, name: &str) -> Result<Option<Value>> {
+    if zip.index_for_name(name).is_none() {
+        return Ok(None);
+    }
+    let content = read_entry(zip, name)?;
+    let value = serde_yaml_ng::from_slice(&content).with_context(|| format!("failed to parse {name} in the backup"))?;
+    Ok(Some(value))
+}
+
+/// 恢复时会保留本机的这些设置，预览中不显示也不比较
+fn strip_local_settings(mut verge: Value) -> Value {
+    if let Some(obj) = verge.as_object_mut() {
+        for key in LOCAL_ONLY_VERGE_KEYS {
+            obj.remove(key);
+        }
+    }
+    verge
+}
+
+fn mask_clash_secrets(mut clash: Value) -> Value {
+    if let Some(obj) = clash.as_object_mut() {
+        for key in MASKED_CLASH_KEYS {
+            if let Some(value) = obj.get_mut(key)
+                && value.as_str().is_some_and(|secret| !secret.is_empty())
+            {
+                *value = Value::from(MASKED_VALUE);
+            }
+        }
+    }
+    clash
+}
+
+/// Compare two documents key by key. Nested objects are walked,
+/// arrays and scalars are compared as a whole, `null` counts as unset.
+fn diff_values(backup: &Value, current: &Value) -> Vec<ValueChange> {
+    let mut changes = Vec::new();
+    collect_changes("", Some(backup), Some(current), &mut changes);
+    changes
+}
+
+fn collect_changes(key: &str, backup: Option<&Value>, current: Option<&Value>, changes: &mut Vec<ValueChange>) {
+    let backup = backup.filter(|value| !value.is_null());
+    let current = current.filter(|value| !value.is_null());
+
+    let empty = Map::new();
+    let backup_obj = backup.map_or(Some(&empty), Value::as_object);
+    let current_obj = current.map_or(Some(&empty), Value::as_object);
+    if let (Some(backup_obj), Some(current_obj)) = (backup_obj, current_obj) {
+        let keys: BTreeSet<&std::string::String> = backup_obj.keys().chain(current_obj.keys()).collect();
+        for child in keys {
+            let path = if key.is_empty() {
+                child.clone()
+            } else {
+                format!("{key}.{child}")
+            };
+            collect_changes(&path, backup_obj.get(child), current_obj.get(child), changes);
+        }
+        return;
+    }
+
+    if backup != current {
+        changes.push(ValueChange {
+            key: key.into(),
+            backup: backup.cloned(),
+            current: current.cloned(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_nested_settings() {
+        let backup = json!({
+            "mixed-port": 7890,
+            "dns": { "enable": true, "nameserver": ["1.1.1.1"] },
+            "tun": { "enable": false },
+            "ipv6": null,
+            "secret": "backup-secret",
+        });
+        let current = json!({
+            "mixed-port": 7897,
+            "dns": { "enable": true, "nameserver": ["8.8.8.8", "1.1.1.1"] },
+            "mode": "rule",
+            "secret": "",
+        });
+
+        let changes = diff_values(&mask_clash_secrets(backup), &mask_clash_secrets(current));
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| (change.key.as_str(), change.backup.clone(), change.current.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "dns.nameserver",
+                    Some(json!(["1.1.1.1"])),
+                    Some(json!(["8.8.8.8", "1.1.1.1"]))
+                ),
+                ("mixed-port", Some(json!(7890)), Some(json!(7897))),
+                ("mode", None, Some(json!("rule"))),
+                ("secret", Some(json!(MASKED_VALUE)), Some(json!(""))),
+                ("tun.enable", Some(json!(false)), None),
+            ]
+        );
+        assert!(diff_values(&json!({ "a": { "b": 1 } }), &json!({ "a": { "b": 1, "c": null } })).is_empty());
+    }
+}
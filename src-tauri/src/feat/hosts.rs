@@ -0,0 +1,266 @@
+use crate::{
+    config::{Config, IVerge, IVergeHostsSource},
+    constants::files::DNS_CONFIG,
+    core::{CoreManager, Timer, handle},
+    utils::{
+        dirs, help,
+        hosts::{self, HostsImportMode, HostsImportSummary, HostsOrigin, ParsedHosts},
+        network::{NetworkManager, ProxyType},
+    },
+};
+use anyhow::{Context as _, Result, anyhow, bail};
+use clash_verge_logging::{Type, logging};
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use tokio::{fs, sync::Mutex};
+
+const FETCH_TIMEOUT_SECS: u64 = 30;
+
+// dns_config.yaml 的读改写需要串行，避免定时刷新与手动导入互相覆盖
+static HOSTS_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Import a local hosts-format file (e.g. `/etc/hosts`) into `dns_config.yaml`
+pub async fn import_hosts_file(path: String, mode: HostsImportMode) -> Result<HostsImportSummary> {
+    let content = fs::read_to_string(path.as_str())
+        .await
+        .with_context(|| format!("failed to read hosts file \"{path}\""))?;
+
+    let summary = apply_hosts(hosts::parse_hosts(&content), mode, None).await?;
+    logging!(info, Type::Config, "Imported hosts file {}: {:?}", path, summary);
+    Ok(summary)
+}
+
+/// Import a hosts list from url, and register it for timer refresh when `update_interval` is set
+pub async fn import_hosts_url(
+    url: String,
+    mode: HostsImportMode,
+    update_interval: Option<u64>,
+) -> Result<HostsImportSummary> {
+    let refresh = update_interval.is_some_and(|interval| interval > 0);
+    let mut sources = load_hosts_sources().await;
+    // 重新导入同一个链接时沿用原来的 uid，保留其写入记录
+    let source_uid = refresh.then(|| {
+        sources
+            .iter()
+            .find(|source| source.url.as_ref() == Some(&url))
+            .and_then(|source| source.uid.clone())
+            .unwrap_or_else(|| help::get_uid("h").into())
+    });
+
+    let summary = apply_hosts(fetch_hosts(&url).await?, mode, source_uid.as_deref()).await?;
+    logging!(
+        info,
+        Type::Config,
+        "Imported hosts list {}: {:?}",
+        help::mask_url(&url),
+        summary
+    );
+
+    if let Some(uid) = source_uid {
+        sources.retain(|source| source.url.as_ref() != Some(&url));
+        sources.push(IVergeHostsSource {
+            uid: Some(uid),
+            url: Some(url),
+            mode: Some(mode),
+            update_interval,
+            updated: Some(chrono::Local::now().timestamp() as usize),
+        });
+        save_hosts_sources(sources).await?;
+        Timer::global().refresh().await?;
+    }
+
+    Ok(summary)
+}
+
+/// Remove a registered hosts source, already imported entries are kept
+pub async fn remove_hosts_source(uid: String) -> Result<()> {
+    let mut sources = load_hosts_sources().await;
+    let before = sources.len();
+    sources.retain(|source| source.uid.as_ref() != Some(&uid));
+    if sources.len() == before {
+        bail!("hosts source \"{uid}\" not found");
+    }
+
+    save_hosts_sources(sources).await?;
+    remove_origin(&uid).await?;
+    Timer::global().refresh().await
+}
+
+/// Re-download a registered hosts source, used by the timer
+pub async fn refresh_hosts_source(uid: &str) -> Result<HostsImportSummary> {
+    let mut sources = load_hosts_sources().await;
+    let source = sources
+        .iter_mut()
+        .find(|source| source.uid.as_deref() == Some(uid))
+        .ok_or_else(|| anyhow!("hosts source \"{uid}\" not found"))?;
+    let url = source
+        .url
+        .clone()
+        .ok_or_else(|| anyhow!("hosts source \"{uid}\" has no url"))?;
+
+    let summary = apply_hosts(fetch_hosts(&url).await?, source.mode.unwrap_or_default(), Some(uid)).await?;
+    source.updated = Some(chrono::Local::now().timestamp() as usize);
+    save_hosts_sources(sources).await?;
+
+    logging!(
+        info,
+        Type::Config,
+        "Refreshed hosts list {}: {:?}",
+        help::mask_url(&url),
+        summary
+    );
+    Ok(summary)
+}
+
+async fn fetch_hosts(url: &str) -> Result<ParsedHosts> {
+    let manager = NetworkManager::new();
+    let resp = match manager
+        .get_with_interrupt(url, ProxyType::None, Some(FETCH_TIMEOUT_SECS), None, false)
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => resp,
+        first => {
+            logging!(
+                warn,
+                Type::Network,
+                "Warning: failed to fetch hosts list directly ({}), retry with clash proxy",
+                match &first {
+                    Ok(resp) => resp.status().to_string(),
+                    Err(err) => help::mask_err(&err.to_string()).into(),
+                }
+            );
+            manager
+                .get_with_interrupt(url, ProxyType::Localhost, Some(FETCH_TIMEOUT_SECS), None, false)
+                .await?
+        }
+    };
+
+    let status = resp.status();
+    if !status.is_success() {
+        bail!("failed to fetch hosts list with status {status}");
+    }
+
+    let parsed = hosts::parse_hosts(resp.text_with_charset()?);
+    if parsed.entries.is_empty() {
+        bail!("the hosts list does not contain any valid entry");
+    }
+    Ok(parsed)
+}
+
+/// 合并进 dns_config.yaml 的 hosts 段，启用 DNS 设置时立即应用
+/// `source` 为自动刷新的列表 uid，会先移除该列表此前写入但上游已删除的地址
+async fn apply_hosts(parsed: ParsedHosts, mode: HostsImportMode, source: Option<&str>) -> Result<HostsImportSummary> {
+    let summary = {
+        let _guard = HOSTS_LOCK.lock().await;
+        let dns_path = dirs::app_home_dir()?.join(DNS_CONFIG);
+
+        let mut dns_config = if dns_path.exists() {
+            let yaml = fs::read_to_string(&dns_path).await?;
+            serde_yaml_ng::from_str::<Option<Mapping>>(&yaml)
+                .context("dns_config.yaml is invalid yaml")?
+                .unwrap_or_default()
+        } else {
+            Mapping::new()
+        };
+
+        // 旧格式的 dns_config.yaml 整体即为 dns 段，写入 hosts 前先包一层
+        if !dns_config.is_empty() && !dns_config.contains_key("dns") && !dns_config.contains_key("hosts") {
+            let legacy = std::mem::take(&mut dns_config);
+            dns_config.insert("dns".into(), Value::Mapping(legacy));
+        }
+
+        let mut existing = dns_config.get("hosts").and_then(Value::as_mapping).cloned();
+        let mut kept = HostsOrigin::new();
+        let mut removed = 0;
+        if let Some(uid) = source {
+            let (still_listed, mut stale) = hosts::split_origin(read_origin(uid).await?, &parsed);
+            for other in read_other_origins(uid).await? {
+                hosts::exclude_origin(&mut stale, &other);
+            }
+            if let Some(existing) = existing.as_mut() {
+                removed = hosts::prune_hosts(existing, &stale);
+            }
+            kept = still_listed;
+        }
+
+        let (merged, mut summary, inserted) = hosts::merge_hosts(existing.as_ref(), parsed, mode);
+        summary.removed = removed;
+        dns_config.insert("hosts".into(), Value::Mapping(merged));
+        fs::write(&dns_path, serde_yaml_ng::to_string(&dns_config)?).await?;
+
+        if let Some(uid) = source {
+            for (host, ips) in inserted {
+                let owned = kept.entry(host).or_default();
+                for ip in ips {
+                    if !owned.contains(&ip) {
+                        owned.push(ip);
+                    }
+                }
+            }
+            save_origin(uid, &kept).await?;
+        }
+        summary
+    };
+
+    if Config::verge().await.latest_arc().enable_dns_settings.unwrap_or(false) {
+        CoreManager::global().update_config_checked().await?;
+        handle::Handle::refresh_clash();
+    }
+
+    Ok(summary)
+}
+
+/// 读取列表上次写入的地址，首次导入时为空
+async fn read_origin(uid: &str) -> Result<HostsOrigin> {
+    let path = dirs::app_hosts_sources_dir()?.join(format!("{uid}.yaml"));
+    if !path.exists() {
+        return Ok(HostsOrigin::new());
+    }
+    help::read_yaml(&path).await
+}
+
+async fn read_other_origins(uid: &str) -> Result<Vec<HostsOrigin>> {
+    let mut origins = Vec::new();
+    for source in load_hosts_sources().await {
+        if let Some(other) = source.uid.as_deref()
+            && other != uid
+        {
+            origins.push(read_origin(other).await?);
+        }
+    }
+    Ok(origins)
+}
+
+async fn save_origin(uid: &str, origin: &HostsOrigin) -> Result<()> {
+    let dir = dirs::app_hosts_sources_dir()?;
+    fs::create_dir_all(&dir).await?;
+    help::save_yaml(&dir.join(format!("{uid}.yaml")), origin, None).await
+}
+
+async fn remove_origin(uid: &str) -> Result<()> {
+    let path = dirs::app_hosts_sources_dir()?.join(format!("{uid}.yaml"));
+    if path.exists() {
+        fs::remove_file(path).await?;
+    }
+    Ok(())
+}
+
+async fn load_hosts_sources() -> Vec<IVergeHostsSource> {
+    Config::verge()
+        .await
+        .latest_arc()
+        .hosts_sources
+        .clone()
+        .unwrap_or_default()
+}
+
+async fn save_hosts_sources(sources: Vec<IVergeHostsSource>) -> Result<()> {
+    let patch = IVerge {
+        hosts_sources: Some(sources),
+        ..IVerge::default()
+    };
+    let verge = Config::verge().await;
+    verge.edit_draft(|d| d.patch_config(&patch));
+    verge.apply();
+    verge.data_arc().save_file().await
+}
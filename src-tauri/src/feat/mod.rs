@@ -1,16 +1,24 @@
 mod backup;
+mod backup_preview;
 mod clash;
 mod config;
+mod hosts;
 mod icon;
 mod profile;
 mod proxy;
+mod quota;
+mod restore;
 mod window;
 
 // Re-export all functions from modules
 pub use backup::*;
+pub use backup_preview::*;
 pub use clash::*;
 pub use config::*;
+pub use hosts::*;
 pub use icon::*;
 pub use profile::*;
 pub use proxy::*;
+pub use quota::*;
+pub use restore::*;
 pub use window::*;
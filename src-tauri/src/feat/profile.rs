@@ -1,14 +1,22 @@
 use crate::{
     cmd,
-    config::{Config, PrfItem, PrfOption, profiles::profiles_draft_update_item_safe},
+    config::{
+        Config, PrfExtra, PrfItem, PrfOption, ProfileUpdateReport, ProfileUpdateStatus, RemoteFetch,
+        profile_history::{self, ProfileVersion},
+        profiles::{profiles_draft_update_item_safe, profiles_draft_update_mirror_state_safe},
+    },
     core::{CoreManager, handle, tray, validate::ValidationOutcome},
     utils::help::{mask_err, mask_url},
 };
 use anyhow::{Result, bail};
 use clash_verge_logging::{Type, logging, logging_error};
+use futures::StreamExt as _;
 use smartstring::alias::String;
 use tauri::Emitter as _;
 
+/// 批量更新订阅时默认同时进行的请求数
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
 /// Toggle proxy profile
 pub async fn toggle_proxy_profile(profile_index: String) {
     logging_error!(
@@ -97,15 +105,182 @@ async fn should_update_profile(uid: &String, ignore_auto_update: bool) -> Result
     }
 }
 
+/// 保存更新后的订阅内容，保存成功后才记录到历史版本
+async fn save_updated_profile(uid: &String, item: &mut PrfItem, status: &str) -> Result<()> {
+    let limit = profile_history_limit().await;
+    let content = item.file_data.clone().filter(|_| limit > 0);
+    // 更新前的文件在保存后会被覆盖，需要提前读取
+    let previous = match content {
+        Some(_) => previous_profile_version(uid).await,
+        None => None,
+    };
+
+    profiles_draft_update_item_safe(uid, item).await?;
+
+    if let Some(content) = content {
+        record_profile_history(uid, &content, item.extra, status, previous, limit).await;
+    }
+    Ok(())
+}
+
+/// 保存本次请求的结果，服务端返回 304 时只刷新更新时间及流量信息，返回订阅内容是否发生变化
+async fn save_fetched_profile(
+    uid: &String,
+    fetched: RemoteFetch,
+    previous: Option<&PrfItem>,
+    status: &str,
+) -> Result<bool> {
+    match (fetched, previous) {
+        (RemoteFetch::Fetched(mut item), _) => {
+            save_updated_profile(uid, &mut item, status).await?;
+            Ok(true)
+        }
+        (RemoteFetch::NotModified(extra), Some(previous)) => {
+            logging!(info, Type::Config, "[订阅更新] 订阅内容未变化 (304)，跳过下载");
+            let mut item = previous.clone();
+            item.file_data = None;
+            // 304 响应中的流量及到期信息同样是最新的
+            if extra.is_some() {
+                item.extra = extra;
+            }
+            item.updated = Some(chrono::Local::now().timestamp() as usize);
+            item.update_failures = None;
+            item.update_error = None;
+            profiles_draft_update_item_safe(uid, &mut item).await?;
+            Ok(false)
+        }
+        (RemoteFetch::NotModified(_), None) => bail!("received 304 without a cached profile"),
+    }
+}
+
+async fn profile_history_limit() -> usize {
+    Config::verge()
+        .await
+        .latest_arc()
+        .profile_history_limit
+        .unwrap_or(profile_history::DEFAULT_HISTORY_LIMIT)
+}
+
+/// 还没有历史记录时读取更新前的文件，保证可以回滚到本次更新之前的版本
+async fn previous_profile_version(uid: &String) -> Option<(String, ProfileVersion)> {
+    if !profile_history::list_versions(uid)
+        .await
+        .is_ok_and(|versions| versions.is_empty())
+    {
+        return None;
+    }
+
+    let previous = Config::profiles().await.latest_arc().get_item(uid).ok().cloned()?;
+    let data = previous.read_file().await.ok()?;
+    let version = ProfileVersion {
+        time: previous.updated.unwrap_or_default() as i64,
+        status: "previous".into(),
+        extra: previous.extra,
+        ..ProfileVersion::default()
+    };
+    Some((data, version))
+}
+
+async fn record_profile_history(
+    uid: &String,
+    content: &str,
+    extra: Option<PrfExtra>,
+    status: &str,
+    previous: Option<(String, ProfileVersion)>,
+    limit: usize,
+) {
+    if let Some((data, version)) = previous {
+        logging_error!(
+            Type::Config,
+            profile_history::record_version(uid, &data, version, limit).await
+        );
+    }
+
+    let version = ProfileVersion {
+        time: chrono::Local::now().timestamp(),
+        status: status.into(),
+        extra,
+        ..ProfileVersion::default()
+    };
+    logging_error!(
+        Type::Config,
+        profile_history::record_version(uid, content, version, limit).await
+    );
+}
+
+/// 比较两个历史版本，`to` 为空时与当前文件比较
+pub async fn diff_profile_versions(uid: &String, from: &str, to: Option<&str>) -> Result<String> {
+    let old = profile_history::read_version(uid, from).await?;
+    let (new, to_label) = match to {
+        Some(to) => (profile_history::read_version(uid, to).await?, to),
+        None => {
+            let item = Config::profiles().await.latest_arc().get_item(uid)?.clone();
+            (item.read_file().await?.into(), "current")
+        }
+    };
+
+    let diff = similar::TextDiff::from_lines(&old, &new);
+    Ok(diff
+        .unified_diff()
+        .context_radius(3)
+        .header(from, to_label)
+        .to_string()
+        .into())
+}
+
+/// 将订阅回滚到指定的历史版本
+pub async fn rollback_profile(uid: &String, version_id: &str) -> Result<()> {
+    let version = profile_history::list_versions(uid)
+        .await?
+        .into_iter()
+        .find(|version| version.id == version_id)
+        .ok_or_else(|| anyhow::anyhow!("profile version \"{version_id}\" not found"))?;
+    let content = profile_history::read_version(uid, version_id).await?;
+    serde_yaml_ng::from_str::<serde_yaml_ng::Mapping>(&content)
+        .map_err(|err| anyhow::anyhow!("profile version \"{version_id}\" is not a valid yaml: {err}"))?;
+
+    let (mut item, is_current) = {
+        let profiles = Config::profiles().await;
+        let profiles = profiles.latest_arc();
+        (profiles.get_item(uid)?.clone(), profiles.is_current_profile_index(uid))
+    };
+    item.file_data = Some(content.into());
+    item.extra = version.extra;
+    item.updated = Some(chrono::Local::now().timestamp() as usize);
+    // 回滚后的内容与服务端不同，下次更新不能再使用条件请求
+    item.etag = None;
+    item.last_modified = None;
+    profiles_draft_update_item_safe(uid, &mut item).await?;
+    logging!(info, Type::Config, "[订阅回滚] {} 已回滚到版本 {}", uid, version_id);
+
+    if is_current {
+        let outcome = CoreManager::global().update_config_forced().await?;
+        if !outcome.is_valid() {
+            bail!("{outcome}");
+        }
+        handle::Handle::refresh_clash();
+    }
+    handle::Handle::notify_profile_changed(uid);
+    Ok(())
+}
+
+/// 单个地址的重试阶梯：按原配置请求 -> Clash 代理 -> 系统代理
+/// (记录的状态, 日志名称, 覆盖的 (self_proxy, with_proxy))
+const UPDATE_LADDER: [(&str, &str, Option<(bool, bool)>); 3] = [
+    ("direct", "正常", None),
+    ("clash_proxy", "Clash代理", Some((true, false))),
+    ("system_proxy", "系统代理", Some((false, true))),
+];
+
 async fn perform_profile_update(
     uid: &String,
     url: &String,
     opt: Option<&PrfOption>,
     option: Option<&PrfOption>,
     is_mannual_trigger: bool,
-) -> Result<bool> {
+) -> Result<ProfileUpdateReport> {
     logging!(info, Type::Config, "[订阅更新] 开始下载新的订阅内容");
-    let mut merged_opt = PrfOption::merge(opt, option);
+    let merged_opt = PrfOption::merge(opt, option);
     let is_current = {
         let profiles = Config::profiles().await;
         profiles.latest_arc().is_current_profile_index(uid)
@@ -116,74 +291,151 @@ async fn perform_profile_update(
         .get_name_by_uid(uid)
         .cloned()
         .unwrap_or_else(|| String::from("UnKnown Profile"));
+    let current = profiles_arc.get_item(uid).ok().cloned();
+    // 本地文件存在时才发送条件请求，否则 304 会导致无内容可用
+    let previous = match current.as_ref() {
+        Some(item) if item.read_file().await.is_ok() => Some(item),
+        _ => None,
+    };
+    let urls = current
+        .as_ref()
+        .map_or_else(|| vec![url.clone()], |item| item.update_urls(url));
+    let mut report = ProfileUpdateReport {
+        is_current,
+        ..ProfileUpdateReport::new(uid, Some(&profile_name))
+    };
 
-    let mut last_err;
-
-    match PrfItem::from_url(url, None, None, merged_opt.as_ref()).await {
-        Ok(mut item) => {
-            logging!(info, Type::Config, "[订阅更新] 更新订阅配置成功");
-            profiles_draft_update_item_safe(uid, &mut item).await?;
-            return Ok(is_current);
-        }
-        Err(err) => {
+    let mut last_err = None;
+    for (index, target) in urls.iter().enumerate() {
+        let is_primary = target == url;
+        if index > 0 {
             logging!(
-                warn,
+                info,
                 Type::Config,
-                "Warning: [订阅更新] 正常更新失败: {}，尝试使用Clash代理更新",
-                mask_err(&err.to_string())
+                "[订阅更新] 尝试使用{}: {}",
+                if is_primary { "主地址" } else { "镜像地址" },
+                mask_url(target)
             );
-            last_err = err;
         }
-    }
 
-    merged_opt.get_or_insert_with(PrfOption::default).self_proxy = Some(true);
-    merged_opt.get_or_insert_with(PrfOption::default).with_proxy = Some(false);
+        let mut attempt_opt = merged_opt.clone();
+        for (status, label, proxy) in UPDATE_LADDER {
+            if let Some((self_proxy, with_proxy)) = proxy {
+                let attempt_opt = attempt_opt.get_or_insert_with(PrfOption::default);
+                attempt_opt.self_proxy = Some(self_proxy);
+                attempt_opt.with_proxy = Some(with_proxy);
+            }
+
+            match PrfItem::from_url_conditional(target, None, None, attempt_opt.as_ref(), previous).await {
+                Ok(fetched) => {
+                    logging!(info, Type::Config, "[订阅更新] 使用 {} 更新订阅配置成功", label);
+                    let changed = save_fetched_profile(uid, fetched, previous, status).await?;
+                    if proxy.is_some() {
+                        handle::Handle::notice_message("update_with_clash_proxy", profile_name.clone());
+                    }
 
-    match PrfItem::from_url(url, None, None, merged_opt.as_ref()).await {
-        Ok(mut item) => {
-            logging!(info, Type::Config, "[订阅更新] 使用 Clash代理 更新订阅配置成功");
-            profiles_draft_update_item_safe(uid, &mut item).await?;
-            handle::Handle::notice_message("update_with_clash_proxy", profile_name);
-            drop(last_err);
-            return Ok(is_current);
-        }
-        Err(err) => {
-            logging!(
-                warn,
-                Type::Config,
-                "Warning: [订阅更新] Clash代理更新失败: {}，尝试使用系统代理更新",
-                mask_err(&err.to_string())
-            );
-            last_err = err;
+                    if !is_primary {
+                        logging!(
+                            info,
+                            Type::Config,
+                            "[订阅更新] 已通过镜像地址更新: {}",
+                            mask_url(target)
+                        );
+                    }
+                    // 镜像优先时主地址未被尝试，同样计入失败次数，以便之后定期重新探测主地址
+                    let active_mirror = (!is_primary).then(|| target.clone());
+                    let primary_failures = current
+                        .as_ref()
+                        .map_or(0, |item| item.next_primary_failures(is_primary));
+                    logging_error!(
+                        Type::Config,
+                        profiles_draft_update_mirror_state_safe(uid, active_mirror.clone(), primary_failures).await
+                    );
+                    report.status = if changed {
+                        ProfileUpdateStatus::Updated
+                    } else {
+                        ProfileUpdateStatus::NotModified
+                    };
+                    report.route = Some(status.into());
+                    report.mirror = active_mirror;
+                    return Ok(report);
+                }
+                Err(err) => {
+                    logging!(
+                        warn,
+                        Type::Config,
+                        "Warning: [订阅更新] {}更新失败: {}",
+                        label,
+                        mask_err(&err.to_string())
+                    );
+                    last_err = Some(err);
+                }
+            }
         }
     }
+    logging!(warn, Type::Config, "Warning: [订阅更新] 所有地址及重试均已失败");
 
-    merged_opt.get_or_insert_with(PrfOption::default).self_proxy = Some(false);
-    merged_opt.get_or_insert_with(PrfOption::default).with_proxy = Some(true);
+    if let Some(current) = current.as_ref() {
+        logging_error!(
+            Type::Config,
+            profiles_draft_update_mirror_state_safe(
+                uid,
+                current.active_mirror.clone(),
+                current.next_primary_failures(false)
+            )
+            .await
+        );
+    }
+    report.status = ProfileUpdateStatus::Failed;
+    report.error = last_err.as_ref().map(|err| mask_err(&err.to_string()).into());
+    if is_mannual_trigger && let Some(last_err) = last_err {
+        handle::Handle::notice_message("update_failed_even_with_clash", format!("{profile_name} - {last_err}"));
+    }
+    Ok(report)
+}
 
-    match PrfItem::from_url(url, None, None, merged_opt.as_ref()).await {
-        Ok(mut item) => {
-            logging!(info, Type::Config, "[订阅更新] 使用 系统代理 更新订阅配置成功");
-            profiles_draft_update_item_safe(uid, &mut item).await?;
-            handle::Handle::notice_message("update_with_clash_proxy", profile_name);
-            drop(last_err);
-            return Ok(is_current);
+/// 下载订阅内容，不刷新内核配置
+async fn fetch_profile(
+    uid: &String,
+    option: Option<&PrfOption>,
+    ignore_auto_update: bool,
+    is_mannual_trigger: bool,
+) -> Result<ProfileUpdateReport> {
+    logging!(info, Type::Config, "[订阅更新] 开始更新订阅 {}", uid);
+    match should_update_profile(uid, ignore_auto_update).await? {
+        Some((url, opt)) => {
+            let report = perform_profile_update(uid, &url, opt.as_ref(), option, is_mannual_trigger).await?;
+            super::check_quota_alerts(Some(uid)).await;
+            Ok(report)
         }
-        Err(err) => {
-            logging!(
-                warn,
-                Type::Config,
-                "Warning: [订阅更新] 系统代理更新失败: {}，所有重试均已失败",
-                mask_err(&err.to_string())
-            );
-            last_err = err;
+        None => {
+            let name = Config::profiles().await.latest_arc().get_name_by_uid(uid).cloned();
+            Ok(ProfileUpdateReport::new(uid, name.as_ref()))
         }
     }
+}
 
-    if is_mannual_trigger {
-        handle::Handle::notice_message("update_failed_even_with_clash", format!("{profile_name} - {last_err}"));
+async fn refresh_core_config(is_mannual_trigger: bool) {
+    logging!(info, Type::Config, "[订阅更新] 更新内核配置");
+    match CoreManager::global().update_config_with_force(is_mannual_trigger).await {
+        Ok(outcome) if outcome.is_valid() => {
+            logging!(info, Type::Config, "[订阅更新] 更新成功");
+            handle::Handle::refresh_clash();
+        }
+        Ok(outcome @ (ValidationOutcome::Skipped { .. } | ValidationOutcome::Busy)) if !is_mannual_trigger => {
+            logging!(info, Type::Config, "[订阅更新] 本次配置刷新已跳过: {}", outcome);
+        }
+        Ok(outcome) => {
+            let message = outcome.to_string();
+            logging!(error, Type::Config, "[订阅更新] 更新失败: {}", message);
+            handle::Handle::notice_message("update_failed", message);
+        }
+        Err(err) => {
+            logging!(error, Type::Config, "[订阅更新] 更新失败: {}", err);
+            handle::Handle::notice_message("update_failed", format!("{err}"));
+            logging!(error, Type::Config, "{err}");
+        }
     }
-    Ok(is_current)
 }
 
 pub async fn update_profile(
@@ -192,44 +444,101 @@ pub async fn update_profile(
     auto_refresh: bool,
     ignore_auto_update: bool,
     is_mannual_trigger: bool,
-) -> Result<()> {
-    logging!(info, Type::Config, "[订阅更新] 开始更新订阅 {}", uid);
-    let url_opt = should_update_profile(uid, ignore_auto_update).await?;
+) -> Result<ProfileUpdateReport> {
+    let report = fetch_profile(uid, option, ignore_auto_update, is_mannual_trigger).await?;
 
-    let should_refresh = match url_opt {
-        Some((url, opt)) => {
-            perform_profile_update(uid, &url, opt.as_ref(), option, is_mannual_trigger).await? && auto_refresh
-        }
-        None => auto_refresh,
+    let should_refresh = match report.status {
+        ProfileUpdateStatus::Skipped => auto_refresh,
+        _ => auto_refresh && report.needs_refresh(),
     };
-
     if should_refresh {
-        logging!(info, Type::Config, "[订阅更新] 更新内核配置");
-        match CoreManager::global().update_config_with_force(is_mannual_trigger).await {
-            Ok(outcome) if outcome.is_valid() => {
-                logging!(info, Type::Config, "[订阅更新] 更新成功");
-                handle::Handle::refresh_clash();
-            }
-            Ok(outcome @ (ValidationOutcome::Skipped { .. } | ValidationOutcome::Busy)) if !is_mannual_trigger => {
-                logging!(info, Type::Config, "[订阅更新] 本次配置刷新已跳过: {}", outcome);
-            }
-            Ok(outcome) => {
-                let message = outcome.to_string();
-                logging!(error, Type::Config, "[订阅更新] 更新失败: {}", message);
-                handle::Handle::notice_message("update_failed", message);
-            }
-            Err(err) => {
-                logging!(error, Type::Config, "[订阅更新] 更新失败: {}", err);
-                handle::Handle::notice_message("update_failed", format!("{err}"));
-                logging!(error, Type::Config, "{err}");
+        refresh_core_config(is_mannual_trigger).await;
+    }
+
+    Ok(report)
+}
+
+/// 按订阅列表顺序选出要更新的远程订阅，`uids` 为空或未指定时选出全部
+fn remote_targets(items: &[PrfItem], uids: Option<&[String]>) -> Vec<String> {
+    let uids = uids.filter(|uids| !uids.is_empty());
+    items
+        .iter()
+        .filter(|item| item.itype.as_deref() == Some("remote"))
+        .filter_map(|item| item.uid.clone())
+        .filter(|uid| uids.is_none_or(|uids| uids.contains(uid)))
+        .collect()
+}
+
+/// 按并发上限批量更新远程订阅，`uids` 为空时更新全部远程订阅
+/// 内核配置只在全部完成后刷新一次，结果按订阅列表顺序返回
+pub async fn update_profiles_batch(uids: Option<&[String]>, concurrency: usize) -> Vec<ProfileUpdateReport> {
+    let targets = Config::profiles()
+        .await
+        .latest_arc()
+        .get_items()
+        .map(|items| remote_targets(items, uids))
+        .unwrap_or_default();
+    let total = targets.len();
+    logging!(
+        info,
+        Type::Config,
+        "[订阅更新] 批量更新 {} 个订阅，并发数 {}",
+        total,
+        concurrency
+    );
+
+    let mut updates = futures::stream::iter(targets.into_iter().enumerate().map(|(index, uid)| async move {
+        handle::Handle::notify_profile_update_started(&uid);
+        let report = fetch_profile(&uid, None, true, false).await.unwrap_or_else(|err| {
+            logging!(warn, Type::Config, "Warning: [订阅更新] {} 更新失败: {}", uid, err);
+            ProfileUpdateReport {
+                status: ProfileUpdateStatus::Failed,
+                error: Some(mask_err(&err.to_string()).into()),
+                ..ProfileUpdateReport::new(&uid, None)
             }
-        }
+        });
+        handle::Handle::notify_profile_update_completed(&uid);
+        (index, report)
+    }))
+    .buffer_unordered(concurrency.max(1));
+
+    let mut reports = Vec::with_capacity(total);
+    while let Some((index, report)) = updates.next().await {
+        handle::Handle::notify_profile_batch_progress(reports.len() + 1, total, &report);
+        reports.push((index, report));
     }
+    reports.sort_by_key(|(index, _)| *index);
+    let reports: Vec<ProfileUpdateReport> = reports.into_iter().map(|(_, report)| report).collect();
 
-    Ok(())
+    if reports.iter().any(ProfileUpdateReport::needs_refresh) {
+        refresh_core_config(true).await;
+    }
+    reports
 }
 
 /// 增强配置
 pub async fn enhance_profiles() -> Result<ValidationOutcome> {
     CoreManager::global().update_config_forced().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(uid: &str, itype: &str) -> PrfItem {
+        PrfItem {
+            uid: Some(uid.into()),
+            itype: Some(itype.into()),
+            ..PrfItem::default()
+        }
+    }
+
+    #[test]
+    fn empty_uids_select_every_remote_profile() {
+        let items = [item("R1", "remote"), item("L1", "local"), item("R2", "remote")];
+
+        assert_eq!(remote_targets(&items, None), ["R1", "R2"]);
+        assert_eq!(remote_targets(&items, Some(&[])), ["R1", "R2"]);
+        assert_eq!(remote_targets(&items, Some(&["R2".into(), "L1".into()])), ["R2"]);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{
+    config::{Config, PrfQuotaAlert, profiles::profiles_draft_update_quota_alert_safe},
+    utils::{
+        notification::{NotificationEvent, notify_event},
+        quota::{self, ProfileQuotaStatus},
+    },
+};
+use clash_verge_logging::{Type, logging, logging_error};
+use smartstring::alias::String;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+// 批量更新时会并发检查，串行处理避免重复提醒
+static ALERT_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Quota status of all remote profiles with subscription info, most urgent first
+pub async fn profile_quota_status() -> Vec<ProfileQuotaStatus> {
+    let (thresholds, expiry_days) = {
+        let verge = Config::verge().await;
+        let verge = verge.latest_arc();
+        (
+            verge
+                .subscription_quota_thresholds
+                .clone()
+                .unwrap_or_else(|| quota::DEFAULT_QUOTA_THRESHOLDS.to_vec()),
+            verge.subscription_expiry_days.unwrap_or(quota::DEFAULT_EXPIRY_DAYS),
+        )
+    };
+    let now = chrono::Local::now().timestamp();
+
+    let mut statuses: Vec<ProfileQuotaStatus> = Config::profiles()
+        .await
+        .latest_arc()
+        .get_items()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let uid = item.uid.as_ref()?;
+                    let extra = item.extra.as_ref()?;
+                    let name = item.name.as_ref().unwrap_or(uid);
+                    Some(quota::evaluate(uid, name, extra, &thresholds, expiry_days, now))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    quota::sort_by_urgency(&mut statuses);
+    statuses
+}
+
+/// 检查订阅流量及到期时间并发送系统通知，`uid` 为空时检查全部订阅
+/// 每个状态只提醒一次，记录保存在订阅中，重启后不会重复提醒
+pub async fn check_quota_alerts(uid: Option<&String>) {
+    let _guard = ALERT_LOCK.lock().await;
+    let statuses = profile_quota_status().await;
+    let notified: HashMap<String, PrfQuotaAlert> = Config::profiles()
+        .await
+        .latest_arc()
+        .get_items()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| Some((item.uid.clone()?, item.quota_alert?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for status in statuses
+        .iter()
+        .filter(|status| uid.is_none_or(|uid| uid == &status.uid))
+    {
+        let state = quota::alert_state(status);
+        let previous = notified.get(&status.uid);
+        if state.as_ref() == previous {
+            continue;
+        }
+        let should_alert = quota::should_alert(state.as_ref(), previous);
+        logging_error!(
+            Type::Config,
+            profiles_draft_update_quota_alert_safe(&status.uid, state).await
+        );
+        if should_alert {
+            send_quota_alert(status).await;
+        }
+    }
+}
+
+async fn send_quota_alert(status: &ProfileQuotaStatus) {
+    logging!(
+        info,
+        Type::Config,
+        "[订阅提醒] {} level={:?}, used={:?}%, days_left={:?}",
+        status.name,
+        status.level,
+        status.used_percent.map(|percent| percent.round()),
+        status.days_left
+    );
+
+    if let Some(percent) = status.used_percent
+        && (status.threshold_reached.is_some() || status.is_exhausted())
+    {
+        notify_event(NotificationEvent::SubscriptionQuota {
+            name: &status.name,
+            percent: percent.floor() as u64,
+        })
+        .await;
+    }
+    if let Some(days) = status.days_left
+        && (status.expiring || status.is_expired())
+    {
+        notify_event(NotificationEvent::SubscriptionExpiry {
+            name: &status.name,
+            days,
+        })
+        .await;
+    }
+}
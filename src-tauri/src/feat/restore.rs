@@ -0,0 +1,611 @@
+use super::backup::{LocalOnlySettings, finalize_restored_verge_config, report_backup_warnings};
+use crate::{
+    config::{Config, IClashTemp, IProfiles, IVerge, PrfItem, profiles::profiles_merge_restored_items_safe},
+    constants::files::DNS_CONFIG,
+    core::{
+        CoreManager,
+        backup::{self, BackupArchive},
+        backup_directory::DirectoryStorage,
+        backup_manifest::{MANIFEST_FILE, ManifestCheck},
+        backup_s3::S3Client,
+        backup_storage::BackupStorage,
+        backup_store::BackupStore,
+        validate::CoreConfigValidator,
+    },
+    module::auto_backup::AutoBackupManager,
+    utils::{
+        dirs::{self, PathBufExec as _, app_home_dir, local_backup_dir},
+        help,
+    },
+};
+use anyhow::{Context as _, Result, anyhow, bail};
+use clash_verge_logging::{Type, logging};
+use serde::{Deserialize, Serialize};
+use smartstring::alias::String;
+use std::{
+    env::temp_dir,
+    io::Read as _,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+
+/// 备份文件所在位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupSource {
+    Local,
+    Webdav,
+    S3,
+    Directory,
+    /// 增量备份仓库中的快照
+    Incremental,
+}
+
+impl BackupSource {
+    /// 远程备份对应的存储，本地备份返回 `None`
+    fn storage(self) -> Option<&'static dyn BackupStorage> {
+        match self {
+            Self::Local | Self::Incremental => None,
+            Self::Webdav => Some(backup::WebDavClient::global()),
+            Self::S3 => Some(S3Client::global()),
+            Self::Directory => Some(DirectoryStorage::global()),
+        }
+    }
+}
+
+/// 恢复前在此目录中解压并校验备份
+const RESTORE_STAGING_DIR: &str = "restore-staging";
+const ROLLBACK_STAGING_DIR: &str = "restore-rollback";
+
+/// 备份中可单独恢复的一项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "uid", rename_all = "snake_case")]
+pub enum RestoreComponentKind {
+    /// 订阅及其扩展 (merge/script/rules/proxies/groups)
+    Profile(String),
+    GlobalMerge,
+    GlobalScript,
+    ClashConfig,
+    VergeSettings,
+    DnsConfig,
+}
+
+impl RestoreComponentKind {
+    /// 全局扩展对应的订阅项 uid
+    const fn global_uid(&self) -> Option<&'static str> {
+        match self {
+            Self::GlobalMerge => Some("Merge"),
+            Self::GlobalScript => Some("Script"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreComponent {
+    pub component: RestoreComponentKind,
+    pub name: String,
+    pub itype: Option<String>,
+    /// 此项包含的备份文件
+    pub files: Vec<String>,
+    /// 当前配置中已存在，恢复时会被覆盖
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestorePlan {
+    pub filename: String,
+    pub components: Vec<RestoreComponent>,
+    /// 备份清单及版本、平台差异的提示
+    pub check: ManifestCheck,
+}
+
+/// List the parts of a backup that can be restored separately
+pub async fn get_backup_restore_plan(
+    source: BackupSource,
+    filename: String,
+    passphrase: Option<String>,
+) -> Result<RestorePlan> {
+    let (mut zip, check) = load_backup_archive(source, &filename, passphrase).await?;
+    let archived = archived_profiles(&mut zip)?;
+    let live = Config::profiles().await.latest_arc();
+
+    let mut components = Vec::new();
+    for item in archived.items.iter().flatten() {
+        let Some(uid) = item.uid.as_ref() else {
+            continue;
+        };
+        let component = match (uid.as_str(), item.itype.as_deref()) {
+            ("Merge", _) => RestoreComponentKind::GlobalMerge,
+            ("Script", _) => RestoreComponentKind::GlobalScript,
+            (_, Some("remote" | "local")) => RestoreComponentKind::Profile(uid.clone()),
+            _ => continue,
+        };
+        let files: Vec<String> = profile_items(&archived, uid)
+            .iter()
+            .filter_map(|item| item.file.clone())
+            .filter(|file| zip.index_for_name(&profile_entry(file)).is_some())
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+        components.push(RestoreComponent {
+            component,
+            name: item.name.clone().unwrap_or_else(|| uid.clone()),
+            itype: item.itype.clone(),
+            files,
+            exists: live.get_item(uid).is_ok(),
+        });
+    }
+
+    let app_dir = app_home_dir()?;
+    for (component, entry) in [
+        (RestoreComponentKind::ClashConfig, dirs::CLASH_CONFIG),
+        (RestoreComponentKind::VergeSettings, dirs::VERGE_CONFIG),
+        (RestoreComponentKind::DnsConfig, DNS_CONFIG),
+    ] {
+        if zip.index_for_name(entry).is_some() {
+            components.push(RestoreComponent {
+                component,
+                name: entry.into(),
+                itype: None,
+                files: vec![entry.into()],
+                exists: app_dir.join(entry).exists(),
+            });
+        }
+    }
+
+    Ok(RestorePlan {
+        filename,
+        components,
+        check,
+    })
+}
+
+/// Restore a whole backup, replacing the current configuration
+pub async fn restore_backup(source: BackupSource, filename: String, passphrase: Option<String>) -> Result<()> {
+    let (zip, check) = load_backup_archive(source, &filename, passphrase).await?;
+    report_backup_warnings(&check);
+    run_restore(zip, None).await?;
+    logging!(info, Type::Backup, "Restored backup {}", filename);
+    Ok(())
+}
+
+/// Restore only the selected parts of a backup.
+/// Profiles are merged into the current list, other profiles are left untouched.
+pub async fn restore_backup_components(
+    source: BackupSource,
+    filename: String,
+    passphrase: Option<String>,
+    components: Vec<RestoreComponentKind>,
+) -> Result<()> {
+    if components.is_empty() {
+        bail!("No backup component selected");
+    }
+    let (zip, check) = load_backup_archive(source, &filename, passphrase).await?;
+    report_backup_warnings(&check);
+    run_restore(zip, Some(&components)).await?;
+    logging!(
+        info,
+        Type::Backup,
+        "Restored {} component(s) from backup {}",
+        components.len(),
+        filename
+    );
+    Ok(())
+}
+
+/// 解压到暂存目录并预检，通过后创建安全快照再写入配置
+/// 内核无法加载恢复后的配置时自动回滚到快照
+async fn run_restore(mut zip: BackupArchive, scope: Option<&[RestoreComponentKind]>) -> Result<()> {
+    let staging = stage_archive(&mut zip, RESTORE_STAGING_DIR).await?;
+    let res = async {
+        preflight_restore(&staging, scope)
+            .await
+            .context("The backup failed validation, nothing was restored")?;
+        let snapshot = AutoBackupManager::global()
+            .create_safety_backup()
+            .await
+            .context("Failed to create a safety backup before restoring")?;
+
+        let mut added = Vec::new();
+        let applied = async {
+            apply_staged(&staging, scope, &mut added).await?;
+            CoreManager::global().update_config_checked().await
+        }
+        .await;
+        if let Err(err) = applied {
+            logging!(
+                error,
+                Type::Backup,
+                "Restore failed, rolling back to {}: {err:#?}",
+                snapshot
+            );
+            rollback(&snapshot)
+                .await
+                .with_context(|| format!("Restore failed ({err}) and rolling back to \"{snapshot}\" also failed"))?;
+            // 快照中没有恢复时新增的文件，需要单独删除
+            remove_added_files(&added).await;
+            bail!("Restore failed, the previous configuration has been restored: {err}");
+        }
+        Ok(())
+    }
+    .await;
+    let _ = fs::remove_dir_all(&staging).await;
+    res
+}
+
+/// 将安全快照完整恢复，不再预检
+async fn rollback(snapshot: &String) -> Result<()> {
+    let path = local_backup_dir()?.join(snapshot.as_str());
+    let (mut zip, _) = backup::open_backup_archive(&path, None).await?;
+    let staging = stage_archive(&mut zip, ROLLBACK_STAGING_DIR).await?;
+    let res = async {
+        apply_staged(&staging, None, &mut Vec::new()).await?;
+        CoreManager::global().update_config_checked().await
+    }
+    .await;
+    let _ = fs::remove_dir_all(&staging).await;
+    res
+}
+
+async fn stage_archive(zip: &mut BackupArchive, dir_name: &str) -> Result<PathBuf> {
+    let staging = app_home_dir()?.join(dir_name);
+    if staging.exists() {
+        fs::remove_dir_all(&staging).await?;
+    }
+    fs::create_dir_all(&staging).await?;
+    zip.extract(&staging)?;
+    Ok(staging)
+}
+
+/// Generate the runtime config from the staged files and let the core check it.
+/// The config is built from standalone values, the global config is left untouched.
+async fn preflight_restore(staging: &Path, scope: Option<&[RestoreComponentKind]>) -> Result<()> {
+    let selected = |kind: &RestoreComponentKind| scope.is_none_or(|scope| scope.contains(kind));
+    let staged_profiles = staged_profiles(staging).await?;
+
+    let clash = if selected(&RestoreComponentKind::ClashConfig) {
+        IClashTemp::from_file(&staging.join(dirs::CLASH_CONFIG)).await?
+    } else {
+        (*Config::clash().await.latest_arc()).clone()
+    };
+    let verge = if selected(&RestoreComponentKind::VergeSettings) {
+        help::read_yaml::<IVerge>(&staging.join(dirs::VERGE_CONFIG)).await?
+    } else {
+        (*Config::verge().await.latest_arc()).clone()
+    };
+    let staged_dns = staging.join(DNS_CONFIG);
+    let dns_path = if selected(&RestoreComponentKind::DnsConfig) && staged_dns.exists() {
+        help::read_mapping(&staged_dns).await?;
+        staged_dns
+    } else {
+        app_home_dir()?.join(DNS_CONFIG)
+    };
+
+    let profiles = match scope {
+        None => staged_profiles,
+        Some(scope) => {
+            let mut profiles = (*Config::profiles().await.latest_arc()).clone();
+            for component in scope {
+                if let RestoreComponentKind::Profile(uid) = component {
+                    profiles.merge_items(profile_items(&staged_profiles, uid).into_iter().cloned().collect());
+                } else if let Some(uid) = component.global_uid() {
+                    let file = staged_profiles.get_item(uid)?.file.clone();
+                    if let Some(item) = profiles
+                        .items
+                        .iter_mut()
+                        .flatten()
+                        .find(|item| item.uid.as_deref() == Some(uid))
+                    {
+                        item.file = file;
+                    }
+                }
+            }
+            profiles
+        }
+    };
+
+    let config = Config::generate_standalone(&clash, &verge, &profiles, Some(&dns_path)).await?;
+    // 放在暂存目录之外，避免被当作备份文件复制到配置目录
+    let check_path = staging.with_extension("yaml");
+    help::save_yaml(&check_path, &config, Some("# Generated by Clash Verge")).await?;
+    let outcome = CoreConfigValidator::validate_config_file_outcome(dirs::path_to_str(&check_path)?, Some(false)).await;
+    let _ = fs::remove_file(&check_path).await;
+
+    let outcome = outcome?;
+    if !outcome.is_valid() {
+        bail!("{outcome}");
+    }
+    Ok(())
+}
+
+/// 暂存的 profiles.yaml，文件指向暂存目录中的副本
+async fn staged_profiles(staging: &Path) -> Result<IProfiles> {
+    let mut profiles = read_staged_profiles(staging).await?;
+    let profiles_dir = staging.join("profiles");
+    for item in profiles.items.iter_mut().flatten() {
+        if let Some(file) = item.file.as_mut() {
+            *file = profiles_dir.join(file.as_str()).to_string_lossy().into();
+        }
+    }
+    Ok(profiles)
+}
+
+/// 将暂存目录中选中的部分写入配置目录并同步内存中的配置
+/// 原本不存在的文件记录到 `added` 中，回滚时删除
+async fn apply_staged(staging: &Path, scope: Option<&[RestoreComponentKind]>, added: &mut Vec<PathBuf>) -> Result<()> {
+    let local = LocalOnlySettings::current().await;
+    let Some(scope) = scope else {
+        copy_staged_files(staging, added).await?;
+        return finalize_restored_verge_config(local).await;
+    };
+
+    let staged = read_staged_profiles(staging).await?;
+    let profiles_dir = dirs::app_profiles_dir()?;
+    let mut restored_items = Vec::new();
+    for component in scope {
+        match component {
+            RestoreComponentKind::Profile(uid) => {
+                let items = profile_items(&staged, uid);
+                if items.is_empty() {
+                    bail!("Profile \"{uid}\" not found in the backup");
+                }
+                for item in items {
+                    if let Some(file) = item.file.as_ref() {
+                        copy_file(
+                            &staging.join(profile_entry(file)),
+                            &profiles_dir.join(file.as_str()),
+                            added,
+                        )
+                        .await?;
+                    }
+                    restored_items.push(item.clone());
+                }
+            }
+            RestoreComponentKind::GlobalMerge | RestoreComponentKind::GlobalScript => {
+                let uid = component.global_uid().unwrap_or_default();
+                // 全局扩展始终存在，只覆盖其文件
+                let file = Config::profiles()
+                    .await
+                    .latest_arc()
+                    .get_item(uid)?
+                    .file
+                    .clone()
+                    .ok_or_else(|| anyhow!("could not find the file of \"{uid}\""))?;
+                let staged_file = staged.get_item(uid)?.file.clone().unwrap_or_else(|| file.clone());
+                copy_file(
+                    &staging.join(profile_entry(&staged_file)),
+                    &profiles_dir.join(file.as_str()),
+                    added,
+                )
+                .await?;
+            }
+            RestoreComponentKind::ClashConfig => {
+                copy_file(&staging.join(dirs::CLASH_CONFIG), &dirs::clash_path()?, added).await?;
+                let restored_clash = IClashTemp::new().await;
+                let clash_draft = Config::clash().await;
+                clash_draft.edit_draft(|d| {
+                    *d = restored_clash.clone();
+                });
+                clash_draft.apply();
+            }
+            RestoreComponentKind::DnsConfig => {
+                copy_file(&staging.join(DNS_CONFIG), &app_home_dir()?.join(DNS_CONFIG), added).await?;
+            }
+            RestoreComponentKind::VergeSettings => {}
+        }
+    }
+
+    if !restored_items.is_empty() {
+        profiles_merge_restored_items_safe(restored_items).await?;
+    }
+
+    // verge 设置放在最后，finalize 会重新加载其它已恢复的配置
+    if scope.contains(&RestoreComponentKind::VergeSettings) {
+        copy_file(&staging.join(dirs::VERGE_CONFIG), &dirs::verge_path()?, added).await?;
+        finalize_restored_verge_config(local).await?;
+    }
+    Ok(())
+}
+
+/// 复制暂存目录中的全部文件，备份清单除外
+async fn copy_staged_files(staging: &Path, added: &mut Vec<PathBuf>) -> Result<()> {
+    copy_dir_files(staging, &app_home_dir()?, added).await?;
+    copy_dir_files(&staging.join("profiles"), &dirs::app_profiles_dir()?, added).await
+}
+
+async fn copy_dir_files(from: &Path, to: &Path, added: &mut Vec<PathBuf>) -> Result<()> {
+    let Ok(mut entries) = fs::read_dir(from).await else {
+        return Ok(());
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.is_file() && entry.file_name() != MANIFEST_FILE {
+            copy_file(&path, &to.join(entry.file_name()), added).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn copy_file(from: &Path, to: &Path, added: &mut Vec<PathBuf>) -> Result<()> {
+    let existed = to.exists();
+    fs::copy(from, to).await?;
+    if !existed {
+        added.push(to.to_path_buf());
+    }
+    Ok(())
+}
+
+async fn remove_added_files(added: &[PathBuf]) {
+    for path in added {
+        if let Err(err) = fs::remove_file(path).await {
+            logging!(warn, Type::Backup, "Failed to remove {}: {err}", path.display());
+        }
+    }
+}
+
+async fn read_staged_profiles(staging: &Path) -> Result<IProfiles> {
+    let profiles = help::read_yaml::<IProfiles>(&staging.join(dirs::PROFILE_YAML))
+        .await
+        .context("failed to parse profiles.yaml in the backup")?;
+    check_profile_files(&profiles)?;
+    Ok(profiles)
+}
+
+/// 打开本地、增量或远程的备份，远程备份下载到临时目录，读取后即删除
+pub(super) async fn load_backup_archive(
+    source: BackupSource,
+    filename: &String,
+    passphrase: Option<String>,
+) -> Result<(BackupArchive, ManifestCheck)> {
+    if source == BackupSource::Incremental {
+        let content = BackupStore::global().export(filename).await?;
+        return backup::open_backup_content(content, passphrase).await;
+    }
+    let Some(storage) = source.storage() else {
+        let path = local_backup_dir()?.join(filename.as_str());
+        if !path.exists() {
+            bail!("Backup file not found: {filename}");
+        }
+        return backup::open_backup_archive(&path, passphrase).await;
+    };
+
+    let path = temp_dir().join(filename.as_str());
+    storage.download(filename.clone(), path.clone()).await.map_err(|err| {
+        logging!(
+            error,
+            Type::Backup,
+            "Failed to download {} backup file: {err:#?}",
+            storage.name()
+        );
+        err
+    })?;
+    let res = backup::open_backup_archive(&path, passphrase).await;
+    let _ = path.remove_if_exists().await;
+    res
+}
+
+pub(super) fn archived_profiles(zip: &mut BackupArchive) -> Result<IProfiles> {
+    let content = read_entry(zip, dirs::PROFILE_YAML)?;
+    let profiles = serde_yaml_ng::from_slice(&content).context("failed to parse profiles.yaml in the backup")?;
+    check_profile_files(&profiles)?;
+    Ok(profiles)
+}
+
+/// 备份中的订阅文件只允许是 profiles 目录中的文件名，防止路径穿越
+fn check_profile_files(profiles: &IProfiles) -> Result<()> {
+    for file in profiles.items.iter().flatten().filter_map(|item| item.file.as_deref()) {
+        if file.is_empty() || file.contains(['/', '\\', ':']) || file.starts_with('.') {
+            bail!("Invalid profile file name in the backup: {file}");
+        }
+    }
+    Ok(())
+}
+
+/// 订阅本身及其引用的扩展项
+fn profile_items<'a>(profiles: &'a IProfiles, uid: &str) -> Vec<&'a PrfItem> {
+    let Ok(item) = profiles.get_item(uid) else {
+        return Vec::new();
+    };
+    let mut items = vec![item];
+    if let Some(option) = item.option.as_ref() {
+        for child in [
+            &option.merge,
+            &option.script,
+            &option.rules,
+            &option.proxies,
+            &option.groups,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Ok(child) = profiles.get_item(child) {
+                items.push(child);
+            }
+        }
+    }
+    items
+}
+
+pub(super) fn profile_entry(file: &str) -> std::string::String {
+    format!("profiles/{file}")
+}
+
+pub(super) fn read_entry(zip: &mut BackupArchive, name: &str) -> Result<Vec<u8>> {
+    let mut entry = zip
+        .by_name(name)
+        .with_context(|| format!("\"{name}\" not found in the backup"))?;
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write as _};
+    use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+    fn archive(file: &str) -> Result<BackupArchive> {
+        let profiles = format!("items:\n  - uid: R1\n    type: remote\n    file: '{file}'\n");
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.start_file(dirs::PROFILE_YAML, options)?;
+        zip.write_all(profiles.as_bytes())?;
+        zip.start_file("profiles/R1.yaml", options)?;
+        zip.write_all(b"proxies: []")?;
+        Ok(ZipArchive::new(zip.finish()?)?)
+    }
+
+    #[tokio::test]
+    async fn reject_path_traversal_in_backup() -> Result<()> {
+        assert!(archived_profiles(&mut archive("R1.yaml")?).is_ok());
+
+        for file in [
+            "../evil.yaml",
+            "..",
+            "/etc/passwd",
+            "nested/evil.yaml",
+            "..\\evil.yaml",
+            "C:\\evil.yaml",
+            ".hidden.yaml",
+            "",
+        ] {
+            let mut zip = archive(file)?;
+            assert!(archived_profiles(&mut zip).is_err(), "{file}");
+
+            let staging = temp_dir().join(format!("restore-staging-{}", nanoid::nanoid!()));
+            zip.extract(&staging)?;
+            let staged = read_staged_profiles(&staging).await;
+            let _ = fs::remove_dir_all(&staging).await;
+            assert!(staged.is_err(), "{file}");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remove_only_files_added_by_restore() -> Result<()> {
+        let root = temp_dir().join(format!("restore-added-{}", nanoid::nanoid!()));
+        let (from, to) = (root.join("from"), root.join("to"));
+        fs::create_dir_all(&from).await?;
+        fs::create_dir_all(&to).await?;
+        for name in ["existing.yaml", "new.yaml", MANIFEST_FILE] {
+            fs::write(from.join(name), "restored").await?;
+        }
+        fs::write(to.join("existing.yaml"), "previous").await?;
+
+        let mut added = Vec::new();
+        copy_dir_files(&from, &to, &mut added).await?;
+        assert_eq!(added, vec![to.join("new.yaml")]);
+        assert!(!to.join(MANIFEST_FILE).exists());
+
+        remove_added_files(&added).await;
+        let new_removed = !to.join("new.yaml").exists();
+        let existing_kept = to.join("existing.yaml").exists();
+        let _ = fs::remove_dir_all(&root).await;
+        assert!(new_removed);
+        assert!(existing_kept);
+        Ok(())
+    }
+}
@@ -154,6 +154,7 @@ mod app_init {
             cmd::patch_clash_mode,
             cmd::change_clash_core,
             cmd::get_runtime_config,
+            cmd::get_fetch_node_prefix,
             cmd::get_runtime_yaml,
             cmd::get_runtime_exists,
             cmd::get_runtime_logs,
@@ -167,6 +168,9 @@ mod app_init {
             cmd::check_dns_config_exists,
             cmd::get_dns_config_content,
             cmd::validate_dns_config,
+            cmd::import_hosts_file,
+            cmd::import_hosts_url,
+            cmd::remove_hosts_source,
             cmd::get_clash_logs,
             cmd::get_verge_config,
             cmd::patch_verge_config,
@@ -186,23 +190,52 @@ mod app_init {
             cmd::import_profile,
             cmd::reorder_profile,
             cmd::update_profile,
+            cmd::update_profiles,
+            cmd::update_profiles_by_tag,
+            cmd::sync_linked_profile,
+            cmd::filter_profiles,
+            cmd::tag_profiles,
             cmd::delete_profile,
+            cmd::delete_profiles,
             cmd::read_profile_file,
             cmd::save_profile_file,
             cmd::get_next_update_time,
+            cmd::get_profile_update_failures,
+            cmd::get_profile_versions,
+            cmd::diff_profile_versions,
+            cmd::rollback_profile,
+            cmd::get_profiles_quota_status,
             cmd::script_validate_notice,
             cmd::validate_script_file,
             cmd::create_local_backup,
             cmd::list_local_backup,
             cmd::delete_local_backup,
             cmd::restore_local_backup,
+            cmd::get_backup_restore_plan,
+            cmd::preview_backup,
+            cmd::restore_backup_components,
+            cmd::prune_backups,
             cmd::import_local_backup,
             cmd::export_local_backup,
+            cmd::list_incremental_backup,
+            cmd::delete_incremental_backup,
+            cmd::restore_incremental_backup,
+            cmd::export_incremental_backup,
             cmd::create_webdav_backup,
             cmd::save_webdav_config,
             cmd::list_webdav_backup,
             cmd::delete_webdav_backup,
             cmd::restore_webdav_backup,
+            cmd::save_s3_config,
+            cmd::create_s3_backup,
+            cmd::list_s3_backup,
+            cmd::delete_s3_backup,
+            cmd::restore_s3_backup,
+            cmd::save_backup_directory,
+            cmd::create_directory_backup,
+            cmd::list_directory_backup,
+            cmd::delete_directory_backup,
+            cmd::restore_directory_backup,
             cmd::get_unlock_items,
             cmd::check_media_unlock,
         ]
@@ -1,8 +1,11 @@
+use super::backup_retention::{local_policy, prune_local_backups, prune_store_backups};
 use crate::{
-    config::{Config, IVerge},
-    feat::create_local_backup_with_namer,
+    config::{Config, IVerge, IVergeAutoBackupTriggers, IVergeBackupRetention},
+    feat::{
+        create_incremental_backup_with_namer, create_local_backup_with_namer, upload_incremental_backup_webdav,
+        upload_local_backup_webdav,
+    },
     process::AsyncHandler,
-    utils::dirs::local_backup_dir,
 };
 use anyhow::Result;
 use chrono::Local;
@@ -10,30 +13,36 @@ use clash_verge_logging::{Type, logging};
 use once_cell::sync::OnceCell;
 use parking_lot::RwLock;
 use std::{
-    path::PathBuf,
+    collections::HashMap,
     sync::{
         Arc,
-        atomic::{AtomicBool, AtomicI64, Ordering},
+        atomic::{AtomicBool, Ordering},
     },
-    time::{Duration, UNIX_EPOCH},
-};
-use tokio::{
-    fs,
-    sync::{Mutex, watch},
+    time::Duration,
 };
+use tokio::sync::{Mutex, watch};
 
 const DEFAULT_INTERVAL_HOURS: u64 = 24;
 const MIN_INTERVAL_HOURS: u64 = 1;
 const MAX_INTERVAL_HOURS: u64 = 168;
 const MIN_BACKUP_INTERVAL_SECS: i64 = 60;
-const AUTO_BACKUP_KEEP: usize = 20;
-const AUTO_MARKER: &str = "-auto-";
+pub(super) const AUTO_MARKER: &str = "-auto-";
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum AutoBackupTrigger {
     Scheduled,
     GlobalMerge,
     GlobalScript,
+    /// 覆盖订阅自己的扩展项之前
+    ProfileEnhancement,
+    /// 删除订阅之前
+    ProfileDelete,
+    /// 切换内核之前
+    CoreChange,
+    /// 安装应用更新之前
+    AppUpdate,
+    /// 恢复备份前的安全快照
+    BeforeRestore,
 }
 
 impl AutoBackupTrigger {
@@ -42,6 +51,11 @@ impl AutoBackupTrigger {
             Self::Scheduled => "scheduled",
             Self::GlobalMerge => "merge",
             Self::GlobalScript => "script",
+            Self::ProfileEnhancement => "enhance",
+            Self::ProfileDelete => "delete",
+            Self::CoreChange => "core",
+            Self::AppUpdate => "update",
+            Self::BeforeRestore => "restore",
         }
     }
 
@@ -55,6 +69,10 @@ struct AutoBackupSettings {
     schedule_enabled: bool,
     interval_hours: u64,
     change_enabled: bool,
+    upload_webdav: bool,
+    incremental: bool,
+    triggers: IVergeAutoBackupTriggers,
+    retention: Option<IVergeBackupRetention>,
 }
 
 impl AutoBackupSettings {
@@ -68,8 +86,34 @@ impl AutoBackupSettings {
             schedule_enabled: verge.enable_auto_backup_schedule.unwrap_or(false),
             interval_hours: interval,
             change_enabled: verge.auto_backup_on_change.unwrap_or(true),
+            upload_webdav: verge.auto_backup_webdav.unwrap_or(false),
+            // 增量仓库不加密，设置了备份口令时改为加密的完整备份
+            incremental: verge.auto_backup_incremental.unwrap_or(false)
+                && verge
+                    .backup_passphrase
+                    .as_ref()
+                    .is_none_or(|passphrase| passphrase.is_empty()),
+            triggers: verge.auto_backup_triggers.unwrap_or_default(),
+            retention: verge.backup_retention,
         }
     }
+
+    /// 定时备份只受定时开关控制，其余触发器还需开启变更时备份
+    /// 全局扩展默认开启，保持原有行为，新增的触发器需要手动开启
+    fn is_enabled(&self, trigger: AutoBackupTrigger) -> bool {
+        let triggers = &self.triggers;
+        let enabled = match trigger {
+            AutoBackupTrigger::Scheduled => return self.schedule_enabled,
+            AutoBackupTrigger::BeforeRestore => return true,
+            AutoBackupTrigger::GlobalMerge => triggers.global_merge.unwrap_or(true),
+            AutoBackupTrigger::GlobalScript => triggers.global_script.unwrap_or(true),
+            AutoBackupTrigger::ProfileEnhancement => triggers.profile_enhancement.unwrap_or(false),
+            AutoBackupTrigger::ProfileDelete => triggers.profile_delete.unwrap_or(false),
+            AutoBackupTrigger::CoreChange => triggers.core_change.unwrap_or(false),
+            AutoBackupTrigger::AppUpdate => triggers.app_update.unwrap_or(false),
+        };
+        self.change_enabled && enabled
+    }
 }
 
 impl Default for AutoBackupSettings {
@@ -78,6 +122,10 @@ impl Default for AutoBackupSettings {
             schedule_enabled: false,
             interval_hours: DEFAULT_INTERVAL_HOURS,
             change_enabled: true,
+            upload_webdav: false,
+            incremental: false,
+            triggers: IVergeAutoBackupTriggers::default(),
+            retention: None,
         }
     }
 }
@@ -87,22 +135,25 @@ pub struct AutoBackupManager {
     settings_tx: watch::Sender<AutoBackupSettings>,
     runner_started: AtomicBool,
     exec_lock: Mutex<()>,
-    last_backup: AtomicI64,
+    /// 每个触发器上次备份的时间，分别去抖
+    last_backup: RwLock<HashMap<AutoBackupTrigger, i64>>,
 }
 
 impl AutoBackupManager {
     pub fn global() -> &'static Self {
         static INSTANCE: OnceCell<AutoBackupManager> = OnceCell::new();
-        INSTANCE.get_or_init(|| {
-            let (tx, _rx) = watch::channel(AutoBackupSettings::default());
-            Self {
-                settings: Arc::new(RwLock::new(AutoBackupSettings::default())),
-                settings_tx: tx,
-                runner_started: AtomicBool::new(false),
-                exec_lock: Mutex::new(()),
-                last_backup: AtomicI64::new(0),
-            }
-        })
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        let (tx, _rx) = watch::channel(AutoBackupSettings::default());
+        Self {
+            settings: Arc::new(RwLock::new(AutoBackupSettings::default())),
+            settings_tx: tx,
+            runner_started: AtomicBool::new(false),
+            exec_lock: Mutex::new(()),
+            last_backup: RwLock::new(HashMap::new()),
+        }
     }
 
     pub async fn init(&self) -> Result<()> {
@@ -126,16 +177,20 @@ impl AutoBackupManager {
     }
 
     pub fn trigger_backup(trigger: AutoBackupTrigger) {
-        AsyncHandler::spawn(move || async move {
-            if let Err(err) = Self::global().execute_trigger(trigger).await {
-                logging!(
-                    warn,
-                    Type::Backup,
-                    "Auto backup execution failed ({:?}): {err:#?}",
-                    trigger
-                );
-            }
-        });
+        AsyncHandler::spawn(move || Self::run_backup(trigger));
+    }
+
+    /// Back up and wait for it to finish, used before destructive changes.
+    /// A failed backup is only logged and never blocks the change itself.
+    pub async fn run_backup(trigger: AutoBackupTrigger) {
+        if let Err(err) = Self::global().execute_trigger(trigger).await {
+            logging!(
+                warn,
+                Type::Backup,
+                "Auto backup execution failed ({:?}): {err:#?}",
+                trigger
+            );
+        }
     }
 
     fn maybe_start_runner(&self, settings: AutoBackupSettings) {
@@ -194,40 +249,68 @@ impl AutoBackupManager {
     }
 
     async fn execute_trigger(&self, trigger: AutoBackupTrigger) -> Result<()> {
-        let snapshot = *self.settings.read();
-
-        if trigger.is_schedule() && !snapshot.schedule_enabled {
-            return Ok(());
-        }
-        if !trigger.is_schedule() && !snapshot.change_enabled {
+        // 安装更新时自动备份可能尚未初始化，总是读取最新的设置
+        let snapshot = Self::load_settings().await;
+        if !snapshot.is_enabled(trigger) {
             return Ok(());
         }
 
-        if !self.should_run_now() {
+        if !self.should_run_now(trigger) {
             return Ok(());
         }
 
         let _guard = self.exec_lock.lock().await;
-        if !self.should_run_now() {
+        if !self.should_run_now(trigger) {
             return Ok(());
         }
 
-        let file_name = create_local_backup_with_namer(|name| append_auto_suffix(name, trigger.slug()).into()).await?;
-        self.last_backup.store(Local::now().timestamp(), Ordering::Release);
+        let file_name = if snapshot.incremental {
+            create_incremental_backup_with_namer(|name| append_auto_suffix(name, trigger.slug()).into()).await?
+        } else {
+            create_local_backup_with_namer(|name| append_auto_suffix(name, trigger.slug()).into()).await?
+        };
+        self.last_backup.write().insert(trigger, Local::now().timestamp());
 
-        if let Err(err) = cleanup_auto_backups().await {
-            logging!(warn, Type::Backup, "Failed to cleanup old auto backups: {err:#?}");
-        }
+        cleanup_auto_backups(snapshot.retention).await;
 
         logging!(info, Type::Backup, "Auto backup created ({:?}): {}", trigger, file_name);
+
+        if trigger.is_schedule() && snapshot.upload_webdav {
+            let uploaded = if snapshot.incremental {
+                upload_incremental_backup_webdav(&file_name).await
+            } else {
+                upload_local_backup_webdav(&file_name).await
+            };
+            if let Err(err) = uploaded {
+                logging!(warn, Type::Backup, "Failed to upload auto backup to WebDAV: {err:#?}");
+            }
+        }
         Ok(())
     }
 
-    fn should_run_now(&self) -> bool {
-        let last = self.last_backup.load(Ordering::Acquire);
-        if last == 0 {
+    /// Back up the current state before a restore.
+    /// Always runs, regardless of the auto-backup settings and the minimum interval.
+    pub async fn create_safety_backup(&self) -> Result<String> {
+        let trigger = AutoBackupTrigger::BeforeRestore;
+        let _guard = self.exec_lock.lock().await;
+        let file_name = create_local_backup_with_namer(|name| append_auto_suffix(name, trigger.slug()).into()).await?;
+
+        let retention = self.settings.read().retention;
+        cleanup_auto_backups(retention).await;
+
+        logging!(
+            info,
+            Type::Backup,
+            "Safety backup created before restore: {}",
+            file_name
+        );
+        Ok(file_name)
+    }
+
+    fn should_run_now(&self, trigger: AutoBackupTrigger) -> bool {
+        let Some(last) = self.last_backup.read().get(&trigger).copied() else {
             return true;
-        }
+        };
         let now = Local::now().timestamp();
         now.saturating_sub(last) >= MIN_BACKUP_INTERVAL_SECS
     }
@@ -239,75 +322,88 @@ impl AutoBackupManager {
 }
 
 fn append_auto_suffix(file_name: &str, slug: &str) -> String {
-    match file_name.rsplit_once('.') {
+    // 按第一个点分割，保留 `.zip.enc` 这样的多段扩展名
+    match file_name.split_once('.') {
         Some((stem, ext)) => format!("{stem}{AUTO_MARKER}{slug}.{ext}"),
         None => format!("{file_name}{AUTO_MARKER}{slug}"),
     }
 }
 
-async fn cleanup_auto_backups() -> Result<()> {
-    if AUTO_BACKUP_KEEP == 0 {
-        return Ok(());
+async fn cleanup_auto_backups(retention: Option<IVergeBackupRetention>) {
+    let policy = local_policy(retention);
+    if let Err(err) = prune_local_backups(&policy, false).await {
+        logging!(warn, Type::Backup, "Failed to cleanup old auto backups: {err:#?}");
     }
-
-    let backup_dir = local_backup_dir()?;
-    if !backup_dir.exists() {
-        return Ok(());
+    if let Err(err) = prune_store_backups(&policy, false).await {
+        logging!(
+            warn,
+            Type::Backup,
+            "Failed to cleanup old incremental backups: {err:#?}"
+        );
     }
+}
 
-    let mut entries = match fs::read_dir(&backup_dir).await {
-        Ok(dir) => dir,
-        Err(err) => {
-            logging!(warn, Type::Backup, "Failed to read backup directory: {err:#?}");
-            return Ok(());
-        }
-    };
-
-    let mut files: Vec<(PathBuf, u64)> = Vec::new();
-
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let file_name = match entry.file_name().into_string() {
-            Ok(name) => name,
-            Err(_) => continue,
+    #[test]
+    fn incremental_falls_back_to_full_backups_with_passphrase() {
+        let mut verge = IVerge {
+            auto_backup_incremental: Some(true),
+            ..IVerge::default()
         };
+        assert!(AutoBackupSettings::from_verge(&verge).incremental);
 
-        if !file_name.contains(AUTO_MARKER) {
-            continue;
-        }
-
-        let modified = entry
-            .metadata()
-            .await
-            .and_then(|meta| meta.modified())
-            .ok()
-            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
-            .map(|dur| dur.as_secs())
-            .unwrap_or(0);
+        verge.backup_passphrase = Some("".into());
+        assert!(AutoBackupSettings::from_verge(&verge).incremental);
 
-        files.push((path, modified));
+        verge.backup_passphrase = Some("passphrase".into());
+        assert!(!AutoBackupSettings::from_verge(&verge).incremental);
     }
 
-    if files.len() <= AUTO_BACKUP_KEEP {
-        return Ok(());
-    }
-
-    files.sort_by_key(|(_, ts)| *ts);
-    let remove_count = files.len() - AUTO_BACKUP_KEEP;
-    for (path, _) in files.into_iter().take(remove_count) {
-        if let Err(err) = fs::remove_file(&path).await {
-            logging!(
-                warn,
-                Type::Backup,
-                "Failed to remove auto backup {}: {err:#?}",
-                path.display()
-            );
+    #[test]
+    fn new_triggers_are_opt_in() {
+        let mut settings = AutoBackupSettings::default();
+        assert!(settings.is_enabled(AutoBackupTrigger::GlobalMerge));
+        assert!(settings.is_enabled(AutoBackupTrigger::GlobalScript));
+        assert!(settings.is_enabled(AutoBackupTrigger::BeforeRestore));
+        assert!(!settings.is_enabled(AutoBackupTrigger::Scheduled));
+        for trigger in [
+            AutoBackupTrigger::ProfileEnhancement,
+            AutoBackupTrigger::ProfileDelete,
+            AutoBackupTrigger::CoreChange,
+            AutoBackupTrigger::AppUpdate,
+        ] {
+            assert!(!settings.is_enabled(trigger), "{trigger:?}");
         }
+
+        settings.triggers.core_change = Some(true);
+        settings.triggers.global_merge = Some(false);
+        assert!(settings.is_enabled(AutoBackupTrigger::CoreChange));
+        assert!(!settings.is_enabled(AutoBackupTrigger::GlobalMerge));
+
+        // 关闭变更时备份后只剩定时备份与恢复前的安全快照
+        settings.change_enabled = false;
+        settings.schedule_enabled = true;
+        assert!(!settings.is_enabled(AutoBackupTrigger::CoreChange));
+        assert!(!settings.is_enabled(AutoBackupTrigger::GlobalScript));
+        assert!(settings.is_enabled(AutoBackupTrigger::Scheduled));
+        assert!(settings.is_enabled(AutoBackupTrigger::BeforeRestore));
     }
 
-    Ok(())
+    #[test]
+    fn debounce_each_trigger_separately() {
+        let manager = AutoBackupManager::new();
+        let now = Local::now().timestamp();
+        manager.last_backup.write().insert(AutoBackupTrigger::CoreChange, now);
+        manager
+            .last_backup
+            .write()
+            .insert(AutoBackupTrigger::ProfileDelete, now - MIN_BACKUP_INTERVAL_SECS);
+
+        assert!(!manager.should_run_now(AutoBackupTrigger::CoreChange));
+        assert!(manager.should_run_now(AutoBackupTrigger::ProfileDelete));
+        assert!(manager.should_run_now(AutoBackupTrigger::AppUpdate));
+    }
 }
@@ -0,0 +1,173 @@
+use crate::{
+    config::Config,
+    core::{
+        backup_directory::DirectoryStorage,
+        backup_storage::{BackupStorage as _, RemoteBackupFile},
+        handle,
+    },
+    process::AsyncHandler,
+    singleton,
+};
+use chrono::{DateTime, Utc};
+use clash_verge_logging::{Type, logging};
+use smartstring::alias::String;
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// 检查备份目录的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+struct WatchState {
+    dir: PathBuf,
+    known: HashSet<String>,
+    latest: Option<DateTime<Utc>>,
+}
+
+impl WatchState {
+    /// 开始监视时目录中已有的备份只作为基准，不会提示恢复
+    fn new(dir: PathBuf, files: Vec<RemoteBackupFile>) -> Self {
+        let mut state = Self {
+            dir,
+            known: HashSet::new(),
+            latest: None,
+        };
+        state.update(files, |_| true);
+        state
+    }
+
+    /// Record newly appeared backups, returning the newest one written by another device
+    /// if it is newer than every backup seen before.
+    fn update(&mut self, files: Vec<RemoteBackupFile>, is_own: impl Fn(&str) -> bool) -> Option<String> {
+        let previous = self.latest;
+        let mut newest: Option<(DateTime<Utc>, String)> = None;
+        for file in files {
+            if !self.known.insert(file.filename.clone()) {
+                continue;
+            }
+            let Ok(time) = DateTime::parse_from_rfc3339(&file.last_modified).map(|time| time.with_timezone(&Utc))
+            else {
+                continue;
+            };
+            self.latest = self.latest.max(Some(time));
+            if is_own(&file.filename) || previous.is_some_and(|latest| time <= latest) {
+                continue;
+            }
+            if newest.as_ref().is_none_or(|(newest, _)| time > *newest) {
+                newest = Some((time, file.filename));
+            }
+        }
+        newest.map(|(_, filename)| filename)
+    }
+}
+
+/// Watches the backup directory and offers to restore newer backups from other devices
+pub struct BackupDirectoryWatcher {
+    started: AtomicBool,
+}
+
+singleton!(BackupDirectoryWatcher, BACKUP_DIRECTORY_WATCHER);
+
+impl BackupDirectoryWatcher {
+    const fn new() -> Self {
+        Self {
+            started: AtomicBool::new(false),
+        }
+    }
+
+    pub fn init(&self) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        AsyncHandler::spawn(|| async {
+            Self::run().await;
+        });
+    }
+
+    async fn run() {
+        let mut state: Option<WatchState> = None;
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+
+            let Some(dir) = watched_directory().await else {
+                state = None;
+                continue;
+            };
+            let storage = DirectoryStorage::global();
+            let files = match storage.list().await {
+                Ok(files) => files,
+                Err(err) => {
+                    logging!(warn, Type::Backup, "Failed to scan backup directory: {err:#?}");
+                    continue;
+                }
+            };
+
+            match state.as_mut() {
+                Some(current) if current.dir == dir => {
+                    if let Some(filename) = current.update(files, |name| storage.is_own_upload(name)) {
+                        logging!(
+                            info,
+                            Type::Backup,
+                            "Found a newer backup in backup directory: {}",
+                            filename
+                        );
+                        handle::Handle::notice_message("backup::directory_newer", filename);
+                    }
+                }
+                _ => state = Some(WatchState::new(dir, files)),
+            }
+        }
+    }
+}
+
+async fn watched_directory() -> Option<PathBuf> {
+    let enabled = Config::verge()
+        .await
+        .latest_arc()
+        .backup_directory_watch
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+    DirectoryStorage::directory().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, time: &str) -> RemoteBackupFile {
+        RemoteBackupFile {
+            filename: name.into(),
+            last_modified: time.into(),
+            content_length: 0,
+        }
+    }
+
+    #[test]
+    fn offer_only_newer_backups_from_other_devices() {
+        let existing = file("linux-backup-2026-03-10_08-00-00.zip", "2026-03-10T08:00:00+00:00");
+        let mut state = WatchState::new(PathBuf::from("/backups"), vec![existing.clone()]);
+        assert_eq!(state.update(vec![existing.clone()], |_| false), None);
+
+        let own = file("linux-backup-2026-03-10_09-00-00.zip", "2026-03-10T09:00:00+00:00");
+        let older = file("macos-backup-2026-03-09_08-00-00.zip", "2026-03-09T08:00:00+00:00");
+        assert_eq!(
+            state.update(vec![existing, own.clone(), older], |name| name == own.filename.as_str()),
+            None
+        );
+
+        let newer = file("macos-backup-2026-03-10_10-00-00.zip", "2026-03-10T10:00:00+00:00");
+        let newest = file("windows-backup-2026-03-10_11-00-00.zip", "2026-03-10T11:00:00+00:00");
+        assert_eq!(
+            state.update(vec![newer, newest.clone()], |_| false),
+            Some(newest.filename.clone())
+        );
+        // 已经提示过的备份不会重复提示
+        assert_eq!(state.update(vec![newest], |_| false), None);
+    }
+}
@@ -0,0 +1,290 @@
+use super::auto_backup::AUTO_MARKER;
+use crate::{
+    config::IVergeBackupRetention,
+    core::{backup::is_backup_file, backup_storage::BackupStorage, backup_store::BackupStore},
+    utils::dirs::local_backup_dir,
+};
+use anyhow::Result;
+use chrono::{DateTime, Datelike as _, Duration, Local, NaiveDateTime, Utc};
+use clash_verge_logging::{Type, logging};
+use smartstring::alias::String;
+use std::{collections::HashSet, env::consts::OS};
+use tokio::fs;
+
+/// 未配置保留策略时本地自动备份保留的数量
+pub const AUTO_BACKUP_KEEP: usize = 20;
+
+const BACKUP_TIME_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+const BACKUP_TIME_LENGTH: usize = 19;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionCandidate {
+    pub name: String,
+    pub time: NaiveDateTime,
+}
+
+impl RetentionCandidate {
+    /// 优先使用文件名中的备份时间，其次使用修改时间
+    fn new(name: &str, modified: DateTime<Utc>) -> Self {
+        Self {
+            name: name.into(),
+            time: backup_time(name).unwrap_or_else(|| modified.with_timezone(&Local).naive_local()),
+        }
+    }
+}
+
+/// Local retention falls back to keeping the newest `AUTO_BACKUP_KEEP` auto backups
+pub fn local_policy(policy: Option<IVergeBackupRetention>) -> IVergeBackupRetention {
+    policy
+        .filter(|policy| !policy.is_empty())
+        .unwrap_or_else(|| IVergeBackupRetention {
+            keep_last: Some(AUTO_BACKUP_KEEP),
+            ..Default::default()
+        })
+}
+
+/// Return the backups not selected by any retention rule, newest first.
+/// An empty policy keeps everything.
+pub fn select_expired(
+    mut backups: Vec<RetentionCandidate>,
+    policy: &IVergeBackupRetention,
+    now: NaiveDateTime,
+) -> Vec<RetentionCandidate> {
+    if policy.is_empty() {
+        return Vec::new();
+    }
+
+    let keep_last = policy.keep_last.unwrap_or(0);
+    let daily_since = policy
+        .keep_daily
+        .filter(|days| *days > 0)
+        .map(|days| now - Duration::days(i64::from(days)));
+    let weekly_since = policy
+        .keep_weekly
+        .filter(|weeks| *weeks > 0)
+        .map(|weeks| now - Duration::weeks(i64::from(weeks)));
+
+    backups.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| b.name.cmp(&a.name)));
+
+    // 按时间倒序遍历，每个日期/周的第一个即为该区间内最新的备份
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    let mut expired = Vec::new();
+    for (index, backup) in backups.into_iter().enumerate() {
+        let daily = daily_since.is_some_and(|since| backup.time >= since) && days.insert(backup.time.date());
+        let weekly = weekly_since.is_some_and(|since| backup.time >= since) && weeks.insert(backup.time.iso_week());
+        if index >= keep_last && !daily && !weekly {
+            expired.push(backup);
+        }
+    }
+    expired
+}
+
+/// Apply the policy to the local auto backups, manual backups are never pruned
+pub async fn prune_local_backups(policy: &IVergeBackupRetention, dry_run: bool) -> Result<Vec<String>> {
+    let backup_dir = local_backup_dir()?;
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut candidates = Vec::new();
+    let mut entries = fs::read_dir(&backup_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let Ok(file_name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if !file_name.contains(AUTO_MARKER) || !is_backup_file(&file_name) {
+            continue;
+        }
+        let modified = metadata.modified().map(DateTime::<Utc>::from).unwrap_or_default();
+        candidates.push(RetentionCandidate::new(&file_name, modified));
+    }
+
+    let expired = select_expired(candidates, policy, Local::now().naive_local());
+    let mut pruned = Vec::with_capacity(expired.len());
+    for backup in expired {
+        if !dry_run && let Err(err) = fs::remove_file(backup_dir.join(backup.name.as_str())).await {
+            logging!(
+                warn,
+                Type::Backup,
+                "Failed to remove local backup {}: {err:#?}",
+                backup.name
+            );
+            continue;
+        }
+        pruned.push(backup.name);
+    }
+
+    if !dry_run && !pruned.is_empty() {
+        logging!(info, Type::Backup, "Pruned {} local backups", pruned.len());
+    }
+    Ok(pruned)
+}
+
+/// Apply the policy to the remote backups created on this platform
+pub async fn prune_remote_backups(
+    storage: &dyn BackupStorage,
+    policy: &IVergeBackupRetention,
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    if policy.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = format!("{OS}-backup-");
+    let candidates = storage
+        .list()
+        .await?
+        .into_iter()
+        .filter(|file| file.filename.starts_with(&prefix) && is_backup_file(&file.filename))
+        .map(|file| {
+            let modified = DateTime::parse_from_rfc3339(&file.last_modified)
+                .map(|time| time.with_timezone(&Utc))
+                .unwrap_or_default();
+            RetentionCandidate::new(&file.filename, modified)
+        })
+        .collect();
+
+    let expired = select_expired(candidates, policy, Local::now().naive_local());
+    let mut pruned = Vec::with_capacity(expired.len());
+    for backup in expired {
+        if !dry_run && let Err(err) = storage.delete(backup.name.clone()).await {
+            logging!(
+                warn,
+                Type::Backup,
+                "Failed to remove {} backup {}: {err:#?}",
+                storage.name(),
+                backup.name
+            );
+            continue;
+        }
+        pruned.push(backup.name);
+    }
+
+    if !dry_run && !pruned.is_empty() {
+        logging!(info, Type::Backup, "Pruned {} {} backups", pruned.len(), storage.name());
+    }
+    Ok(pruned)
+}
+
+/// Apply the policy to the incremental backups, then drop the files no snapshot refers to anymore
+pub async fn prune_store_backups(policy: &IVergeBackupRetention, dry_run: bool) -> Result<Vec<String>> {
+    let store = BackupStore::global();
+    let candidates = store
+        .list()
+        .await?
+        .into_iter()
+        .map(|snapshot| {
+            let created = DateTime::from_timestamp(snapshot.created_at, 0).unwrap_or_default();
+            RetentionCandidate::new(&snapshot.name, created)
+        })
+        .collect();
+
+    let pruned: Vec<String> = select_expired(candidates, policy, Local::now().naive_local())
+        .into_iter()
+        .map(|backup| backup.name)
+        .collect();
+    if !dry_run && !pruned.is_empty() {
+        let report = store.delete(&pruned).await?;
+        logging!(
+            info,
+            Type::Backup,
+            "Pruned {} incremental backups, freed {} bytes",
+            pruned.len(),
+            report.bytes
+        );
+    }
+    Ok(pruned)
+}
+
+/// 从 `{OS}-backup-%Y-%m-%d_%H-%M-%S...` 格式的文件名中解析备份时间
+fn backup_time(file_name: &str) -> Option<NaiveDateTime> {
+    let (_, rest) = file_name.split_once("-backup-")?;
+    let stamp = rest.get(..BACKUP_TIME_LENGTH)?;
+    NaiveDateTime::parse_from_str(stamp, BACKUP_TIME_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(name: &str) -> Option<RetentionCandidate> {
+        Some(RetentionCandidate {
+            name: name.into(),
+            time: backup_time(name)?,
+        })
+    }
+
+    fn expired_names(names: &[&str], policy: &IVergeBackupRetention, now: &str) -> Vec<String> {
+        let backups = names.iter().filter_map(|name| candidate(name)).collect();
+        let now = NaiveDateTime::parse_from_str(now, BACKUP_TIME_FORMAT).unwrap_or_default();
+        select_expired(backups, policy, now)
+            .into_iter()
+            .map(|backup| backup.name)
+            .collect()
+    }
+
+    #[test]
+    fn backup_retention_rules() {
+        let names = [
+            "linux-backup-2026-03-10_20-00-00.zip",
+            "linux-backup-2026-03-10_08-00-00-auto-scheduled.zip",
+            "linux-backup-2026-03-09_12-00-00.zip.enc",
+            "linux-backup-2026-03-05_12-00-00.zip",
+            "linux-backup-2026-03-03_12-00-00.zip",
+            "linux-backup-2026-02-20_12-00-00.zip",
+            "linux-backup-2026-01-01_12-00-00.zip",
+        ];
+        let now = "2026-03-10_21-00-00";
+        assert_eq!(backup_time("backup.zip"), None);
+
+        assert!(expired_names(&names, &IVergeBackupRetention::default(), now).is_empty());
+
+        let keep_last = IVergeBackupRetention {
+            keep_last: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            expired_names(&names, &keep_last, now),
+            [
+                "linux-backup-2026-02-20_12-00-00.zip",
+                "linux-backup-2026-01-01_12-00-00.zip"
+            ]
+        );
+
+        // 每天只保留最新的一个，超出天数范围的全部清理
+        let daily = IVergeBackupRetention {
+            keep_daily: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(
+            expired_names(&names, &daily, now),
+            [
+                "linux-backup-2026-03-10_08-00-00-auto-scheduled.zip",
+                "linux-backup-2026-03-03_12-00-00.zip",
+                "linux-backup-2026-02-20_12-00-00.zip",
+                "linux-backup-2026-01-01_12-00-00.zip",
+            ]
+        );
+
+        // 2026-03-09 is a Monday: the 9th and 10th share an ISO week, so do the 3rd and 5th
+        let combined = IVergeBackupRetention {
+            keep_last: Some(1),
+            keep_daily: Some(0),
+            keep_weekly: Some(4),
+        };
+        assert_eq!(
+            expired_names(&names, &combined, now),
+            [
+                "linux-backup-2026-03-10_08-00-00-auto-scheduled.zip",
+                "linux-backup-2026-03-09_12-00-00.zip.enc",
+                "linux-backup-2026-03-03_12-00-00.zip",
+                "linux-backup-2026-01-01_12-00-00.zip",
+            ]
+        );
+    }
+}
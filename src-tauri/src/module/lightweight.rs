@@ -249,8 +249,9 @@ async fn setup_light_weight_timer() -> Result<()> {
         let mut timer_map = Timer::global().timer_map.write();
         let timer_task = crate::core::timer::TimerTask {
             task_id,
-            interval_minutes: once_by_minutes,
+            schedule: crate::core::timer::TimerSchedule::Interval(once_by_minutes),
             last_run: chrono::Local::now().timestamp(),
+            retry: None,
         };
         timer_map.insert(LIGHT_WEIGHT_TASK_UID.into(), timer_task);
     }
@@ -0,0 +1,271 @@
+use crate::{
+    config::{Config, PrfItem, profiles::profiles_patch_item_safe},
+    core::{CoreManager, handle, validate::CoreConfigValidator},
+    process::AsyncHandler,
+    singleton,
+    utils::dirs,
+};
+use anyhow::{Context as _, Result, anyhow};
+use clash_verge_logging::{Type, logging};
+use serde_yaml_ng::Mapping;
+use smartstring::alias::String;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::fs;
+
+/// 检查外部文件变化的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 文件停止变化后再等待的时间，避免生成工具分多次写入时重复加载
+const DEBOUNCE: Duration = Duration::from_millis(800);
+
+/// (修改时间, 文件大小)
+type FileStamp = (SystemTime, u64);
+
+struct WatchState {
+    path: String,
+    stamp: Option<FileStamp>,
+    /// the file changed at this time and has not been synced yet
+    pending_since: Option<Instant>,
+}
+
+impl WatchState {
+    fn new(path: String, stamp: Option<FileStamp>) -> Self {
+        Self {
+            path,
+            stamp,
+            // 首次发现时同步一次，包括应用关闭期间的修改
+            pending_since: Some(Instant::now()),
+        }
+    }
+}
+
+/// Watches the external files of linked local profiles
+pub struct LinkedProfileWatcher {
+    started: AtomicBool,
+}
+
+singleton!(LinkedProfileWatcher, LINKED_PROFILE_WATCHER);
+
+impl LinkedProfileWatcher {
+    const fn new() -> Self {
+        Self {
+            started: AtomicBool::new(false),
+        }
+    }
+
+    pub fn init(&self) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        AsyncHandler::spawn(|| async {
+            Self::run().await;
+        });
+    }
+
+    async fn run() {
+        let mut states: HashMap<String, WatchState> = HashMap::new();
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+
+            let linked = linked_profiles().await;
+            states.retain(|uid, _| linked.iter().any(|(linked_uid, _)| linked_uid == uid));
+
+            for (uid, path) in linked {
+                let stamp = file_stamp(&path).await;
+                let state = states
+                    .entry(uid.clone())
+                    .or_insert_with(|| WatchState::new(path.clone(), stamp));
+                if state.path != path {
+                    *state = WatchState::new(path, stamp);
+                    continue;
+                }
+                if state.stamp != stamp {
+                    if stamp.is_none() {
+                        logging!(
+                            warn,
+                            Type::Config,
+                            "Warning: [关联文件] 无法读取 {}，保留上次的内容",
+                            path
+                        );
+                    }
+                    state.stamp = stamp;
+                    state.pending_since = Some(Instant::now());
+                    continue;
+                }
+
+                let settled = state.pending_since.is_some_and(|since| since.elapsed() >= DEBOUNCE);
+                if stamp.is_none() || !settled {
+                    continue;
+                }
+                state.pending_since = None;
+
+                if let Err(err) = sync_linked_profile(&uid).await {
+                    let name = Config::profiles()
+                        .await
+                        .latest_arc()
+                        .get_name_by_uid(&uid)
+                        .cloned()
+                        .unwrap_or_else(|| uid.clone());
+                    logging!(warn, Type::Config, "Warning: [关联文件] {} 同步失败: {}", name, err);
+                    handle::Handle::notice_message("linked_profile::invalid", format!("{name} - {err}"));
+                }
+            }
+        }
+    }
+}
+
+/// 将关联的外部文件同步到订阅文件，返回内容是否发生变化
+/// 外部文件无效时保留订阅文件中上一次有效的内容
+pub async fn sync_linked_profile(uid: &String) -> Result<bool> {
+    let (item, is_current) = {
+        let profiles = Config::profiles().await;
+        let profiles = profiles.latest_arc();
+        (profiles.get_item(uid)?.clone(), profiles.is_current_profile_index(uid))
+    };
+    let linked_path = item
+        .linked_path
+        .as_ref()
+        .ok_or_else(|| anyhow!("profile \"{uid}\" is not linked to an external file"))?;
+    let file = item.file.as_ref().ok_or_else(|| anyhow!("could not find the file"))?;
+
+    let content = fs::read_to_string(linked_path.as_str())
+        .await
+        .with_context(|| format!("failed to read the linked file \"{linked_path}\""))?;
+    let content = content.trim_start_matches('\u{feff}');
+    let previous = item.read_file().await.ok();
+    if previous.as_deref() == Some(content) {
+        return Ok(false);
+    }
+    serde_yaml_ng::from_str::<Mapping>(content).context("the linked file is not a valid yaml mapping")?;
+
+    let path = dirs::app_profiles_dir()?.join(file.as_str());
+    replace_validated(&path, content, |staged| async move {
+        let outcome = CoreConfigValidator::validate_config_file_outcome(&staged.to_string_lossy(), Some(false)).await?;
+        if outcome.is_valid() {
+            Ok(())
+        } else {
+            Err(anyhow!("{outcome}"))
+        }
+    })
+    .await?;
+
+    profiles_patch_item_safe(
+        uid,
+        &PrfItem {
+            updated: Some(chrono::Local::now().timestamp() as usize),
+            ..PrfItem::default()
+        },
+    )
+    .await?;
+    logging!(info, Type::Config, "[关联文件] 已同步 {} -> {}", linked_path, uid);
+
+    if is_current {
+        CoreManager::global().update_config_checked().await?;
+        handle::Handle::refresh_clash();
+    }
+    handle::Handle::notice_message(
+        "linked_profile::reloaded",
+        item.name.clone().unwrap_or_else(|| uid.clone()),
+    );
+    Ok(true)
+}
+
+/// 先将内容写入同目录下的临时文件并校验，通过后再替换 `path`
+/// 校验失败时 `path` 保持不变（包括不存在的情况）
+async fn replace_validated<F, Fut>(path: &Path, content: &str, validate: F) -> Result<()>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid profile path \"{}\"", path.display()))?
+        .to_string_lossy();
+    // 保留扩展名，校验时据此判断文件类型
+    let staged = path.with_file_name(format!(".linked-{file_name}"));
+    fs::write(&staged, content)
+        .await
+        .with_context(|| format!("failed to write \"{}\"", staged.display()))?;
+
+    if let Err(err) = validate(staged.clone()).await {
+        let _ = fs::remove_file(&staged).await;
+        return Err(err);
+    }
+    fs::rename(&staged, path)
+        .await
+        .with_context(|| format!("failed to replace \"{}\"", path.display()))
+}
+
+async fn linked_profiles() -> Vec<(String, String)> {
+    Config::profiles()
+        .await
+        .latest_arc()
+        .get_items()
+        .map(|items| {
+            items
+                .iter()
+                .filter(|item| item.itype.as_deref() == Some("local"))
+                .filter_map(|item| Some((item.uid.clone()?, item.linked_path.clone()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn file_stamp(path: &str) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn replace_profile_after_validation() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("linked-profile-{}", nanoid::nanoid!()));
+        fs::create_dir_all(&dir).await?;
+        let path = dir.join("profile.yaml");
+        fs::write(&path, "mode: rule\n").await?;
+
+        replace_validated(&path, "mode: global\n", |staged| async move {
+            assert_eq!(staged.extension().and_then(|ext| ext.to_str()), Some("yaml"));
+            assert_eq!(fs::read_to_string(&staged).await?, "mode: global\n");
+            Ok(())
+        })
+        .await?;
+        assert_eq!(fs::read_to_string(&path).await?, "mode: global\n");
+        assert!(!dir.join(".linked-profile.yaml").exists());
+
+        let _ = fs::remove_dir_all(&dir).await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keep_profile_when_validation_fails() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("linked-profile-{}", nanoid::nanoid!()));
+        fs::create_dir_all(&dir).await?;
+        let path = dir.join("profile.yaml");
+        fs::write(&path, "mode: rule\n").await?;
+
+        let result = replace_validated(&path, "proxies: 1\n", |_| async { Err(anyhow!("invalid proxies")) }).await;
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).await?, "mode: rule\n");
+        assert!(!dir.join(".linked-profile.yaml").exists());
+
+        // 首次同步时订阅文件尚不存在，校验失败后也不应留下无效文件
+        let missing = dir.join("missing.yaml");
+        let result = replace_validated(&missing, "proxies: 1\n", |_| async { Err(anyhow!("invalid proxies")) }).await;
+        assert!(result.is_err());
+        assert!(!missing.exists());
+        assert!(!dir.join(".linked-missing.yaml").exists());
+
+        let _ = fs::remove_dir_all(&dir).await;
+        Ok(())
+    }
+}
@@ -1,2 +1,5 @@
 pub mod auto_backup;
+pub mod backup_directory_watcher;
+pub mod backup_retention;
 pub mod lightweight;
+pub mod linked_profile;
@@ -85,6 +85,16 @@ pub fn app_profiles_dir() -> Result<PathBuf> {
     Ok(app_home_dir()?.join("profiles"))
 }
 
+/// remote profile history dir, kept out of the profiles dir so it is not scanned or backed up
+pub fn app_profile_history_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("profile_history"))
+}
+
+/// addresses written by each auto-refreshed hosts list
+pub fn app_hosts_sources_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("hosts_sources"))
+}
+
 /// icons dir
 pub fn app_icons_dir() -> Result<PathBuf> {
     Ok(app_home_dir()?.join("icons"))
@@ -0,0 +1,370 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::IpAddr,
+    str::FromStr as _,
+};
+
+/// 常见 hosts 文件 / 广告屏蔽列表中自带的系统条目，导入时忽略
+const SYSTEM_HOSTNAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+];
+
+/// How imported entries are combined with the existing `hosts` section
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HostsImportMode {
+    /// keep existing entries, append new domains and union addresses of duplicated ones
+    #[default]
+    Merge,
+    /// drop the existing section and keep only the imported entries
+    Replace,
+}
+
+/// host -> addresses a hosts source has written into the `hosts` section
+pub type HostsOrigin = BTreeMap<String, Vec<String>>;
+
+/// Result of parsing a hosts-format file
+#[derive(Debug, Default)]
+pub struct ParsedHosts {
+    /// domain -> addresses, in first-seen order and deduplicated
+    pub entries: Vec<(String, Vec<String>)>,
+    /// lines or hostnames rejected by validation
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HostsImportSummary {
+    /// number of distinct domains found in the source
+    pub parsed: usize,
+    /// domains that did not exist in the `hosts` section before
+    pub added: usize,
+    /// existing domains that received new addresses
+    pub updated: usize,
+    /// domains whose addresses were dropped by the source since the last refresh
+    pub removed: usize,
+    /// invalid lines or hostnames
+    pub skipped: usize,
+    /// size of the `hosts` section after import
+    pub total: usize,
+}
+
+/// 解析 hosts 格式内容
+/// `<ip> <host> [host...] [# comment]`
+pub fn parse_hosts(content: &str) -> ParsedHosts {
+    let mut parsed = ParsedHosts::default();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let Some(ip) = parts.next().and_then(normalize_ip) else {
+            parsed.skipped += 1;
+            continue;
+        };
+
+        for host in parts {
+            if SYSTEM_HOSTNAMES.contains(&host.to_ascii_lowercase().as_str()) {
+                continue;
+            }
+            let Some(host) = normalize_host(host) else {
+                parsed.skipped += 1;
+                continue;
+            };
+
+            match index.get(&host) {
+                Some(&pos) => {
+                    let ips = &mut parsed.entries[pos].1;
+                    if !ips.contains(&ip) {
+                        ips.push(ip.clone());
+                    }
+                }
+                None => {
+                    index.insert(host.clone(), parsed.entries.len());
+                    parsed.entries.push((host, vec![ip.clone()]));
+                }
+            }
+        }
+    }
+
+    parsed
+}
+
+/// 校验 IPv4/IPv6 地址并统一格式，带 zone id 的地址 mihomo 不支持
+fn normalize_ip(raw: &str) -> Option<String> {
+    if raw.contains('%') {
+        return None;
+    }
+    IpAddr::from_str(raw).ok().map(|ip| ip.to_string().into())
+}
+
+/// 校验域名并转换为 mihomo hosts 支持的通配符格式
+/// - `*.example.com` 匹配单级子域名
+/// - `+.example.com` 匹配域名本身及所有子域名
+/// - `.example.com` (dnsmasq 风格) 转换为 `+.example.com`
+fn normalize_host(raw: &str) -> Option<String> {
+    let host = raw.trim_end_matches('.').to_ascii_lowercase();
+    if host.is_empty() || host.len() > 253 || IpAddr::from_str(&host).is_ok() {
+        return None;
+    }
+
+    let (prefix, domain) = if let Some(rest) = host.strip_prefix("*.") {
+        ("*.", rest)
+    } else if let Some(rest) = host.strip_prefix("+.") {
+        ("+.", rest)
+    } else if let Some(rest) = host.strip_prefix('.') {
+        ("+.", rest)
+    } else {
+        ("", host.as_str())
+    };
+
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if !domain.split('.').all(valid_label) {
+        return None;
+    }
+
+    Some(format!("{prefix}{domain}").into())
+}
+
+fn addresses_of(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.as_str().into()],
+        Value::Sequence(seq) => seq.iter().filter_map(Value::as_str).map(Into::into).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn addresses_to_value(addresses: Vec<String>) -> Value {
+    if addresses.len() == 1 {
+        Value::from(addresses[0].as_str())
+    } else {
+        Value::Sequence(addresses.iter().map(|s| Value::from(s.as_str())).collect())
+    }
+}
+
+/// 将解析结果合并进现有的 hosts 映射，同时返回本次新写入的地址
+pub fn merge_hosts(
+    existing: Option<&Mapping>,
+    parsed: ParsedHosts,
+    mode: HostsImportMode,
+) -> (Mapping, HostsImportSummary, HostsOrigin) {
+    let mut hosts = match (mode, existing) {
+        (HostsImportMode::Merge, Some(existing)) => existing.clone(),
+        _ => Mapping::new(),
+    };
+    let mut summary = HostsImportSummary {
+        parsed: parsed.entries.len(),
+        skipped: parsed.skipped,
+        ..HostsImportSummary::default()
+    };
+    let mut inserted = HostsOrigin::new();
+
+    for (host, ips) in parsed.entries {
+        let key = Value::from(host.as_str());
+        match hosts.get(&key) {
+            Some(current) => {
+                let mut addresses = addresses_of(current);
+                let new_ips: Vec<String> = ips.into_iter().filter(|ip| !addresses.contains(ip)).collect();
+                if !new_ips.is_empty() {
+                    summary.updated += 1;
+                    addresses.extend(new_ips.iter().cloned());
+                    hosts.insert(key, addresses_to_value(addresses));
+                    inserted.insert(host, new_ips);
+                }
+            }
+            None => {
+                summary.added += 1;
+                hosts.insert(key, addresses_to_value(ips.clone()));
+                inserted.insert(host, ips);
+            }
+        }
+    }
+
+    summary.total = hosts.len();
+    (hosts, summary, inserted)
+}
+
+/// Split the addresses a source wrote last time into those still listed upstream and those dropped since
+pub fn split_origin(previous: HostsOrigin, parsed: &ParsedHosts) -> (HostsOrigin, HostsOrigin) {
+    let upstream: HashMap<&str, &Vec<String>> = parsed.entries.iter().map(|(host, ips)| (host.as_str(), ips)).collect();
+
+    let mut kept = HostsOrigin::new();
+    let mut stale = HostsOrigin::new();
+    for (host, ips) in previous {
+        let listed = upstream.get(host.as_str());
+        let (still, dropped): (Vec<String>, Vec<String>) = ips
+            .into_iter()
+            .partition(|ip| listed.is_some_and(|listed| listed.contains(ip)));
+        if !still.is_empty() {
+            kept.insert(host.clone(), still);
+        }
+        if !dropped.is_empty() {
+            stale.insert(host, dropped);
+        }
+    }
+    (kept, stale)
+}
+
+/// 从 `origin` 中去掉 `other` 也写入过的地址
+pub fn exclude_origin(origin: &mut HostsOrigin, other: &HostsOrigin) {
+    origin.retain(|host, ips| {
+        if let Some(other_ips) = other.get(host) {
+            ips.retain(|ip| !other_ips.contains(ip));
+        }
+        !ips.is_empty()
+    });
+}
+
+/// 移除来源已不再提供的地址，地址被清空的域名一并删除，返回受影响的域名数量
+pub fn prune_hosts(hosts: &mut Mapping, stale: &HostsOrigin) -> usize {
+    let mut removed = 0;
+    for (host, ips) in stale {
+        let key = Value::from(host.as_str());
+        let Some(current) = hosts.get(&key) else {
+            continue;
+        };
+        let mut addresses = addresses_of(current);
+        let before = addresses.len();
+        addresses.retain(|ip| !ips.contains(ip));
+        if addresses.len() == before {
+            continue;
+        }
+
+        removed += 1;
+        if addresses.is_empty() {
+            hosts.remove(&key);
+        } else {
+            hosts.insert(key, addresses_to_value(addresses));
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_system_hosts_file() {
+        let content = "
+# comment line
+127.0.0.1   localhost
+::1         localhost ip6-localhost ip6-loopback
+192.168.1.10 nas.lan nas   # trailing comment
+192.168.1.11 NAS.lan.
+fe80::1%lo0 link-local.lan
+";
+        let parsed = parse_hosts(content);
+        assert_eq!(parsed.skipped, 1);
+        assert_eq!(
+            parsed.entries,
+            vec![
+                ("nas.lan".into(), vec!["192.168.1.10".into(), "192.168.1.11".into()]),
+                ("nas".into(), vec!["192.168.1.10".into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_adblock_list_with_duplicates() {
+        let content = "0.0.0.0 0.0.0.0\n0.0.0.0 ads.example.com\n0.0.0.0 ads.example.com\n0.0.0.0 -bad-.example.com\n";
+        let parsed = parse_hosts(content);
+        assert_eq!(parsed.entries, vec![("ads.example.com".into(), vec!["0.0.0.0".into()])]);
+        assert_eq!(parsed.skipped, 2);
+    }
+
+    #[test]
+    fn normalize_ipv6_and_wildcards() {
+        let parsed = parse_hosts("2001:DB8:0:0::1 *.cdn.example.com .example.org +.example.net a*.example.com");
+        assert_eq!(
+            parsed.entries,
+            vec![
+                ("*.cdn.example.com".into(), vec!["2001:db8::1".into()]),
+                ("+.example.org".into(), vec!["2001:db8::1".into()]),
+                ("+.example.net".into(), vec!["2001:db8::1".into()]),
+            ]
+        );
+        assert_eq!(parsed.skipped, 1);
+    }
+
+    #[test]
+    fn merge_and_replace_modes() {
+        let existing: Mapping = serde_yaml_ng::from_str("a.com: 1.1.1.1\nb.com: 2.2.2.2\n").unwrap_or_default();
+        let content = "1.1.1.1 a.com\n3.3.3.3 a.com c.com\n";
+
+        let (merged, summary, inserted) = merge_hosts(Some(&existing), parse_hosts(content), HostsImportMode::Merge);
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(summary.total, 3);
+        assert_eq!(
+            merged.get("a.com").map(addresses_of),
+            Some(vec!["1.1.1.1".into(), "3.3.3.3".into()])
+        );
+        // 已存在的地址不记为该来源写入
+        assert_eq!(
+            inserted,
+            HostsOrigin::from([
+                ("a.com".into(), vec!["3.3.3.3".into()]),
+                ("c.com".into(), vec!["3.3.3.3".into()]),
+            ])
+        );
+
+        let (replaced, summary, _) = merge_hosts(Some(&existing), parse_hosts(content), HostsImportMode::Replace);
+        assert_eq!(summary.total, 2);
+        assert!(replaced.get("b.com").is_none());
+    }
+
+    #[test]
+    fn prune_entries_dropped_upstream() {
+        let existing: Mapping =
+            serde_yaml_ng::from_str("a.com: [1.1.1.1, 3.3.3.3]\nb.com: 2.2.2.2\nc.com: 3.3.3.3\nd.com: 4.4.4.4\n")
+                .unwrap_or_default();
+        let previous = HostsOrigin::from([
+            ("a.com".into(), vec!["3.3.3.3".into()]),
+            ("c.com".into(), vec!["3.3.3.3".into()]),
+            ("d.com".into(), vec!["4.4.4.4".into()]),
+        ]);
+        let others = HostsOrigin::from([("d.com".into(), vec!["4.4.4.4".into()])]);
+
+        // 上游只剩 c.com
+        let parsed = parse_hosts("3.3.3.3 c.com\n");
+        let (kept, mut stale) = split_origin(previous, &parsed);
+        assert_eq!(kept, HostsOrigin::from([("c.com".into(), vec!["3.3.3.3".into()])]));
+        exclude_origin(&mut stale, &others);
+
+        let mut hosts = existing;
+        assert_eq!(prune_hosts(&mut hosts, &stale), 1);
+        assert_eq!(hosts.get("a.com").map(addresses_of), Some(vec!["1.1.1.1".into()]));
+        assert!(hosts.contains_key("b.com"));
+        assert!(hosts.contains_key("c.com"));
+        // 其他来源仍在使用的地址保留
+        assert!(hosts.contains_key("d.com"));
+
+        let (merged, summary, inserted) = merge_hosts(Some(&hosts), parsed, HostsImportMode::Merge);
+        assert_eq!(summary.total, 4);
+        assert!(inserted.is_empty());
+        assert_eq!(merged.len(), 4);
+    }
+}
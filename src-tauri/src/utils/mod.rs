@@ -2,12 +2,15 @@
 pub mod connections_stream;
 pub mod dirs;
 pub mod help;
+pub mod hosts;
 pub mod init;
 #[cfg(target_os = "linux")]
 pub mod linux;
 pub mod network;
 pub mod notification;
+pub mod quota;
 pub mod resolve;
+pub mod schedule;
 #[cfg(target_os = "windows")]
 pub mod schtasks;
 pub mod server;
@@ -1,9 +1,10 @@
 use crate::config::Config;
 use anyhow::Result;
 use base64::{Engine as _, engine::general_purpose};
+use network_interface::{NetworkInterface, NetworkInterfaceConfig as _};
 use reqwest::{
     Client, Proxy, StatusCode,
-    header::{HeaderMap, HeaderValue, USER_AGENT},
+    header::{AUTHORIZATION, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, USER_AGENT},
 };
 use smartstring::alias::String;
 use std::{sync::Arc, time::Duration};
@@ -35,13 +36,18 @@ impl HttpResponse {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum ProxyType {
     None,
     Localhost,
     System,
+    /// `http`, `https`, `socks5` or `socks5h` proxy url
+    Custom(String),
 }
 
+/// 自定义代理支持的协议，socks5h 由代理服务器解析域名
+const CUSTOM_PROXY_SCHEMES: [&str; 4] = ["http", "https", "socks5", "socks5h"];
+
 #[derive(Debug, Clone, Copy)]
 enum TlsRootMode {
     PlatformVerifier,
@@ -82,6 +88,13 @@ impl NetworkManager {
 
         // 设置代理
         if let Some(proxy_str) = proxy_url {
+            let scheme = Url::parse(&proxy_str)
+                .map_err(|e| anyhow::anyhow!("invalid proxy url: {e}"))?
+                .scheme()
+                .to_ascii_lowercase();
+            if !CUSTOM_PROXY_SCHEMES.contains(&scheme.as_str()) {
+                anyhow::bail!("unsupported proxy scheme \"{scheme}\"");
+            }
             let proxy = Proxy::all(proxy_str)?;
             builder = builder.proxy(proxy);
         } else {
@@ -147,17 +160,20 @@ impl NetworkManager {
         timeout_secs: Option<u64>,
         user_agent: Option<String>,
         accept_invalid_certs: bool,
+        headers: &HeaderMap,
     ) -> Result<Client> {
         self.create_request_with_tls_mode(
             proxy_type,
             timeout_secs,
             user_agent,
             accept_invalid_certs,
+            headers,
             TlsRootMode::PlatformVerifier,
         )
         .await
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn get_with_tls_mode(
         &self,
         url: &str,
@@ -165,12 +181,15 @@ impl NetworkManager {
         timeout_secs: Option<u64>,
         user_agent: Option<String>,
         accept_invalid_certs: bool,
+        headers: &HeaderMap,
         tls_root_mode: TlsRootMode,
     ) -> Result<HttpResponse> {
         let mut parsed = Url::parse(url)?;
-        let mut extra_headers = HeaderMap::new();
+        let mut extra_headers = headers.clone();
 
+        // 订阅设置中的认证信息优先于 url 中的用户名密码
         if !parsed.username().is_empty()
+            && !extra_headers.contains_key(AUTHORIZATION)
             && let Some(pass) = parsed.password()
         {
             let username = percent_encoding::percent_decode_str(parsed.username())
@@ -181,7 +200,9 @@ impl NetworkManager {
                 .into_owned();
             let auth_str = format!("{}:{}", username, password);
             let encoded = general_purpose::STANDARD.encode(auth_str);
-            extra_headers.insert("Authorization", HeaderValue::from_str(&format!("Basic {}", encoded))?);
+            let mut value = HeaderValue::from_str(&format!("Basic {}", encoded))?;
+            value.set_sensitive(true);
+            extra_headers.insert(AUTHORIZATION, value);
         }
 
         parsed.set_username("").ok();
@@ -194,17 +215,12 @@ impl NetworkManager {
                 timeout_secs,
                 user_agent,
                 accept_invalid_certs,
+                &extra_headers,
                 tls_root_mode,
             )
             .await?;
 
-        let mut request_builder = client.get(parsed);
-
-        for (key, value) in extra_headers.iter() {
-            request_builder = request_builder.header(key, value);
-        }
-
-        let response = match request_builder.send().await {
+        let response = match client.get(parsed).send().await {
             Ok(resp) => resp,
             Err(e) => {
                 return Err(anyhow::Error::new(e).context("Request failed"));
@@ -229,6 +245,7 @@ impl NetworkManager {
         timeout_secs: Option<u64>,
         user_agent: Option<String>,
         accept_invalid_certs: bool,
+        extra_headers: &HeaderMap,
         tls_root_mode: TlsRootMode,
     ) -> Result<Client> {
         let proxy_url: Option<std::string::String> = match proxy_type {
@@ -250,6 +267,7 @@ impl NetworkManager {
                     None
                 }
             }
+            ProxyType::Custom(url) => Some(url.trim().to_owned()),
         };
 
         let mut headers = HeaderMap::new();
@@ -264,6 +282,11 @@ impl NetworkManager {
             );
         }
 
+        // 自定义请求头可以覆盖默认的 User-Agent
+        for (name, value) in extra_headers {
+            headers.insert(name, value.clone());
+        }
+
         self.build_client(proxy_url, headers, accept_invalid_certs, timeout_secs, tls_root_mode)
     }
 
@@ -274,14 +297,36 @@ impl NetworkManager {
         timeout_secs: Option<u64>,
         user_agent: Option<String>,
         accept_invalid_certs: bool,
+    ) -> Result<HttpResponse> {
+        self.get_with_headers(
+            url,
+            proxy_type,
+            timeout_secs,
+            user_agent,
+            accept_invalid_certs,
+            &HeaderMap::new(),
+        )
+        .await
+    }
+
+    /// 与 `get_with_interrupt` 相同，附带额外的请求头（如条件请求）
+    pub async fn get_with_headers(
+        &self,
+        url: &str,
+        proxy_type: ProxyType,
+        timeout_secs: Option<u64>,
+        user_agent: Option<String>,
+        accept_invalid_certs: bool,
+        headers: &HeaderMap,
     ) -> Result<HttpResponse> {
         let platform_result = self
             .get_with_tls_mode(
                 url,
-                proxy_type,
+                proxy_type.clone(),
                 timeout_secs,
                 user_agent.clone(),
                 accept_invalid_certs,
+                headers,
                 TlsRootMode::PlatformVerifier,
             )
             .await;
@@ -295,6 +340,7 @@ impl NetworkManager {
                     timeout_secs,
                     user_agent,
                     accept_invalid_certs,
+                    headers,
                     TlsRootMode::StaticWebpkiRoots,
                 )
                 .await
@@ -307,3 +353,262 @@ impl NetworkManager {
         }
     }
 }
+
+/// 获取系统网络接口的详细信息，只保留系统列出的网卡
+pub fn network_interfaces() -> Result<Vec<NetworkInterface>> {
+    let names = tauri_plugin_clash_verge_sysinfo::list_network_interfaces();
+    let mut interfaces = NetworkInterface::show()?;
+    interfaces.retain(|interface| names.contains(&interface.name));
+    Ok(interfaces)
+}
+
+/// 条件请求头，订阅未变化时服务端返回 304
+pub fn conditional_headers(etag: Option<&str>, last_modified: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(value) = etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+        headers.insert(IF_NONE_MATCH, value);
+    }
+    if let Some(value) = last_modified.and_then(|date| HeaderValue::from_str(date).ok()) {
+        headers.insert(IF_MODIFIED_SINCE, value);
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{ETAG, LAST_MODIFIED};
+    use std::net::Ipv4Addr;
+    use tokio::{
+        io::{AsyncReadExt as _, AsyncWriteExt as _},
+        net::{TcpListener, TcpStream},
+    };
+
+    const ETAG_VALUE: &str = "\"v1\"";
+    const LAST_MODIFIED_VALUE: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+    /// 简易的 HTTP 服务器，`respond` 根据小写后的请求内容生成响应
+    async fn serve(listener: TcpListener, respond: fn(&str) -> std::string::String) {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = vec![0; 4096];
+            let Ok(n) = stream.read(&mut buf).await else {
+                continue;
+            };
+            let request = std::string::String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+            let _ = stream.write_all(respond(&request).as_bytes()).await;
+            let _ = stream.shutdown().await;
+        }
+    }
+
+    fn empty_response(status: &str) -> std::string::String {
+        format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+    }
+
+    /// 订阅服务器：请求头带有匹配的 If-None-Match 时返回 304
+    fn subscription_response(request: &str) -> std::string::String {
+        if request.contains(&format!("if-none-match: {}", ETAG_VALUE.to_ascii_lowercase())) {
+            return empty_response("304 Not Modified");
+        }
+        let body = "proxies: []\n";
+        format!(
+            "HTTP/1.1 200 OK\r\nETag: {ETAG_VALUE}\r\nLast-Modified: {LAST_MODIFIED_VALUE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    /// 私有订阅服务器：缺少 token 或设备 id 时返回 401
+    fn private_response(request: &str) -> std::string::String {
+        if request.contains("authorization: bearer secret") && request.contains("x-device-id: device-1") {
+            empty_response("200 OK")
+        } else {
+            empty_response("401 Unauthorized")
+        }
+    }
+
+    /// HTTP 代理替身：只接受 absolute-form 的订阅请求
+    fn http_proxy_response(request: &str) -> std::string::String {
+        if request.starts_with("get http://subscription.invalid/sub ") {
+            empty_response("200 OK")
+        } else {
+            empty_response("400 Bad Request")
+        }
+    }
+
+    /// SOCKS5 代理替身：完成无认证的 CONNECT 后，直接以 HTTP 响应返回收到的目标地址
+    async fn serve_socks5(listener: TcpListener) {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let _ = socks5_connect(&mut stream).await;
+        }
+    }
+
+    async fn socks5_connect(stream: &mut TcpStream) -> std::io::Result<()> {
+        let mut greeting = [0u8; 2];
+        stream.read_exact(&mut greeting).await?;
+        let mut methods = vec![0u8; usize::from(greeting[1])];
+        stream.read_exact(&mut methods).await?;
+        stream.write_all(&[5, 0]).await?;
+
+        // VER CMD RSV ATYP
+        let mut head = [0u8; 4];
+        stream.read_exact(&mut head).await?;
+        let host = match head[3] {
+            1 => {
+                let mut ip = [0u8; 4];
+                stream.read_exact(&mut ip).await?;
+                Ipv4Addr::from(ip).to_string()
+            }
+            3 => {
+                let mut len = [0u8; 1];
+                stream.read_exact(&mut len).await?;
+                let mut host = vec![0u8; usize::from(len[0])];
+                stream.read_exact(&mut host).await?;
+                std::string::String::from_utf8_lossy(&host).into_owned()
+            }
+            _ => return Ok(()),
+        };
+        let mut port = [0u8; 2];
+        stream.read_exact(&mut port).await?;
+        stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+
+        let mut buf = vec![0; 4096];
+        let _ = stream.read(&mut buf).await?;
+        let body = format!("{host}:{}", u16::from_be_bytes(port));
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    #[test]
+    fn conditional_headers_skip_missing_values() {
+        let headers = conditional_headers(Some(ETAG_VALUE), None);
+        assert_eq!(
+            headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()),
+            Some(ETAG_VALUE)
+        );
+        assert!(headers.get(IF_MODIFIED_SINCE).is_none());
+        assert!(conditional_headers(None, None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn not_modified_when_etag_matches() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/sub", listener.local_addr()?);
+        tokio::spawn(serve(listener, subscription_response));
+
+        let manager = NetworkManager::new();
+        let first = manager
+            .get_with_headers(&url, ProxyType::None, Some(5), None, false, &HeaderMap::new())
+            .await?;
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers().get(ETAG).and_then(|v| v.to_str().ok());
+        let last_modified = first.headers().get(LAST_MODIFIED).and_then(|v| v.to_str().ok());
+        assert_eq!(etag, Some(ETAG_VALUE));
+        assert_eq!(last_modified, Some(LAST_MODIFIED_VALUE));
+
+        let second = manager
+            .get_with_headers(
+                &url,
+                ProxyType::None,
+                Some(5),
+                None,
+                false,
+                &conditional_headers(etag, last_modified),
+            )
+            .await?;
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn send_custom_headers_and_auth() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(serve(listener, private_response));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        headers.insert("x-device-id", HeaderValue::from_static("device-1"));
+
+        let manager = NetworkManager::new();
+        // 显式设置的 Authorization 不会被 url 中的用户名密码覆盖
+        let authorized = manager
+            .get_with_headers(
+                &format!("http://user:pass@{addr}/sub"),
+                ProxyType::None,
+                Some(5),
+                None,
+                false,
+                &headers,
+            )
+            .await?;
+        assert_eq!(authorized.status(), StatusCode::OK);
+
+        let anonymous = manager
+            .get_with_headers(
+                &format!("http://{addr}/sub"),
+                ProxyType::None,
+                Some(5),
+                None,
+                false,
+                &HeaderMap::new(),
+            )
+            .await?;
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetch_through_custom_proxy() -> Result<()> {
+        let manager = NetworkManager::new();
+        let url = "http://subscription.invalid/sub";
+
+        let socks = TcpListener::bind("127.0.0.1:0").await?;
+        let socks_addr = socks.local_addr()?;
+        tokio::spawn(serve_socks5(socks));
+        // socks5h 由代理解析域名，本地无法解析的域名也能访问
+        let response = manager
+            .get_with_interrupt(
+                url,
+                ProxyType::Custom(format!("socks5h://{socks_addr}").into()),
+                Some(5),
+                None,
+                false,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text_with_charset()?, "subscription.invalid:80");
+
+        let http = TcpListener::bind("127.0.0.1:0").await?;
+        let http_addr = http.local_addr()?;
+        tokio::spawn(serve(http, http_proxy_response));
+        let response = manager
+            .get_with_interrupt(
+                url,
+                ProxyType::Custom(format!("http://{http_addr}").into()),
+                Some(5),
+                None,
+                false,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let unsupported = manager
+            .get_with_interrupt(
+                url,
+                ProxyType::Custom("ftp://127.0.0.1:21".into()),
+                Some(5),
+                None,
+                false,
+            )
+            .await;
+        assert!(
+            unsupported
+                .err()
+                .is_some_and(|err| err.to_string().contains("unsupported proxy scheme"))
+        );
+        Ok(())
+    }
+}
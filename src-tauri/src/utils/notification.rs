@@ -14,6 +14,14 @@ pub enum NotificationEvent<'a> {
     LightweightModeEntered,
     ProfilesReactivated,
     AppQuit,
+    SubscriptionQuota {
+        name: &'a str,
+        percent: u64,
+    },
+    SubscriptionExpiry {
+        name: &'a str,
+        days: i64,
+    },
     #[cfg(target_os = "macos")]
     AppHidden,
 }
@@ -73,6 +81,29 @@ pub async fn notify_event<'a>(event: NotificationEvent<'a>) {
             let body = clash_verge_i18n::t!("notifications.appQuit.body");
             notify(title, body);
         }
+        NotificationEvent::SubscriptionQuota { name, percent } => {
+            let title = clash_verge_i18n::t!("notifications.subscriptionQuota.title");
+            let body = if percent >= 100 {
+                clash_verge_i18n::t!("notifications.subscriptionQuota.exhausted")
+            } else {
+                clash_verge_i18n::t!("notifications.subscriptionQuota.body")
+            };
+            let body = body
+                .replace("{name}", name)
+                .replace("{percent}", &percent.to_string())
+                .into();
+            notify(title, body);
+        }
+        NotificationEvent::SubscriptionExpiry { name, days } => {
+            let title = clash_verge_i18n::t!("notifications.subscriptionExpiry.title");
+            let body = if days < 0 {
+                clash_verge_i18n::t!("notifications.subscriptionExpiry.expired")
+            } else {
+                clash_verge_i18n::t!("notifications.subscriptionExpiry.body")
+            };
+            let body = body.replace("{name}", name).replace("{days}", &days.to_string()).into();
+            notify(title, body);
+        }
         #[cfg(target_os = "macos")]
         NotificationEvent::AppHidden => {
             let title = clash_verge_i18n::t!("notifications.appHidden.title");
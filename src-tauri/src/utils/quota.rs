@@ -0,0 +1,229 @@
+use crate::config::{PrfExtra, PrfQuotaAlert};
+use serde::Serialize;
+use smartstring::alias::String;
+use std::cmp::Ordering;
+
+/// 默认在流量使用达到 80% 与 95% 时提醒
+pub const DEFAULT_QUOTA_THRESHOLDS: [u8; 2] = [80, 95];
+
+/// 默认在订阅到期前 7 天内提醒
+pub const DEFAULT_EXPIRY_DAYS: u32 = 7;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaLevel {
+    #[default]
+    Normal,
+    /// a usage threshold is reached or the subscription expires soon
+    Warning,
+    /// the traffic is used up or the subscription has expired
+    Depleted,
+}
+
+/// Quota and expiry status of a remote profile, built from `subscription-userinfo`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProfileQuotaStatus {
+    pub uid: String,
+    pub name: String,
+    pub level: QuotaLevel,
+
+    /// upload + download in bytes
+    pub used: u64,
+
+    /// 0 means unlimited
+    pub total: u64,
+
+    /// None when the traffic is unlimited
+    pub used_percent: Option<f64>,
+
+    /// the highest configured threshold that has been reached
+    pub threshold_reached: Option<u8>,
+
+    /// expire timestamp in seconds, None when it never expires
+    pub expire: Option<i64>,
+
+    /// whole days until expiry, negative once expired
+    pub days_left: Option<i64>,
+
+    /// expires within the configured number of days
+    pub expiring: bool,
+}
+
+impl ProfileQuotaStatus {
+    pub const fn is_exhausted(&self) -> bool {
+        self.total > 0 && self.used >= self.total
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.days_left.is_some_and(|days| days < 0)
+    }
+}
+
+/// 根据订阅信息与提醒阈值计算订阅的流量及到期状态
+pub fn evaluate(
+    uid: &str,
+    name: &str,
+    extra: &PrfExtra,
+    thresholds: &[u8],
+    expiry_days: u32,
+    now: i64,
+) -> ProfileQuotaStatus {
+    let used = extra.upload.saturating_add(extra.download);
+    let used_percent = (extra.total > 0).then(|| used as f64 * 100.0 / extra.total as f64);
+    let threshold_reached = used_percent.and_then(|percent| {
+        thresholds
+            .iter()
+            .copied()
+            .filter(|&threshold| threshold > 0 && percent >= f64::from(threshold))
+            .max()
+    });
+
+    let expire = (extra.expire > 0).then_some(extra.expire as i64);
+    let days_left = expire.map(|expire| (expire - now).div_euclid(DAY_SECS));
+    let expiring = days_left.is_some_and(|days| expiry_days > 0 && days < i64::from(expiry_days));
+
+    let mut status = ProfileQuotaStatus {
+        uid: uid.into(),
+        name: name.into(),
+        used,
+        total: extra.total,
+        used_percent,
+        threshold_reached,
+        expire,
+        days_left,
+        expiring,
+        ..ProfileQuotaStatus::default()
+    };
+    status.level = if status.is_exhausted() || status.is_expired() {
+        QuotaLevel::Depleted
+    } else if threshold_reached.is_some() || expiring {
+        QuotaLevel::Warning
+    } else {
+        QuotaLevel::Normal
+    };
+    status
+}
+
+/// 需要记录的提醒状态，恢复正常时为 `None`，之后再次达到阈值时重新提醒
+pub fn alert_state(status: &ProfileQuotaStatus) -> Option<PrfQuotaAlert> {
+    (status.level != QuotaLevel::Normal).then(|| PrfQuotaAlert {
+        threshold: status.threshold_reached.unwrap_or_default(),
+        exhausted: status.is_exhausted(),
+        expiring: status.expiring,
+        expired: status.is_expired(),
+    })
+}
+
+/// 是否出现了上次提醒之后新达到的状态，已提醒过的状态不再重复提醒
+pub fn should_alert(state: Option<&PrfQuotaAlert>, notified: Option<&PrfQuotaAlert>) -> bool {
+    let Some(state) = state else {
+        return false;
+    };
+    let notified = notified.copied().unwrap_or_default();
+    state.threshold > notified.threshold
+        || (state.exhausted && !notified.exhausted)
+        || (state.expiring && !notified.expiring)
+        || (state.expired && !notified.expired)
+}
+
+/// 按紧急程度排序：状态等级、剩余天数、已用比例
+pub fn sort_by_urgency(statuses: &mut [ProfileQuotaStatus]) {
+    statuses.sort_by(|a, b| {
+        b.level
+            .cmp(&a.level)
+            .then_with(|| match (a.days_left, b.days_left) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+            .then_with(|| {
+                b.used_percent
+                    .unwrap_or_default()
+                    .total_cmp(&a.used_percent.unwrap_or_default())
+            })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+    const NOW: i64 = 1_700_000_000;
+
+    fn extra(used: u64, total: u64, expire_in_days: i64) -> PrfExtra {
+        PrfExtra {
+            upload: 0,
+            download: used,
+            total,
+            expire: if expire_in_days == 0 {
+                0
+            } else {
+                (NOW + expire_in_days * DAY_SECS + 60) as u64
+            },
+        }
+    }
+
+    #[test]
+    fn evaluate_thresholds_and_expiry() {
+        let thresholds = DEFAULT_QUOTA_THRESHOLDS;
+
+        let normal = evaluate("a", "a", &extra(10 * GB, 100 * GB, 30), &thresholds, 7, NOW);
+        assert_eq!(normal.level, QuotaLevel::Normal);
+        assert_eq!(normal.threshold_reached, None);
+        assert_eq!(normal.days_left, Some(30));
+
+        let high = evaluate("b", "b", &extra(96 * GB, 100 * GB, 0), &thresholds, 7, NOW);
+        assert_eq!(high.level, QuotaLevel::Warning);
+        assert_eq!(high.threshold_reached, Some(95));
+        assert_eq!(high.expire, None);
+
+        let expiring = evaluate("c", "c", &extra(0, 0, 3), &thresholds, 7, NOW);
+        assert_eq!(expiring.level, QuotaLevel::Warning);
+        assert!(expiring.expiring && expiring.used_percent.is_none());
+
+        let expired = evaluate("d", "d", &extra(0, 100 * GB, -1), &thresholds, 7, NOW);
+        assert_eq!(expired.level, QuotaLevel::Depleted);
+
+        let exhausted = evaluate("e", "e", &extra(100 * GB, 100 * GB, 30), &[], 0, NOW);
+        assert_eq!(exhausted.level, QuotaLevel::Depleted);
+        assert_eq!(exhausted.threshold_reached, None);
+    }
+
+    #[test]
+    fn sort_most_urgent_first() {
+        let thresholds = DEFAULT_QUOTA_THRESHOLDS;
+        let mut statuses = vec![
+            evaluate("normal", "", &extra(GB, 100 * GB, 0), &thresholds, 7, NOW),
+            evaluate("quota", "", &extra(85 * GB, 100 * GB, 0), &thresholds, 7, NOW),
+            evaluate("expiring", "", &extra(GB, 100 * GB, 2), &thresholds, 7, NOW),
+            evaluate("expired", "", &extra(GB, 100 * GB, -3), &thresholds, 7, NOW),
+        ];
+        sort_by_urgency(&mut statuses);
+
+        let order: Vec<&str> = statuses.iter().map(|status| status.uid.as_str()).collect();
+        assert_eq!(order, vec!["expired", "expiring", "quota", "normal"]);
+    }
+
+    #[test]
+    fn alert_once_per_state() {
+        let thresholds = DEFAULT_QUOTA_THRESHOLDS;
+        let status = |used| evaluate("a", "a", &extra(used * GB, 100 * GB, 0), &thresholds, 7, NOW);
+
+        let first = alert_state(&status(85));
+        assert!(should_alert(first.as_ref(), None));
+        // 已提醒过的阈值不再提醒
+        assert!(!should_alert(alert_state(&status(90)).as_ref(), first.as_ref()));
+
+        let higher = alert_state(&status(96));
+        assert!(should_alert(higher.as_ref(), first.as_ref()));
+        assert!(!should_alert(alert_state(&status(85)).as_ref(), higher.as_ref()));
+
+        // 流量重置后清除记录，再次达到阈值时重新提醒
+        assert_eq!(alert_state(&status(10)), None);
+        assert!(should_alert(alert_state(&status(85)).as_ref(), None));
+    }
+}
@@ -14,7 +14,10 @@ use crate::{
         tray::Tray,
     },
     feat,
-    module::{auto_backup::AutoBackupManager, lightweight::auto_lightweight_boot},
+    module::{
+        auto_backup::AutoBackupManager, backup_directory_watcher::BackupDirectoryWatcher,
+        lightweight::auto_lightweight_boot, linked_profile::LinkedProfileWatcher,
+    },
     process::AsyncHandler,
     utils::{init, server, window_manager::WindowManager},
 };
@@ -62,6 +65,9 @@ pub fn resolve_setup_async() {
             init_system_proxy_guard().await;
         });
 
+        init_linked_profile_watcher();
+        init_backup_directory_watcher();
+
         let _ = futures::join!(
             core_init,
             init_tray(),
@@ -130,6 +136,14 @@ pub(super) async fn init_auto_backup() {
     logging_error!(Type::Setup, AutoBackupManager::global().init().await);
 }
 
+pub(super) fn init_linked_profile_watcher() {
+    LinkedProfileWatcher::global().init();
+}
+
+pub(super) fn init_backup_directory_watcher() {
+    BackupDirectoryWatcher::global().init();
+}
+
 async fn init_silent_updater() {
     use crate::core::SilentUpdater;
     use crate::core::handle::Handle;
@@ -142,7 +156,7 @@ async fn init_silent_updater() {
     // If install succeeds:
     //   - Windows: NSIS takes over and the process exits automatically
     //   - macOS/Linux: binary is replaced, we restart the app
-    if SilentUpdater::global().try_install_on_startup(app_handle).await {
+    if feat::install_cached_update(app_handle).await {
         logging!(info, Type::Setup, "Update installed at startup, restarting...");
         app_handle.restart();
     }
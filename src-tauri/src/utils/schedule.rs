@@ -0,0 +1,159 @@
+use anyhow::{Result, anyhow, bail};
+use chrono::{Local, TimeZone as _};
+use cron_clock::Schedule;
+use smartstring::alias::String;
+use std::str::FromStr as _;
+
+/// 未设置时，cron 定时更新的随机延迟上限（秒）
+pub const DEFAULT_CRON_JITTER_SECS: u64 = 300;
+
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Convert a standard crontab expression (`minute hour day month weekday`) to the
+/// six-field form with seconds used by the scheduler.
+///
+/// Numeric weekdays are rewritten as names, since the scheduler counts Sunday as 1
+/// while crontab counts it as 0 or 7. Validation and `next_run` use the same parser
+/// as the scheduler, so an accepted expression is always schedulable.
+pub fn normalize_cron(expr: &str) -> Result<String> {
+    let expr = expr.trim();
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let [minute, hour, day, month, weekday] = fields.as_slice() else {
+        bail!("cron expression \"{expr}\" must have 5 fields: minute hour day month weekday");
+    };
+
+    let weekday = normalize_weekday(weekday)?;
+    let normalized = format!("0 {minute} {hour} {day} {month} {weekday}");
+    Schedule::from_str(&normalized).map_err(|e| anyhow!("invalid cron expression \"{expr}\": {e}"))?;
+    Ok(normalized.into())
+}
+
+/// 将数字形式的星期字段展开后按连续区间重新输出为名称，
+/// 例如 `1-7` 中的 7 表示周日，需要拆分为 `SUN-SAT` 而不是 `MON-SUN`
+fn normalize_weekday(field: &str) -> Result<std::string::String> {
+    if field == "*" || field.chars().any(|c| c.is_ascii_alphabetic()) {
+        return Ok(field.to_owned());
+    }
+
+    let mut days = [false; 7];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| anyhow!("invalid weekday step \"{step}\""))?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            None if range == "*" => (0, 7),
+            Some((start, end)) => (parse_weekday(start)?, parse_weekday(end)?),
+            // `5/2` 与 crontab 一致，表示从周五开始到周末
+            None if step.is_some() => (parse_weekday(range)?, 7),
+            None => {
+                let day = parse_weekday(range)?;
+                (day, day)
+            }
+        };
+        if start > end {
+            bail!("invalid weekday range \"{range}\"");
+        }
+        for day in (start..=end).step_by(step.unwrap_or(1)) {
+            days[day % 7] = true;
+        }
+    }
+
+    let mut parts = Vec::new();
+    let mut day = 0;
+    while day < days.len() {
+        if !days[day] {
+            day += 1;
+            continue;
+        }
+        let start = day;
+        while day + 1 < days.len() && days[day + 1] {
+            day += 1;
+        }
+        parts.push(if start == day {
+            WEEKDAYS[start].to_owned()
+        } else {
+            format!("{}-{}", WEEKDAYS[start], WEEKDAYS[day])
+        });
+        day += 1;
+    }
+    Ok(parts.join(","))
+}
+
+fn parse_weekday(day: &str) -> Result<usize> {
+    day.parse::<usize>()
+        .ok()
+        .filter(|day| *day <= 7)
+        .ok_or_else(|| anyhow!("invalid weekday \"{day}\""))
+}
+
+/// 标准化后的表达式在 `after` 之后的下一次触发时间（秒）
+pub fn next_run(normalized: &str, after: i64) -> Option<i64> {
+    let schedule = Schedule::from_str(normalized).ok()?;
+    let after = Local.timestamp_opt(after, 0).single()?;
+    schedule.after(&after).next().map(|time| time.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(day: u32, hour: u32, minute: u32) -> i64 {
+        // 2024-06-03 is a Monday
+        Local
+            .with_ymd_and_hms(2024, 6, day, hour, minute, 0)
+            .single()
+            .map(|time| time.timestamp())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn normalize_crontab_expressions() {
+        assert_eq!(normalize_cron("0 4 * * *").ok(), Some("0 0 4 * * *".into()));
+        assert_eq!(normalize_cron(" 30 8 * * 1-5 ").ok(), Some("0 30 8 * * MON-FRI".into()));
+        assert_eq!(normalize_cron("0 */6 * * 0,6").ok(), Some("0 0 */6 * * SUN,SAT".into()));
+        assert!(normalize_cron("0 4 * *").is_err());
+        assert!(normalize_cron("0 25 * * *").is_err());
+        assert!(normalize_cron("0 4 * * 8").is_err());
+    }
+
+    #[test]
+    fn normalize_crontab_weekdays() {
+        assert_eq!(normalize_weekday("1-7").ok(), Some("SUN-SAT".to_owned()));
+        assert_eq!(normalize_weekday("5-7").ok(), Some("SUN,FRI-SAT".to_owned()));
+        assert_eq!(normalize_weekday("7").ok(), Some("SUN".to_owned()));
+        assert_eq!(normalize_weekday("*/2").ok(), Some("SUN,TUE,THU,SAT".to_owned()));
+        assert_eq!(normalize_weekday("1-5/2").ok(), Some("MON,WED,FRI".to_owned()));
+        assert_eq!(normalize_weekday("6,0").ok(), Some("SUN,SAT".to_owned()));
+        assert_eq!(normalize_weekday("MON-FRI").ok(), Some("MON-FRI".to_owned()));
+        assert!(normalize_weekday("5-1").is_err());
+        assert!(normalize_weekday("*/0").is_err());
+
+        // every expression accepted here must also be accepted by the scheduler
+        assert!(normalize_cron("0 9 * * 1-7").is_ok());
+        assert!(normalize_cron("0 9 * * 5-7").is_ok());
+    }
+
+    #[test]
+    fn next_run_at_wall_clock_time() {
+        let daily = normalize_cron("0 4 * * *").unwrap_or_default();
+        assert_eq!(next_run(&daily, local(3, 3, 0)), Some(local(3, 4, 0)));
+        assert_eq!(next_run(&daily, local(3, 4, 0)), Some(local(4, 4, 0)));
+
+        // Friday 09:00 -> Monday 08:30
+        let weekdays = normalize_cron("30 8 * * 1-5").unwrap_or_default();
+        assert_eq!(next_run(&weekdays, local(7, 9, 0)), Some(local(10, 8, 30)));
+
+        // Friday to Sunday, with Sunday written as 7: Monday 10:00 -> Friday 09:00 -> Sunday 09:00
+        let weekend = normalize_cron("0 9 * * 5-7").unwrap_or_default();
+        assert_eq!(next_run(&weekend, local(3, 10, 0)), Some(local(7, 9, 0)));
+        assert_eq!(next_run(&weekend, local(8, 10, 0)), Some(local(9, 9, 0)));
+    }
+}
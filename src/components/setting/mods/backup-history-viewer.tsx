@@ -97,9 +97,11 @@ export const BackupHistoryViewer = ({
   const isBusy = loading || isRestoring || isRestarting
 
   const buildRow = useCallback(
-    (item: ILocalBackupFile | IWebDavFile): BackupRow | null => {
+    (item: ILocalBackupFile | IRemoteBackupFile): BackupRow | null => {
       const { filename, last_modified } = item
-      if (!filename.toLowerCase().endsWith('.zip')) return null
+      const lowerName = filename.toLowerCase()
+      if (!lowerName.endsWith('.zip') && !lowerName.endsWith('.zip.enc'))
+        return null
 
       const platform =
         (filename.includes('-') && filename.split('-')[0]) ||
@@ -82,7 +82,7 @@ export function BackupViewer({ ref }: { ref?: Ref<DialogRef> }) {
   const handleImport = useLockFn(async () => {
     const selected = await openDialog({
       multiple: false,
-      filters: [{ name: 'Backup File', extensions: ['zip'] }],
+      filters: [{ name: 'Backup File', extensions: ['zip', 'enc'] }],
     })
     if (!selected || Array.isArray(selected)) return
     try {
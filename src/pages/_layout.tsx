@@ -37,6 +37,7 @@ import { LayoutTraffic } from '@/components/layout/layout-traffic'
 import { NoticeManager } from '@/components/layout/notice-manager'
 import { UpdateButton } from '@/components/layout/update-button'
 import { WindowControls } from '@/components/layout/window-controller'
+import { ConfirmViewer } from '@/components/profile/confirm-viewer'
 import { useI18n } from '@/hooks/use-i18n'
 import { useVerge } from '@/hooks/use-verge'
 import { useWindowDecorations } from '@/hooks/use-window'
@@ -49,7 +50,10 @@ import {
   useLoadingOverlay,
   useNavMenuOrder,
 } from './_layout/hooks'
-import { handleNoticeMessage } from './_layout/utils'
+import {
+  handleNoticeMessage,
+  restoreNewerDirectoryBackup,
+} from './_layout/utils'
 import { navItems } from './_routers'
 import LogsPage from './logs'
 
@@ -221,11 +225,19 @@ const Layout = () => {
 
   useLoadingOverlay(themeReady)
 
+  // 备份目录中出现其他设备的新备份时询问是否恢复
+  const [newerBackup, setNewerBackup] = useState<string | null>(null)
+
+  const handleConfirmNewerBackup = useCallback(() => {
+    if (newerBackup) void restoreNewerDirectoryBackup(newerBackup)
+    setNewerBackup(null)
+  }, [newerBackup])
+
   const handleNotice = useCallback(
     (payload: [string, string]) => {
       const [status, msg] = payload
       try {
-        handleNoticeMessage(status, msg, t, navigate)
+        handleNoticeMessage(status, msg, t, navigate, setNewerBackup)
       } catch (error) {
         console.error('[通知处理] 失败:', error)
       }
@@ -263,6 +275,13 @@ const Layout = () => {
     <ThemeProvider theme={theme}>
       {/* 左侧底部窗口控制按钮 */}
       <NoticeManager position={verge?.notice_position} />
+      <ConfirmViewer
+        open={newerBackup !== null}
+        title={t('settings.modals.backup.actions.restoreBackup')}
+        message={`${t('settings.modals.backup.messages.confirmRestoreNewer')} (${newerBackup ?? ''})`}
+        onClose={() => setNewerBackup(null)}
+        onConfirm={handleConfirmNewerBackup}
+      />
       <div
         style={{
           animation: 'fadeIn 0.5s',
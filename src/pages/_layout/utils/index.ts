@@ -1,2 +1,5 @@
 export { hideInitialOverlay } from './initial-loading-overlay'
-export { handleNoticeMessage } from './notification-handlers'
+export {
+  handleNoticeMessage,
+  restoreNewerDirectoryBackup,
+} from './notification-handlers'
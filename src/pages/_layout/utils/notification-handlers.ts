@@ -1,13 +1,26 @@
+import { restartApp, restoreDirectoryBackup } from '@/services/cmds'
 import { showNotice } from '@/services/notice-service'
 
 type NavigateFunction = (path: string, options?: any) => void
 type TranslateFunction = (key: string) => string
 
+// 确认恢复备份目录中其他设备的新备份，成功后重启应用
+export const restoreNewerDirectoryBackup = async (filename: string) => {
+  try {
+    await restoreDirectoryBackup(filename)
+    showNotice.success('settings.modals.backup.messages.restoreSuccess')
+    window.setTimeout(() => void restartApp().catch(showNotice.error), 1000)
+  } catch (error) {
+    showNotice.error(error)
+  }
+}
+
 export const handleNoticeMessage = (
   status: string,
   msg: string,
   t: TranslateFunction,
   navigate: NavigateFunction,
+  onDirectoryNewer: (filename: string) => void,
 ) => {
   const handlers: Record<string, () => void> = {
     'import_sub_url::ok': () => {
@@ -36,6 +49,12 @@ export const handleNoticeMessage = (
       ),
     'reactivate_profiles::error': () => showNotice.error(msg),
     update_failed: () => showNotice.error(msg),
+    'update_selected::not_restored': () => showNotice.info(msg),
+    'linked_profile::reloaded': () => showNotice.info(msg),
+    'linked_profile::invalid': () => showNotice.error(msg),
+    'backup::restore_warning': () => showNotice.info(msg),
+    'backup::directory_newer': () => onDirectoryNewer(msg),
+    'update_changes::nodes_removed': () => showNotice.info(msg),
     'config_validate::boot_error': () =>
       showNotice.error('shared.feedback.validation.config.bootFailed', msg),
     'config_validate::core_change': () =>
@@ -74,6 +74,26 @@ export async function deleteProfile(index: string) {
   return invoke<void>('delete_profile', { index })
 }
 
+export async function syncLinkedProfile(index: string) {
+  return invoke<boolean>('sync_linked_profile', { index })
+}
+
+export async function deleteProfiles(uids: string[]) {
+  return invoke<void>('delete_profiles', { uids })
+}
+
+export async function filterProfiles(tags?: string[], folder?: string) {
+  return invoke<string[]>('filter_profiles', { tags, folder })
+}
+
+export async function tagProfiles(
+  uids: string[],
+  add?: string[],
+  remove?: string[],
+) {
+  return invoke<void>('tag_profiles', { uids, add, remove })
+}
+
 export async function patchProfile(
   index: string,
   profile: Partial<IProfileItem>,
@@ -81,6 +101,44 @@ export async function patchProfile(
   return invoke<void>('patch_profile', { index, profile })
 }
 
+export async function updateProfiles(uids?: string[], concurrency?: number) {
+  return invoke<IProfileUpdateReport[]>('update_profiles', {
+    uids,
+    concurrency,
+  })
+}
+
+export async function updateProfilesByTag(tag: string, concurrency?: number) {
+  return invoke<IProfileUpdateReport[]>('update_profiles_by_tag', {
+    tag,
+    concurrency,
+  })
+}
+
+export async function getProfileUpdateFailures() {
+  return invoke<IProfileUpdateFailure[]>('get_profile_update_failures')
+}
+
+export async function getProfileVersions(index: string) {
+  return invoke<IProfileVersion[]>('get_profile_versions', { index })
+}
+
+export async function diffProfileVersions(
+  index: string,
+  from: string,
+  to?: string,
+) {
+  return invoke<string>('diff_profile_versions', { index, from, to })
+}
+
+export async function rollbackProfile(index: string, version: string) {
+  return invoke<void>('rollback_profile', { index, version })
+}
+
+export async function getProfilesQuotaStatus() {
+  return invoke<IProfileQuotaStatus[]>('get_profiles_quota_status')
+}
+
 export async function getClashInfo() {
   return invoke<IClashInfo | null>('get_clash_info')
 }
@@ -126,6 +184,19 @@ export async function syncTrayProxySelection() {
   return invoke<void>('sync_tray_proxy_selection')
 }
 
+let fetchNodePrefix: Promise<string> | undefined
+
+// 前缀由后端提供，避免与注入的节点名不一致
+export async function getFetchNodePrefix() {
+  fetchNodePrefix ??= invoke<string>('get_fetch_node_prefix').catch(
+    (error) => {
+      fetchNodePrefix = undefined
+      throw error
+    },
+  )
+  return fetchNodePrefix
+}
+
 export async function calcuProxies(): Promise<{
   global: IProxyGroupItem
   direct: IProxyItem
@@ -133,9 +204,10 @@ export async function calcuProxies(): Promise<{
   records: Record<string, IProxyItem>
   proxies: IProxyItem[]
 }> {
-  const [proxyResponse, providerResponse] = await Promise.all([
+  const [proxyResponse, providerResponse, hiddenPrefix] = await Promise.all([
     getProxies(),
     calcuProxyProviders(),
+    getFetchNodePrefix(),
   ])
 
   const proxyRecord = proxyResponse.proxies
@@ -164,6 +236,9 @@ export async function calcuProxies(): Promise<{
     }
   }
 
+  // proxies injected for fetching subscriptions through a node stay internal
+  const isVisible = (name: string) => !name.startsWith(hiddenPrefix)
+
   const { GLOBAL: global, DIRECT: direct, REJECT: reject } = proxyRecord
 
   let groups: IProxyGroupItem[] = Object.values(proxyRecord).reduce<
@@ -172,7 +247,7 @@ export async function calcuProxies(): Promise<{
     if (each?.name !== 'GLOBAL' && each?.all) {
       acc.push({
         ...each,
-        all: each.all!.map((item) => generateItem(item)),
+        all: each.all!.filter(isVisible).map((item) => generateItem(item)),
       })
     }
 
@@ -186,7 +261,9 @@ export async function calcuProxies(): Promise<{
       if (proxyRecord[name]?.all) {
         acc.push({
           ...proxyRecord[name],
-          all: proxyRecord[name].all!.map((item) => generateItem(item)),
+          all: proxyRecord[name]
+            .all!.filter(isVisible)
+            .map((item) => generateItem(item)),
         })
       }
       return acc
@@ -202,13 +279,18 @@ export async function calcuProxies(): Promise<{
 
   const proxies = [direct, reject].concat(
     Object.values(proxyRecord).filter(
-      (p) => !p?.all?.length && p?.name !== 'DIRECT' && p?.name !== 'REJECT',
+      (p) =>
+        !p?.all?.length &&
+        p?.name !== 'DIRECT' &&
+        p?.name !== 'REJECT' &&
+        isVisible(p?.name ?? ''),
     ),
   )
 
   const _global = {
     ...global,
-    all: global?.all?.map((item) => generateItem(item)) || [],
+    all:
+      global?.all?.filter(isVisible).map((item) => generateItem(item)) || [],
   }
 
   return {
@@ -232,6 +314,26 @@ export async function calcuProxyProviders() {
   )
 }
 
+export async function importHostsFile(path: string, mode?: HostsImportMode) {
+  return invoke<IHostsImportSummary>('import_hosts_file', { path, mode })
+}
+
+export async function importHostsUrl(
+  url: string,
+  mode?: HostsImportMode,
+  updateInterval?: number,
+) {
+  return invoke<IHostsImportSummary>('import_hosts_url', {
+    url,
+    mode,
+    updateInterval,
+  })
+}
+
+export async function removeHostsSource(uid: string) {
+  return invoke<void>('remove_hosts_source', { uid })
+}
+
 export async function getClashLogs() {
   const regex = /time="(.+?)"\s+level=(.+?)\s+msg="(.+?)"/
   const newRegex = /(.+?)\s+(.+?)\s+(.+)/
@@ -459,12 +561,60 @@ export async function deleteLocalBackup(filename: string) {
   return invoke<void>('delete_local_backup', { filename })
 }
 
-export async function restoreWebDavBackup(filename: string) {
-  return invoke<void>('restore_webdav_backup', { filename })
+export async function restoreWebDavBackup(
+  filename: string,
+  passphrase?: string,
+) {
+  return invoke<void>('restore_webdav_backup', { filename, passphrase })
+}
+
+export async function restoreLocalBackup(
+  filename: string,
+  passphrase?: string,
+) {
+  return invoke<void>('restore_local_backup', { filename, passphrase })
+}
+
+export async function getBackupRestorePlan(
+  source: BackupSource,
+  filename: string,
+  passphrase?: string,
+) {
+  return invoke<IRestorePlan>('get_backup_restore_plan', {
+    source,
+    filename,
+    passphrase,
+  })
+}
+
+export async function previewBackup(
+  source: BackupSource,
+  filename: string,
+  passphrase?: string,
+) {
+  return invoke<IBackupPreview>('preview_backup', {
+    source,
+    filename,
+    passphrase,
+  })
 }
 
-export async function restoreLocalBackup(filename: string) {
-  return invoke<void>('restore_local_backup', { filename })
+export async function restoreBackupComponents(
+  source: BackupSource,
+  filename: string,
+  components: IRestoreComponentKind[],
+  passphrase?: string,
+) {
+  return invoke<void>('restore_backup_components', {
+    source,
+    filename,
+    components,
+    passphrase,
+  })
+}
+
+export async function pruneBackups(dryRun: boolean) {
+  return invoke<IBackupPruneReport>('prune_backups', { dryRun })
 }
 
 export async function importLocalBackup(source: string) {
@@ -475,6 +625,28 @@ export async function exportLocalBackup(filename: string, destination: string) {
   return invoke<void>('export_local_backup', { filename, destination })
 }
 
+export async function listIncrementalBackup() {
+  return invoke<ILocalBackupFile[]>('list_incremental_backup')
+}
+
+export async function deleteIncrementalBackup(filename: string) {
+  return invoke<void>('delete_incremental_backup', { filename })
+}
+
+export async function restoreIncrementalBackup(
+  filename: string,
+  passphrase?: string,
+) {
+  return invoke<void>('restore_incremental_backup', { filename, passphrase })
+}
+
+export async function exportIncrementalBackup(
+  filename: string,
+  destination: string,
+) {
+  return invoke<void>('export_incremental_backup', { filename, destination })
+}
+
 export async function saveWebdavConfig(
   url: string,
   username: string,
@@ -488,11 +660,64 @@ export async function saveWebdavConfig(
 }
 
 export async function listWebDavBackup() {
-  const list: IWebDavFile[] = await invoke<IWebDavFile[]>('list_webdav_backup')
-  list.map((item) => {
-    item.filename = item.href.split('/').pop() as string
+  return invoke<IRemoteBackupFile[]>('list_webdav_backup')
+}
+
+export async function saveS3Config(
+  endpoint: string,
+  bucket: string,
+  prefix: string,
+  region: string,
+  accessKeyId: string,
+  secretAccessKey: string,
+) {
+  return invoke<void>('save_s3_config', {
+    endpoint,
+    bucket,
+    prefix,
+    region,
+    accessKeyId,
+    secretAccessKey,
   })
-  return list
+}
+
+export async function createS3Backup() {
+  return invoke<void>('create_s3_backup')
+}
+
+export async function listS3Backup() {
+  return invoke<IRemoteBackupFile[]>('list_s3_backup')
+}
+
+export async function deleteS3Backup(filename: string) {
+  return invoke<void>('delete_s3_backup', { filename })
+}
+
+export async function restoreS3Backup(filename: string, passphrase?: string) {
+  return invoke<void>('restore_s3_backup', { filename, passphrase })
+}
+
+export async function saveBackupDirectory(path: string) {
+  return invoke<void>('save_backup_directory', { path })
+}
+
+export async function createDirectoryBackup() {
+  return invoke<void>('create_directory_backup')
+}
+
+export async function listDirectoryBackup() {
+  return invoke<IRemoteBackupFile[]>('list_directory_backup')
+}
+
+export async function deleteDirectoryBackup(filename: string) {
+  return invoke<void>('delete_directory_backup', { filename })
+}
+
+export async function restoreDirectoryBackup(
+  filename: string,
+  passphrase?: string,
+) {
+  return invoke<void>('restore_directory_backup', { filename, passphrase })
 }
 
 export async function listLocalBackup() {
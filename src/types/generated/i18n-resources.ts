@@ -756,6 +756,7 @@ export interface TranslationResources {
             backupFailed: string
             confirmDelete: string
             confirmRestore: string
+            confirmRestoreNewer: string
             invalidWebdavUrl: string
             localBackupCreated: string
             localBackupExported: string
@@ -271,6 +271,13 @@ interface IProfileItem {
   desc?: string
   file?: string
   url?: string
+  tags?: string[]
+  folder?: string
+  linked_path?: string
+  mirrors?: string[]
+  active_mirror?: string
+  update_failures?: number
+  update_error?: string
   updated?: number
   selected?: {
     name?: string
@@ -286,13 +293,59 @@ interface IProfileItem {
   home?: string
 }
 
+interface IProfileVersion {
+  id: string
+  time: number
+  status: 'direct' | 'clash_proxy' | 'system_proxy' | 'previous'
+  extra?: IProfileItem['extra']
+  size: number
+}
+
+interface IProfileUpdateReport {
+  uid: string
+  name: string
+  status: 'updated' | 'not_modified' | 'skipped' | 'failed'
+  route?: 'direct' | 'clash_proxy' | 'system_proxy'
+  mirror?: string
+  error?: string
+  is_current: boolean
+}
+
+interface IProfileUpdateFailure {
+  uid: string
+  failures: number
+  last_error?: string
+  next_retry?: number
+}
+
+interface IProfileQuotaStatus {
+  uid: string
+  name: string
+  level: 'normal' | 'warning' | 'depleted'
+  used: number
+  total: number
+  used_percent?: number | null
+  threshold_reached?: number | null
+  expire?: number | null
+  days_left?: number | null
+  expiring: boolean
+}
+
 interface IProfileOption {
   user_agent?: string
   with_proxy?: boolean
   self_proxy?: boolean
   update_interval?: number
+  update_cron?: string
+  update_jitter?: number
   timeout_seconds?: number
   danger_accept_invalid_certs?: boolean
+  headers?: Record<string, string>
+  auth?:
+    | { type: 'bearer'; token: string }
+    | { type: 'basic'; username: string; password: string }
+  fetch_proxy?: string
+  fetch_node?: { profile: string; name: string }
   allow_auto_update?: boolean
   merge?: string
   script?: string
@@ -929,6 +982,10 @@ interface IVergeConfig {
   enable_system_proxy?: boolean
   enable_global_hotkey?: boolean
   enable_dns_settings?: boolean
+  profile_history_limit?: number
+  subscription_quota_thresholds?: number[]
+  subscription_expiry_days?: number
+  hosts_sources?: IVergeHostsSource[]
   proxy_auto_config?: boolean
   pac_file_content?: string
   proxy_host?: string
@@ -975,24 +1032,53 @@ interface IVergeConfig {
   enable_auto_backup_schedule?: boolean
   auto_backup_interval_hours?: number
   auto_backup_on_change?: boolean
+  auto_backup_triggers?: IVergeAutoBackupTriggers
+  auto_backup_webdav?: boolean
+  auto_backup_incremental?: boolean
+  backup_retention?: IVergeBackupRetention
+  backup_directory?: string
+  backup_directory_watch?: boolean
   proxy_layout_column?: number
   test_list?: IVergeTestItem[]
   webdav_url?: string
   webdav_username?: string
   webdav_password?: string
+  backup_passphrase?: string
+  s3_endpoint?: string
+  s3_bucket?: string
+  s3_prefix?: string
+  s3_region?: string
+  s3_access_key_id?: string
+  s3_secret_access_key?: string
   home_cards?: Record<string, boolean>
   enable_hover_jump_navigator?: boolean
   hover_jump_navigator_delay?: number
   enable_external_controller?: boolean
 }
 
-interface IWebDavFile {
+type HostsImportMode = 'merge' | 'replace'
+
+interface IVergeHostsSource {
+  uid?: string
+  url?: string
+  mode?: HostsImportMode
+  update_interval?: number
+  updated?: number
+}
+
+interface IHostsImportSummary {
+  parsed: number
+  added: number
+  updated: number
+  removed: number
+  skipped: number
+  total: number
+}
+
+interface IRemoteBackupFile {
   filename: string
-  href: string
   last_modified: string
   content_length: number
-  content_type: string
-  tag: string
 }
 
 interface ILocalBackupFile {
@@ -1002,6 +1088,107 @@ interface ILocalBackupFile {
   content_length: number
 }
 
+type BackupSource = 'local' | 'webdav' | 's3' | 'directory' | 'incremental'
+
+type IRestoreComponentKind =
+  | { kind: 'profile'; uid: string }
+  | { kind: 'global_merge' }
+  | { kind: 'global_script' }
+  | { kind: 'clash_config' }
+  | { kind: 'verge_settings' }
+  | { kind: 'dns_config' }
+
+interface IRestoreComponent {
+  component: IRestoreComponentKind
+  name: string
+  itype?: string
+  files: string[]
+  exists: boolean
+}
+
+interface IBackupManifest {
+  app_version: string
+  os: string
+  created_at: number
+  verge_schema: number
+  profiles_schema: number
+  entries: { name: string; size: number; sha256: string }[]
+}
+
+interface IBackupManifestCheck {
+  manifest?: IBackupManifest
+  warnings: string[]
+}
+
+interface IRestorePlan {
+  filename: string
+  components: IRestoreComponent[]
+  check: IBackupManifestCheck
+}
+
+interface IBackupProfilePreview {
+  uid: string
+  name?: string
+  itype?: string
+  url?: string
+  current: boolean
+}
+
+interface IBackupValueChange {
+  key: string
+  backup?: unknown
+  current?: unknown
+}
+
+interface IBackupProfileChange {
+  uid: string
+  name?: string
+  fields: ('name' | 'type' | 'url' | 'content')[]
+}
+
+interface IBackupDiff {
+  profiles: {
+    added: IBackupProfilePreview[]
+    removed: IBackupProfilePreview[]
+    changed: IBackupProfileChange[]
+    current?: IBackupValueChange
+  }
+  verge: IBackupValueChange[]
+  clash: IBackupValueChange[]
+}
+
+interface IBackupPreview {
+  filename: string
+  check: IBackupManifestCheck
+  profiles: IBackupProfilePreview[]
+  verge?: Record<string, unknown>
+  clash?: Record<string, unknown>
+  diff: IBackupDiff
+}
+
+interface IVergeAutoBackupTriggers {
+  global_merge?: boolean
+  global_script?: boolean
+  profile_enhancement?: boolean
+  profile_delete?: boolean
+  core_change?: boolean
+  app_update?: boolean
+}
+
+interface IVergeBackupRetention {
+  keep_last?: number
+  keep_daily?: number
+  keep_weekly?: number
+}
+
+interface IBackupPruneReport {
+  local: string[]
+  webdav: string[]
+  s3: string[]
+  directory: string[]
+  incremental: string[]
+}
+
 interface IWebDavConfig {
   url: string
   username: string